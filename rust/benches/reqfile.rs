@@ -0,0 +1,101 @@
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use deepwoken::{
+    Stat,
+    model::{
+        req::{MinCostCache, Requirement},
+        reqfile::Reqfile,
+    },
+    util::statmap::StatMap,
+};
+
+const REQFILE: &str = r"
+Free:
+base := 25 STR
+base => heavy := 40 STR, 30 FTD
+35cha OR 35wll OR 35int
+lht + med + hvy = 60
+40 ice
+50 ice, 50 ltn
+
+Post:
+75r hvy
+20r ftd, 20r flm, 20r ltn
+80r flm
+";
+
+fn candidates() -> Vec<StatMap> {
+    (0..64)
+        .map(|i| {
+            let mut map = StatMap::new();
+            for (n, stat) in [
+                Stat::Strength,
+                Stat::Fortitude,
+                Stat::Charisma,
+                Stat::HeavyWeapon,
+                Stat::Frostdraw,
+                Stat::Thundercall,
+                Stat::Flamecharm,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                map.insert(stat, (i * 7 + n as i64 * 13) % 100);
+            }
+            map
+        })
+        .collect()
+}
+
+fn satisfaction(c: &mut Criterion) {
+    let rf = Reqfile::parse_str(REQFILE).unwrap();
+    let maps = candidates();
+
+    c.bench_function("reqfile satisfied_by", |b| {
+        b.iter(|| {
+            maps.iter()
+                .filter(|m| rf.req_iter().all(|r| r.satisfied_by(black_box(m))))
+                .count()
+        });
+    });
+
+    let compiled = rf.compile();
+    c.bench_function("reqfile compiled", |b| {
+        b.iter(|| maps.iter().filter(|m| compiled(black_box(m))).count());
+    });
+}
+
+fn min_cost(c: &mut Criterion) {
+    let rf = Reqfile::parse_str(REQFILE).unwrap();
+    // the cache is keyed by name, so give the anonymous ones one
+    let reqs: Vec<Requirement> = rf
+        .req_iter()
+        .enumerate()
+        .map(|(i, req)| {
+            let mut req = req.clone();
+            req.name.get_or_insert_with(|| format!("req_{i}"));
+            req
+        })
+        .collect();
+
+    c.bench_function("requirement min_cost", |b| {
+        b.iter(|| reqs.iter().map(|r| black_box(r).min_cost()).sum::<i64>());
+    });
+
+    let mut cache = MinCostCache::new();
+    c.bench_function("requirement min_cost cached", |b| {
+        b.iter(|| reqs.iter().map(|r| cache.min_cost(black_box(r))).sum::<i64>());
+    });
+}
+
+fn solve(c: &mut Criterion) {
+    let rf = Reqfile::parse_str(REQFILE).unwrap();
+
+    c.bench_function("reqfile solve", |b| {
+        b.iter(|| black_box(&rf).solve());
+    });
+}
+
+criterion_group!(benches, satisfaction, min_cost, solve);
+criterion_main!(benches);
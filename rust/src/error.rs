@@ -2,11 +2,35 @@ use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum DeepError {
-    #[error("Parse error: {0}")]
-    Req(String),
+    /// A requirement that didn't parse. `offset` is the byte offset into the input where it
+    /// failed, and `snippet` the input around that point.
+    #[error("Parse error at byte {offset}: {message} (near '{snippet}')")]
+    Req {
+        message: String,
+        offset: usize,
+        snippet: String,
+    },
 
-    #[error("Parse on line {line}: {message}")]
-    Reqfile { line: usize, message: String },
+    /// `column` is 1-based and counted in chars, set when the failure could be pinned down
+    /// within the line.
+    #[error(
+        "Parse on line {line}{}: {message}",
+        .column.map(|c| format!(", column {c}")).unwrap_or_default()
+    )]
+    Reqfile {
+        line: usize,
+        column: Option<usize>,
+        message: String,
+    },
+
+    /// Every line that failed in a [`Requirement::parse_many`](crate::model::req::Requirement::parse_many),
+    /// each as a [`DeepError::Reqfile`]
+    #[error(
+        "Failed to parse {} requirement(s): {}",
+        .0.len(),
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    Reqs(Vec<DeepError>),
 
     #[error("IO error: {0}")]
     IO(String),
@@ -16,16 +40,44 @@ pub enum DeepError {
     #[error("Build reqfile error: {0}")]
     ReqfileBuild(String),
 
+    /// A programmatically built [`Clause`](crate::model::req::Clause) breaks an invariant,
+    /// see [`Clause::try_finish`](crate::model::req::Clause::try_finish)
+    #[error("Invalid clause: {0}")]
+    Clause(String),
+
+    /// A stat map that can't be had in game, see
+    /// [`StatMap::validate`](crate::util::statmap::StatMap::validate)
+    #[error(
+        "Invalid stats: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    )]
+    InvalidStats(Vec<crate::util::statmap::StatViolation>),
+
+    /// A stat name that isn't a stat's name, short name or alias
+    #[error("Unknown stat: {0}")]
+    UnknownStat(String),
+
+    /// See [`Requirement::to_dnf`](crate::model::req::Requirement::to_dnf)
+    #[error("DNF expansion error: {0}")]
+    Dnf(String),
+
     #[error("Stat formula error: {0}")]
     Formula(String),
 
+    /// The request didn't go through (connection, timeout, server error). Worth retrying.
     #[cfg(feature = "fetch")]
-    #[error("Reqwest error: {0}")]
-    ReqwestError(#[from] reqwest::Error),
+    #[error("Network error: {0}")]
+    Network(String),
 
+    /// The release or asset doesn't exist, retrying won't help.
     #[cfg(feature = "fetch")]
-    #[error("Fetch data error: {0}")]
-    FetchError(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// The response arrived but isn't valid data.
+    #[cfg(feature = "fetch")]
+    #[error("Failed to decode fetched data: {0}")]
+    DecodeData(String),
 }
 
 pub type Result<T> = core::result::Result<T, DeepError>;
@@ -35,3 +87,16 @@ impl From<std::io::Error> for DeepError {
         Self::IO(value.to_string())
     }
 }
+
+#[cfg(feature = "fetch")]
+impl From<reqwest::Error> for DeepError {
+    fn from(value: reqwest::Error) -> Self {
+        if value.status() == Some(reqwest::StatusCode::NOT_FOUND) {
+            Self::NotFound(value.to_string())
+        } else if value.is_decode() {
+            Self::DecodeData(value.to_string())
+        } else {
+            Self::Network(value.to_string())
+        }
+    }
+}
@@ -349,6 +349,13 @@ pub fn worst_requirement_ratio(build: &BuildParams, talents: &[String], reqs: &R
         let clause_ratio = match clause.clause_type {
             ClauseType::Or => clause.atoms().iter().map(ratio).fold(0.0_f64, f64::max),
             ClauseType::And => clause.atoms().iter().map(ratio).fold(1.0_f64, f64::min),
+            ClauseType::Not => {
+                if clause.satisfied_by(&build.stats) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
         };
         worst = worst.min(clause_ratio);
     }
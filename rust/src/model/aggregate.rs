@@ -7,7 +7,7 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use crate::formulas::CombatState;
-use crate::util::statmap::StatMap;
+use crate::util::statmap::{StatMap, deserialize_nonzero, serialize_nonzero};
 
 /// Where a contribution came from.
 ///
@@ -288,6 +288,10 @@ pub struct Scenario {
 #[serde(default)]
 /// All the information needed to derive total stats of the build
 pub struct BuildParams {
+    #[serde(
+        serialize_with = "serialize_nonzero",
+        deserialize_with = "deserialize_nonzero"
+    )]
     pub stats: StatMap,
     pub race: String,
     pub talents: Vec<String>,
@@ -1,6 +1,6 @@
 // Types that wrap the structures found in pocamind/data
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 
@@ -8,9 +8,12 @@ use crate::Stat;
 use crate::error::{DeepError, Result};
 use crate::model::enums::{EquipmentSlot, ItemRarity, MantraType, RangeType, TalentRarity, WeaponType};
 use crate::model::formula::{StatContributions, StatFormula};
+#[cfg(feature = "intern")]
+use crate::model::intern::{InternedRequirement, Interner};
 use crate::model::req::{PrereqGroup, Requirement};
 use crate::util::graph::PrereqGraph;
-use crate::util::name_to_identifier;
+use crate::util::statmap::StatMap;
+use crate::util::{edit_distance, name_to_identifier};
 
 fn build_requirement(
     namespace: &str,
@@ -25,6 +28,84 @@ fn build_requirement(
     req
 }
 
+/// One `search_*` method per kind of item, over its map in [`DeepData`]
+macro_rules! search_methods {
+    ($( $method:ident => $field:ident: $ty:ty ),+ $(,)?) => {
+        $(
+            #[doc = concat!(
+                "The ", stringify!($field), " ranked by how closely their name matches `query`, ",
+                "best first, for typo tolerant lookups. Scores run from 0.0 to 1.0 (an exact ",
+                "match), ties are broken alphabetically."
+            )]
+            #[must_use]
+            pub fn $method(&self, query: &str) -> Vec<(&$ty, f64)> {
+                search(&self.$field, query)
+            }
+        )+
+    };
+}
+
+/// A `*_with_reqs` and `reqless_*` pair per kind of item with requirements, splitting the
+/// items returned by the kind's iterator on whether their requirement is empty
+macro_rules! req_filters {
+    ($( $iter:ident => $with:ident, $without:ident: $ty:ty ),+ $(,)?) => {
+        $(
+            #[doc = concat!(
+                "The ", stringify!($iter), " that require something, see [`DeepData::",
+                stringify!($without), "`]"
+            )]
+            pub fn $with(&self) -> impl Iterator<Item = &$ty> {
+                self.$iter().filter(|x| !x.reqs.is_empty())
+            }
+
+            #[doc = concat!(
+                "The ", stringify!($iter), " whose requirement is empty (`()`), i.e. free picks"
+            )]
+            pub fn $without(&self) -> impl Iterator<Item = &$ty> {
+                self.$iter().filter(|x| x.reqs.is_empty())
+            }
+        )+
+    };
+}
+
+/// Every entry of `map` ranked by how close its key is to `query` (after
+/// [`name_to_identifier`]), best first. The score is one minus the edit distance over the
+/// longer of the two, so an exact match scores 1.0 and nothing in common scores 0.0 (and is
+/// left out). Ties go alphabetically by key.
+fn search<'a, T>(map: &'a HashMap<String, T>, query: &str) -> Vec<(&'a T, f64)> {
+    ranked(map, query)
+        .into_iter()
+        .map(|(_, item, score)| (item, score))
+        .collect()
+}
+
+/// The key in `map` closest to `query`, if it scores at least [`DeepData::FUZZY_THRESHOLD`]
+fn closest_key<T>(map: &HashMap<String, T>, query: &str) -> Option<String> {
+    ranked(map, query)
+        .into_iter()
+        .next()
+        .filter(|(_, _, score)| *score >= DeepData::FUZZY_THRESHOLD)
+        .map(|(key, _, _)| key.clone())
+}
+
+#[allow(clippy::cast_precision_loss, reason = "names are short")]
+fn ranked<'a, T>(map: &'a HashMap<String, T>, query: &str) -> Vec<(&'a String, &'a T, f64)> {
+    let query = name_to_identifier(query);
+
+    let mut hits: Vec<(&String, &T, f64)> = map
+        .iter()
+        .map(|(key, item)| {
+            let longest = key.chars().count().max(query.chars().count()).max(1);
+            let score = 1.0 - edit_distance(key, &query) as f64 / longest as f64;
+            (key, item, score)
+        })
+        .filter(|(_, _, score)| *score > 0.0)
+        .collect();
+
+    hits.sort_by(|(a_key, _, a), (b_key, _, b)| b.total_cmp(a).then_with(|| a_key.cmp(b_key)));
+    hits
+}
+
 fn reqless_requirement(qualified_id: &str) -> Requirement {
     let mut req = Requirement::new();
     req.name = Some(qualified_id.to_string());
@@ -54,6 +135,12 @@ pub struct Aspect {
 
 impl Aspect {
     pub const NAMESPACE: &'static str = "aspect";
+
+    /// The stats this aspect grants for free
+    #[must_use]
+    pub fn innate_statmap(&self) -> StatMap {
+        StatMap::from(self.innate.clone())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -355,6 +442,47 @@ impl Objective {
     }
 }
 
+/// The bundle's item categories, as (json key, namespace) pairs.
+const CATEGORIES: [(&str, &str); 10] = [
+    ("aspects", Aspect::NAMESPACE),
+    ("talents", Talent::NAMESPACE),
+    ("mantras", Mantra::NAMESPACE),
+    ("weapons", Weapon::NAMESPACE),
+    ("outfits", Outfit::NAMESPACE),
+    ("equipment", Equipment::NAMESPACE),
+    ("enchants", Enchant::NAMESPACE),
+    ("origins", Origin::NAMESPACE),
+    ("resonances", Resonance::NAMESPACE),
+    ("objectives", Objective::NAMESPACE),
+];
+
+/// What changed from one bundle to another, see [`DeepData::diff`]. Every entry is a
+/// qualified id (`ns:key`), so the category is its namespace. Each list is sorted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl DataDiff {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// `items` sorted by `name`
+fn by_name<'a, T>(items: impl Iterator<Item = &'a T>, name: fn(&T) -> &str) -> Vec<&'a T> {
+    let mut items: Vec<&T> = items.collect();
+    items.sort_by(|a, b| name(a).cmp(name(b)));
+    items
+}
+
 /// A struct mirroring the structure of the 'all.json'
 /// bundle found on [pocamind/data releases](https://github.com/pocamind/data/releases).
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -376,6 +504,10 @@ pub struct DeepData {
     /// The shape is guarenteed to have at least the fields that `DeepData` has.
     #[serde(skip, default)]
     raw: String,
+
+    /// Tag of the release the data came from, see [`DeepData::version`]
+    #[serde(skip, default)]
+    version: Option<String>,
 }
 
 impl DeepData {
@@ -426,6 +558,19 @@ impl DeepData {
             .expect("bundled all.json failed to parse")
     }
 
+    /// A tiny hand-picked bundle (a few talents with a prereq chain, one weapon, one aspect)
+    /// for tests that need data without the network or the full bundle.
+    ///
+    /// # Panics
+    ///
+    /// Never, short of the fixture falling out of sync with the data schema.
+    #[cfg(any(test, feature = "testing"))]
+    #[must_use]
+    pub fn test_fixture() -> DeepData {
+        DeepData::from_json(include_str!("../../assets/fixture.json"))
+            .expect("fixture.json failed to parse")
+    }
+
     /// Retrieve the raw JSON used to construct the data schema. 
     /// 
     /// We expose this functionality because the data schema may be
@@ -435,6 +580,25 @@ impl DeepData {
         &self.raw
     }
 
+    /// Same as [`DeepData::raw`], as a `&str`
+    #[must_use]
+    pub fn raw_json(&self) -> &str {
+        &self.raw
+    }
+
+    /// Tag of the release the data was fetched from (e.g. `v1.2.3`), to show which data
+    /// version is loaded or to bust caches. `None` for data parsed with
+    /// [`DeepData::from_json`].
+    #[must_use]
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    #[cfg(feature = "fetch")]
+    pub(crate) fn set_version(&mut self, version: Option<String>) {
+        self.version = version;
+    }
+
     /// Retrieve a talent by it's name.
     ///
     /// The passed in name can be it's in-game name, or the
@@ -522,6 +686,60 @@ impl DeepData {
         self.objectives.get(&name_to_identifier(name))
     }
 
+    /// The lowest score [`DeepData::get_talent_fuzzy`] accepts as a match
+    pub const FUZZY_THRESHOLD: f64 = 0.75;
+
+    search_methods! {
+        search_talent => talents: Talent,
+        search_mantra => mantras: Mantra,
+        search_weapon => weapons: Weapon,
+        search_outfit => outfits: Outfit,
+        search_equipment => equipment: Equipment,
+        search_aspect => aspects: Aspect,
+        search_enchant => enchants: Enchant,
+        search_preset => presets: Preset,
+        search_origin => origins: Origin,
+        search_resonance => resonances: Resonance,
+        search_objective => objectives: Objective,
+    }
+
+    /// [`DeepData::get_talent`], falling back to the closest [`DeepData::search_talent`]
+    /// match if it scores at least [`DeepData::FUZZY_THRESHOLD`]
+    #[must_use]
+    pub fn get_talent_fuzzy(&self, query: &str) -> Option<&Talent> {
+        self.get_talent(query).or_else(|| {
+            self.search_talent(query)
+                .into_iter()
+                .next()
+                .filter(|(_, score)| *score >= Self::FUZZY_THRESHOLD)
+                .map(|(talent, _)| talent)
+        })
+    }
+
+    /// The qualified id (`ns:key`) in the data that `qualified_id` refers to: itself if it
+    /// exists, otherwise the closest key in the same namespace scoring at least
+    /// [`DeepData::FUZZY_THRESHOLD`]. Lets ids saved before an item was renamed be remapped.
+    #[must_use]
+    pub fn resolve_id(&self, qualified_id: &str) -> Option<String> {
+        let (namespace, key) = qualified_id.split_once(':')?;
+
+        let key = match namespace {
+            Talent::NAMESPACE => closest_key(&self.talents, key),
+            Mantra::NAMESPACE => closest_key(&self.mantras, key),
+            Weapon::NAMESPACE => closest_key(&self.weapons, key),
+            Outfit::NAMESPACE => closest_key(&self.outfits, key),
+            Equipment::NAMESPACE => closest_key(&self.equipment, key),
+            Objective::NAMESPACE => closest_key(&self.objectives, key),
+            Aspect::NAMESPACE => closest_key(&self.aspects, key),
+            Origin::NAMESPACE => closest_key(&self.origins, key),
+            Resonance::NAMESPACE => closest_key(&self.resonances, key),
+            Enchant::NAMESPACE => closest_key(&self.enchants, key),
+            _ => None,
+        }?;
+
+        Some(format!("{namespace}:{key}"))
+    }
+
     #[must_use]
     pub fn requirement(&self, qualified_id: &str) -> Option<Requirement> {
         let (namespace, key) = qualified_id.split_once(':')?;
@@ -547,6 +765,23 @@ impl DeepData {
         }
     }
 
+    /// Every item's [`DeepData::requirement`] by qualified id, fed through `interner` so the
+    /// ones sharing a name or clause set share one allocation. For holding the whole bundle's
+    /// requirements in memory, e.g. in WASM.
+    #[cfg(feature = "intern")]
+    pub fn intern_requirements(
+        &self,
+        interner: &mut Interner,
+    ) -> HashMap<String, InternedRequirement> {
+        self.ids()
+            .into_iter()
+            .filter_map(|id| {
+                let req = self.requirement(&id)?;
+                Some((id, interner.requirement(&req)))
+            })
+            .collect()
+    }
+
     #[must_use]
     pub fn implicit_requirements(&self) -> HashMap<String, Requirement> {
         self.talents
@@ -595,6 +830,141 @@ impl DeepData {
         graph
     }
 
+    /// The qualified id (`ns:key`) of every item in the bundle, sorted.
+    #[must_use]
+    pub fn ids(&self) -> Vec<String> {
+        let keys: [(&str, Vec<&String>); 10] = [
+            (Aspect::NAMESPACE, self.aspects.keys().collect()),
+            (Talent::NAMESPACE, self.talents.keys().collect()),
+            (Mantra::NAMESPACE, self.mantras.keys().collect()),
+            (Weapon::NAMESPACE, self.weapons.keys().collect()),
+            (Outfit::NAMESPACE, self.outfits.keys().collect()),
+            (Equipment::NAMESPACE, self.equipment.keys().collect()),
+            (Enchant::NAMESPACE, self.enchants.keys().collect()),
+            (Origin::NAMESPACE, self.origins.keys().collect()),
+            (Resonance::NAMESPACE, self.resonances.keys().collect()),
+            (Objective::NAMESPACE, self.objectives.keys().collect()),
+        ];
+
+        let mut ids: Vec<String> = keys
+            .iter()
+            .flat_map(|(ns, keys)| keys.iter().map(move |key| format!("{ns}:{key}")))
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// A smaller bundle holding only the given qualified ids, everything they (transitively)
+    /// have as a prereq, and the implicit talents. Ids not in the bundle are ignored, and
+    /// non-item data (presets and the like) is kept whole.
+    ///
+    /// Works on the raw JSON, so fields `DeepData` doesn't know about survive the trip.
+    pub fn subset<'a>(&self, ids: impl IntoIterator<Item = &'a str>) -> Result<DeepData> {
+        let graph = self.prereq_graph();
+
+        let mut keep: HashSet<String> = self.implicit_requirements().into_keys().collect();
+        for id in ids {
+            keep.extend(graph.all_prereqs(id));
+            keep.insert(id.to_string());
+        }
+
+        let mut raw = self.raw_value()?;
+        for (field, ns) in CATEGORIES {
+            if let Some(serde_json::Value::Object(items)) = raw.get_mut(field) {
+                items.retain(|key, _| keep.contains(&format!("{ns}:{key}")));
+            }
+        }
+
+        let mut data = DeepData::from_json(&raw.to_string())?;
+        data.version.clone_from(&self.version);
+        Ok(data)
+    }
+
+    /// Patches this bundle with every item in `patch`, replacing existing entries and adding
+    /// new ones. Returns the qualified ids that were written, sorted.
+    ///
+    /// Only item categories are patched, the rest of `patch` is ignored.
+    pub fn overlay(&mut self, patch: &DeepData) -> Result<Vec<String>> {
+        let mut raw = self.raw_value()?;
+        let patch = patch.raw_value()?;
+
+        let mut written = vec![];
+        if let Some(root) = raw.as_object_mut() {
+            for (field, ns) in CATEGORIES {
+                let Some(serde_json::Value::Object(items)) = patch.get(field) else {
+                    continue;
+                };
+
+                let target = root
+                    .entry(field)
+                    .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+
+                if let Some(target) = target.as_object_mut() {
+                    for (key, item) in items {
+                        target.insert(key.clone(), item.clone());
+                        written.push(format!("{ns}:{key}"));
+                    }
+                }
+            }
+        }
+
+        let version = self.version.take();
+        *self = DeepData::from_json(&raw.to_string())?;
+        self.version = version;
+
+        written.sort();
+        Ok(written)
+    }
+
+    /// The items added, removed or changed going from this bundle to `other`, e.g. for a
+    /// changelog between two releases. An item changed if any field `DeepData` knows about
+    /// differs, fields only in the raw payload aren't compared.
+    ///
+    /// # Panics
+    ///
+    /// Never, the bundle's types always serialize.
+    #[must_use]
+    pub fn diff(&self, other: &DeepData) -> DataDiff {
+        let value = |data: &DeepData| {
+            serde_json::to_value(data).expect("DeepData always serializes to JSON")
+        };
+        let (old, new) = (value(self), value(other));
+
+        let mut diff = DataDiff::default();
+        for (field, ns) in CATEGORIES {
+            let items =
+                |value: &serde_json::Value| value.get(field).and_then(|v| v.as_object()).cloned();
+            let old = items(&old).unwrap_or_default();
+            let new = items(&new).unwrap_or_default();
+
+            for (key, item) in &new {
+                match old.get(key) {
+                    None => diff.added.push(format!("{ns}:{key}")),
+                    Some(previous) if previous != item => diff.changed.push(format!("{ns}:{key}")),
+                    Some(_) => {}
+                }
+            }
+            for key in old.keys().filter(|key| !new.contains_key(*key)) {
+                diff.removed.push(format!("{ns}:{key}"));
+            }
+        }
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+
+    /// The raw payload as JSON, falling back to serializing `self` when there is none (e.g.
+    /// built through `Default`).
+    fn raw_value(&self) -> Result<serde_json::Value> {
+        if self.raw.is_empty() {
+            Ok(serde_json::to_value(self)?)
+        } else {
+            Ok(serde_json::from_str(&self.raw)?)
+        }
+    }
+
     /// Retrieve an iterator of talents
     pub fn talents(&self) -> impl Iterator<Item = &Talent> {
         self.talents.values()
@@ -646,6 +1016,101 @@ impl DeepData {
     pub fn objectives(&self) -> impl Iterator<Item = &Objective> {
         self.objectives.values()
     }
+
+    /// Talents in `category` (e.g. `flamecharmer`), ignoring case, sorted by name. Vaulted
+    /// talents are only kept with `include_vaulted`.
+    #[must_use]
+    pub fn talents_by_category(&self, category: &str, include_vaulted: bool) -> Vec<&Talent> {
+        by_name(
+            self.talents().filter(|t| {
+                t.category.eq_ignore_ascii_case(category) && (include_vaulted || !t.vaulted)
+            }),
+            |t| &t.name,
+        )
+    }
+
+    /// Same as [`DeepData::talents_by_category`], by [rarity](TalentRarity) name
+    #[must_use]
+    pub fn talents_by_rarity(&self, rarity: &str, include_vaulted: bool) -> Vec<&Talent> {
+        by_name(
+            self.talents().filter(|t| {
+                t.rarity.name().eq_ignore_ascii_case(rarity) && (include_vaulted || !t.vaulted)
+            }),
+            |t| &t.name,
+        )
+    }
+
+    /// Same as [`DeepData::talents_by_category`], by [weapon type](WeaponType) name.
+    /// Weapons can't be vaulted.
+    #[must_use]
+    pub fn weapons_by_type(&self, weapon_type: &str) -> Vec<&Weapon> {
+        by_name(
+            self.weapons()
+                .filter(|w| w.weapon_type.name().eq_ignore_ascii_case(weapon_type)),
+            |w| &w.name,
+        )
+    }
+
+    /// Same as [`DeepData::talents_by_category`], by [mantra type](MantraType) name
+    #[must_use]
+    pub fn mantras_by_type(&self, mantra_type: &str, include_vaulted: bool) -> Vec<&Mantra> {
+        by_name(
+            self.mantras().filter(|m| {
+                m.mantra_type.name().eq_ignore_ascii_case(mantra_type)
+                    && (include_vaulted || !m.vaulted)
+            }),
+            |m| &m.name,
+        )
+    }
+
+    /// Same as [`DeepData::talents_by_category`], for outfits. Outfits can't be vaulted.
+    #[must_use]
+    pub fn outfits_by_category(&self, category: &str) -> Vec<&Outfit> {
+        by_name(
+            self.outfits()
+                .filter(|o| o.category.eq_ignore_ascii_case(category)),
+            |o| &o.name,
+        )
+    }
+
+    /// Every category a talent, mantra or outfit is in, sorted and without duplicates. E.g.
+    /// to fill a picker for [`DeepData::talents_by_category`].
+    #[must_use]
+    pub fn categories(&self) -> Vec<&str> {
+        let categories: std::collections::BTreeSet<&str> = self
+            .talents()
+            .map(|t| t.category.as_str())
+            .chain(self.mantras().map(|m| m.category.as_str()))
+            .chain(self.outfits().map(|o| o.category.as_str()))
+            .filter(|category| !category.is_empty())
+            .collect();
+        categories.into_iter().collect()
+    }
+
+    req_filters! {
+        talents => talents_with_reqs, reqless_talents: Talent,
+        mantras => mantras_with_reqs, reqless_mantras: Mantra,
+        weapons => weapons_with_reqs, reqless_weapons: Weapon,
+        outfits => outfits_with_reqs, reqless_outfits: Outfit,
+        equipment => equipment_with_reqs, reqless_equipment: Equipment,
+        objectives => objectives_with_reqs, reqless_objectives: Objective,
+    }
+
+    /// Every stat some item's requirement uses, across talents, mantras, weapons, outfits,
+    /// equipment and objectives. E.g. to only offer filters for stats that matter in this
+    /// bundle. [`Stat::Total`] is left out, see [`Requirement::used_stats`].
+    #[must_use]
+    pub fn all_used_stats(&self) -> HashSet<Stat> {
+        self.talents()
+            .map(|x| &x.reqs)
+            .chain(self.mantras().map(|x| &x.reqs))
+            .chain(self.weapons().map(|x| &x.reqs))
+            .chain(self.outfits().map(|x| &x.reqs))
+            .chain(self.equipment().map(|x| &x.reqs))
+            .chain(self.objectives().map(|x| &x.reqs))
+            .flat_map(Requirement::used_stats)
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -653,6 +1118,132 @@ mod tests {
     use super::*;
     use crate::model::req::PrereqGroup;
 
+    #[test]
+    fn search_ranks_typos() {
+        let data = DeepData::test_fixture();
+
+        let hits = data.search_talent("flamecharmr");
+        assert_eq!(hits[0].0.name, "Flamecharmer");
+        assert!(hits[0].1 < 1.0 && hits[0].1 > DeepData::FUZZY_THRESHOLD);
+        assert_eq!(hits[1].0.name, "Adept Flamecharmer");
+        assert!(hits.windows(2).all(|w| w[0].1 >= w[1].1));
+
+        assert!((data.search_talent("Flamecharmer")[0].1 - 1.0).abs() < f64::EPSILON);
+        assert_eq!(
+            data.get_talent_fuzzy("silencer blade")
+                .map(|t| t.name.as_str()),
+            Some("Silencer's Blade")
+        );
+        assert!(data.get_talent_fuzzy("zzz").is_none());
+
+        // ties are alphabetical
+        let map = HashMap::from([("abd".to_string(), 1), ("abc".to_string(), 2)]);
+        let hits = search(&map, "abe");
+        assert_eq!(hits.iter().map(|(v, _)| **v).collect::<Vec<_>>(), [2, 1]);
+        assert!((hits[0].1 - hits[1].1).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn all_used_stats_matches_fixture() {
+        let data = DeepData::test_fixture();
+
+        // the fixture's 165s TTL gate doesn't count
+        assert_eq!(
+            data.all_used_stats(),
+            HashSet::from([Stat::Flamecharm, Stat::Galebreathe, Stat::MediumWeapon])
+        );
+        assert!(DeepData::default().all_used_stats().is_empty());
+    }
+
+    #[test]
+    fn diff_between_bundles() {
+        let talent = |key: &str, reqs: &str, extra: &str| {
+            format!(
+                r#""{key}":{{"name":"{key}","desc":"","rarity":"Common","category":"",
+                    "reqs":"{reqs}","count_towards_talent_total":true,"vaulted":false,
+                    "voi":false{extra}}}"#
+            )
+        };
+        let old = DeepData::from_json(&format!(
+            r#"{{"talents":{{{},{},{}}}}}"#,
+            talent("kept", "20s STR", ""),
+            talent("nerfed", "20s STR", ""),
+            talent("gone", "10s AGL", ""),
+        ))
+        .unwrap();
+        let new = DeepData::from_json(&format!(
+            r#"{{"talents":{{{},{},{}}},
+                "aspects":{{"adret":{{"name":"Adret","desc":"","is_pathfinder":true,"variants":{{}},
+                    "innate":{{}}}}}}}}"#,
+            talent("kept", "20s STR", r#","unknown_field":1"#),
+            talent("nerfed", "40s STR", ""),
+            talent("fresh", "10s AGL", ""),
+        ))
+        .unwrap();
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, ["aspect:adret", "talent:fresh"]);
+        assert_eq!(diff.removed, ["talent:gone"]);
+        // fields DeepData doesn't know about aren't compared
+        assert_eq!(diff.changed, ["talent:nerfed"]);
+
+        let json: serde_json::Value = serde_json::from_str(&diff.to_json().unwrap()).unwrap();
+        assert_eq!(json["removed"][0], "talent:gone");
+        assert!(new.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn filters_ignore_case() {
+        let data = DeepData::test_fixture();
+
+        let names =
+            |talents: Vec<&Talent>| talents.iter().map(|t| t.name.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            names(data.talents_by_category("flamecharmer", true)),
+            ["Adept Flamecharmer", "Flamecharmer"]
+        );
+        assert_eq!(
+            names(data.talents_by_rarity("ADVANCED", true)),
+            ["A World Without Song"]
+        );
+        assert_eq!(data.weapons_by_type("spear").len(), 1);
+        assert!(data.talents_by_category("nope", true).is_empty());
+        assert_eq!(data.categories(), ["Flamecharmer", "Silencer"]);
+
+        // vaulted items only show up when asked for
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/all.json");
+        let data = DeepData::from_json(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let all = data.talents_by_category("cryomancer", true);
+        let live = data.talents_by_category("cryomancer", false);
+        assert_eq!(all.len(), live.len() + 1);
+        assert!(live.iter().all(|t| !t.vaulted));
+        let spike_traps = &data.get_talent("spike_traps").unwrap().name;
+        assert!(all.iter().any(|t| &t.name == spike_traps));
+
+        let oaths = data.mantras_by_type("oath", true);
+        assert!(!oaths.is_empty() && oaths.iter().all(|m| m.mantra_type == MantraType::Oath));
+        assert!(!data.outfits_by_category("adept").is_empty());
+        assert!(data.categories().contains(&"Cryomancer"));
+    }
+
+    #[test]
+    fn reqless_talents_partition() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/all.json");
+        let data = DeepData::from_json(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+        let with: HashSet<&str> = data.talents_with_reqs().map(|t| t.name.as_str()).collect();
+        let without: HashSet<&str> = data.reqless_talents().map(|t| t.name.as_str()).collect();
+
+        assert!(!with.is_empty() && !without.is_empty());
+        assert!(with.is_disjoint(&without));
+        assert_eq!(with.len() + without.len(), data.talents().count());
+        assert!(with.contains("Flamecharmer"));
+        assert!(
+            data.reqless_talents()
+                .all(|t| t.reqs.to_string().ends_with("()"))
+        );
+    }
+
     const NEW_FORMAT: &str = r#"{
         "talents": {
             "a_world_without_song": {
@@ -690,6 +1281,43 @@ mod tests {
         assert_eq!(req.clauses.len(), 1);
     }
 
+    #[test]
+    fn aspect_innate_statmap_cost() {
+        let data = DeepData::from_json(
+            r#"{"aspects":{"adret":{"name":"Adret","desc":"","is_pathfinder":true,"variants":{},
+                "innate":{"Charisma":3,"Willpower":2}}}}"#,
+        )
+        .unwrap();
+
+        let innate = data.get_aspect("Adret").unwrap().innate_statmap();
+        assert_eq!(innate.get(&crate::Stat::Charisma), 3);
+        assert_eq!(innate.cost(), 5);
+    }
+
+    #[test]
+    fn talent_tooltip_fields() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/all.json");
+        let data = DeepData::from_json(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+        let talent = data.get_talent("spike_traps").unwrap();
+        assert!(talent.vaulted);
+        assert!(!talent.desc.is_empty());
+
+        // the bindings hand talents over as this JSON, typed by `Talent` in ts/types.ts
+        let json = serde_json::to_value(talent).unwrap();
+        assert_eq!(json["rarity"], serde_json::to_value(talent.rarity).unwrap());
+        assert_eq!(json["vaulted"], true);
+        for field in [
+            "desc",
+            "category",
+            "exclusive",
+            "stats",
+            "count_towards_talent_total",
+        ] {
+            assert!(json.get(field).is_some(), "missing {field}");
+        }
+    }
+
     #[test]
     fn objectives_table_loads() {
         let data = DeepData::from_json(NEW_FORMAT).unwrap();
@@ -702,4 +1330,98 @@ mod tests {
         assert_eq!(req.name, Some("objective:justicar".to_string()));
         assert!(req.is_empty());
     }
+
+    const PATCHABLE: &str = r#"{
+        "talents": {
+            "silencers_blade": {
+                "name": "Silencer's Blade",
+                "desc": "",
+                "rarity": "Rare",
+                "category": "Silencer",
+                "reqs": "40s WND",
+                "prereqs": [],
+                "count_towards_talent_total": true,
+                "vaulted": false,
+                "voi": false
+            },
+            "a_world_without_song": {
+                "name": "A World Without Song",
+                "desc": "",
+                "rarity": "Advanced",
+                "category": "Silencer",
+                "reqs": "75s WND",
+                "prereqs": ["talent:silencers_blade"],
+                "count_towards_talent_total": true,
+                "vaulted": false,
+                "voi": false
+            },
+            "unrelated": {
+                "name": "Unrelated",
+                "desc": "",
+                "rarity": "Common",
+                "category": "Other",
+                "reqs": "10s STR",
+                "count_towards_talent_total": true,
+                "vaulted": false,
+                "voi": false
+            }
+        },
+        "objectives": {
+            "justicar": {
+                "name": "Justicar",
+                "desc": "",
+                "accountWideUnlock": true
+            }
+        }
+    }"#;
+
+    #[test]
+    fn subset_keeps_prereq_closure() {
+        let data = DeepData::from_json(PATCHABLE).unwrap();
+
+        let subset = data.subset(["talent:a_world_without_song"]).unwrap();
+        assert_eq!(
+            subset.ids(),
+            ["talent:a_world_without_song", "talent:silencers_blade"]
+        );
+        assert!(subset.get_talent("unrelated").is_none());
+        assert!(subset.raw().contains("Silencer's Blade"));
+    }
+
+    #[test]
+    fn overlay_replaces_and_adds() {
+        let mut data = DeepData::from_json(PATCHABLE).unwrap();
+        let patch = DeepData::from_json(
+            r#"{
+                "talents": {
+                    "unrelated": {
+                        "name": "Unrelated",
+                        "desc": "patched",
+                        "rarity": "Common",
+                        "category": "Other",
+                        "reqs": "20s STR",
+                        "count_towards_talent_total": true,
+                        "vaulted": false,
+                        "voi": false
+                    }
+                },
+                "objectives": {
+                    "new_objective": {
+                        "name": "New Objective",
+                        "desc": "",
+                        "accountWideUnlock": false
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let written = data.overlay(&patch).unwrap();
+        assert_eq!(written, ["objective:new_objective", "talent:unrelated"]);
+
+        assert_eq!(data.get_talent("unrelated").unwrap().desc, "patched");
+        assert!(data.get_talent("a_world_without_song").is_some());
+        assert!(data.get_objective("justicar").is_some());
+        assert!(data.get_objective("new_objective").is_some());
+    }
 }
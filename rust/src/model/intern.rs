@@ -0,0 +1,226 @@
+//! Interned requirements, for holding a whole bundle's worth of requirements in memory
+//! (e.g. in WASM) without every copy of a name or stat block owning its own allocation.
+//!
+//! Many items share a stat block (every `25 STR` talent, every reqless aspect), so a bundle
+//! has far fewer distinct clause sets than requirements. Names and prereqs are deduplicated
+//! too, which pays off where the same prereq is listed by many dependents. See
+//! [`DeepData::intern_requirements`](crate::model::data::DeepData::intern_requirements), and
+//! the `interning_shrinks_the_bundle` test for the saving on the bundled data.
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+    sync::Arc,
+};
+
+use crate::model::req::{Clause, PrereqGroup, Requirement};
+
+/// A [`Requirement`] whose names and clauses are shared with every other requirement
+/// interned by the same [`Interner`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InternedRequirement {
+    pub name: Option<Arc<str>>,
+    /// Each group's alternatives, in the same order as [`PrereqGroup::alternatives`]
+    pub prereqs: Box<[Box<[Arc<str>]>]>,
+    pub clauses: Arc<BTreeSet<Clause>>,
+}
+
+/// Deduplicates the strings and clause sets of the requirements fed through it
+#[derive(Debug, Default)]
+pub struct Interner {
+    names: HashSet<Arc<str>>,
+    clauses: HashSet<Arc<BTreeSet<Clause>>>,
+}
+
+impl Interner {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared copy of `s`, allocating it the first time it's seen
+    pub fn name(&mut self, s: &str) -> Arc<str> {
+        if let Some(name) = self.names.get(s) {
+            return name.clone();
+        }
+
+        let name: Arc<str> = Arc::from(s);
+        self.names.insert(name.clone());
+        name
+    }
+
+    fn clauses(&mut self, clauses: &BTreeSet<Clause>) -> Arc<BTreeSet<Clause>> {
+        if let Some(shared) = self.clauses.get(clauses) {
+            return shared.clone();
+        }
+
+        let shared = Arc::new(clauses.clone());
+        self.clauses.insert(shared.clone());
+        shared
+    }
+
+    pub fn requirement(&mut self, req: &Requirement) -> InternedRequirement {
+        InternedRequirement {
+            name: req.name.as_deref().map(|n| self.name(n)),
+            prereqs: req
+                .prereqs
+                .iter()
+                .map(|group| group.alternatives().map(|alt| self.name(alt)).collect())
+                .collect(),
+            clauses: self.clauses(&req.clauses),
+        }
+    }
+
+    /// Distinct names held
+    #[must_use]
+    pub fn name_count(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Distinct clause sets held
+    #[must_use]
+    pub fn clause_set_count(&self) -> usize {
+        self.clauses.len()
+    }
+}
+
+impl From<&InternedRequirement> for Requirement {
+    fn from(req: &InternedRequirement) -> Self {
+        Requirement {
+            name: req.name.as_deref().map(str::to_string),
+            prereqs: req
+                .prereqs
+                .iter()
+                .map(|group| PrereqGroup::any(group.iter().map(AsRef::<str>::as_ref)))
+                .collect(),
+            clauses: (*req.clauses).clone(),
+        }
+    }
+}
+
+impl fmt::Display for InternedRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Requirement::from(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stat;
+    use crate::model::data::DeepData;
+    use crate::model::req::Atom;
+
+    #[test]
+    fn interned_displays_the_same() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/all.json");
+        let data = DeepData::from_json(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+        let reqs: Vec<Requirement> = data
+            .talents()
+            .map(|t| t.reqs.clone())
+            .chain(data.mantras().map(|m| m.reqs.clone()))
+            .chain(["a | b, c => d := 40 STR, (20 FLM OR 20 ICE)"
+                .parse()
+                .unwrap()])
+            .collect();
+
+        let mut interner = Interner::new();
+        let shared: Vec<InternedRequirement> =
+            reqs.iter().map(|r| interner.requirement(r)).collect();
+
+        for (req, int) in reqs.iter().zip(&shared) {
+            assert_eq!(req.to_string(), int.to_string());
+            assert_eq!(&Requirement::from(int), req);
+        }
+
+        assert!(interner.clause_set_count() < reqs.len());
+    }
+
+    /// Rough heap bytes behind `clauses`, counting each clause, atom and stat but not the
+    /// `BTreeSet` nodes holding them
+    fn clause_bytes(clauses: &BTreeSet<Clause>) -> usize {
+        clauses
+            .iter()
+            .flat_map(|clause| &clause.atoms)
+            .map(|atom| size_of::<Atom>() + atom.stats.len() * size_of::<Stat>())
+            .sum::<usize>()
+            + clauses.len() * size_of::<Clause>()
+    }
+
+    /// Rough heap bytes behind an owned requirement
+    fn owned_bytes(req: &Requirement) -> usize {
+        let names = req
+            .name
+            .iter()
+            .chain(req.prereqs.iter().flat_map(|g| &g.alternatives))
+            .map(String::len)
+            .sum::<usize>();
+        let groups: usize = req
+            .prereqs
+            .iter()
+            .map(|g| g.alternatives.len() * size_of::<String>())
+            .sum();
+        names + groups + req.prereqs.len() * size_of::<PrereqGroup>() + clause_bytes(&req.clauses)
+    }
+
+    /// Rough heap bytes behind an interned requirement, leaving out what it shares
+    fn interned_bytes(req: &InternedRequirement) -> usize {
+        req.prereqs
+            .iter()
+            .map(|g| size_of::<Box<[Arc<str>]>>() + g.len() * size_of::<Arc<str>>())
+            .sum()
+    }
+
+    #[test]
+    fn interning_shrinks_the_bundle() {
+        // an Arc's strong and weak counts
+        const ARC_HEADER: usize = 2 * size_of::<usize>();
+
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/all.json");
+        let data = DeepData::from_json(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+        let mut interner = Interner::new();
+        let reqs = data.intern_requirements(&mut interner);
+
+        let owned: usize = reqs
+            .keys()
+            .map(|id| owned_bytes(&data.requirement(id).unwrap()))
+            .sum();
+        let shared: usize = reqs.values().map(interned_bytes).sum::<usize>()
+            + interner
+                .names
+                .iter()
+                .map(|name| ARC_HEADER + name.len())
+                .sum::<usize>()
+            + interner
+                .clauses
+                .iter()
+                .map(|clauses| ARC_HEADER + clause_bytes(clauses))
+                .sum::<usize>();
+
+        // most requirements share their clauses with another, and that saves at least a tenth
+        // of the memory despite the pointers
+        let clause_sets = interner.clause_set_count();
+        assert!(
+            clause_sets * 3 < reqs.len(),
+            "{clause_sets} clause sets for {} requirements",
+            reqs.len()
+        );
+        assert!(
+            shared * 10 < owned * 9,
+            "{shared} bytes interned, {owned} owned"
+        );
+    }
+
+    #[test]
+    fn names_are_shared() {
+        let mut interner = Interner::new();
+        let a = interner.requirement(&"base => x := 40 STR".parse().unwrap());
+        let b = interner.requirement(&"base => y := 40 STR".parse().unwrap());
+
+        assert!(Arc::ptr_eq(&a.prereqs[0][0], &b.prereqs[0][0]));
+        assert!(Arc::ptr_eq(&a.clauses, &b.clauses));
+        assert_eq!(interner.name_count(), 3);
+    }
+}
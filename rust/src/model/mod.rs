@@ -2,6 +2,8 @@ pub mod aggregate;
 pub mod data;
 pub mod enums;
 pub mod formula;
+#[cfg(feature = "intern")]
+pub mod intern;
 pub mod opt;
 pub mod req;
 pub mod reqfile;
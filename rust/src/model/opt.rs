@@ -1,6 +1,10 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, ops::RangeInclusive};
 
-use crate::{model::req::Timing, req::Requirement};
+use crate::{
+    model::req::Timing,
+    req::Requirement,
+    util::{solve, statmap::StatMap},
+};
 
 /// Represents a group of requirements that are optional, but will be
 /// either all acquired or all not
@@ -13,10 +17,41 @@ pub struct OptionalGroup {
 }
 
 impl OptionalGroup {
+    /// The weights a reqfile may assign to an optional requirement
+    pub const WEIGHT_RANGE: RangeInclusive<i64> = 1..=20;
+
     pub fn get_set(&mut self, timing: Timing) -> &mut HashSet<Requirement> {
         match timing {
             Timing::Free => &mut self.general,
             Timing::Post => &mut self.post,
         }
     }
+
+    /// Solves the free and post members separately, returning `(free, post)`, so a planner
+    /// can tell what the group costs before and after the shrine.
+    #[must_use]
+    pub fn solve_timed(&self) -> (StatMap, StatMap) {
+        (solve::min_statmap(&self.general), solve::min_statmap(&self.post))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stat;
+
+    #[test]
+    fn solve_timed_splits_free_and_post() {
+        let group = OptionalGroup {
+            general: HashSet::from(["25 STR".parse().unwrap(), "20 STR OR 20 AGL".parse().unwrap()]),
+            post: HashSet::from(["40 FLM".parse().unwrap()]),
+            weight: 1,
+        };
+
+        let (free, post) = group.solve_timed();
+        assert_eq!(free.get(&Stat::Strength), 25);
+        assert_eq!(free.get(&Stat::Flamecharm), 0);
+        assert_eq!(post.get(&Stat::Flamecharm), 40);
+        assert_eq!(post.cost(), 40);
+    }
 }
@@ -1,15 +1,18 @@
 use core::fmt;
 use std::{
-    collections::{BTreeSet, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     hash::Hash,
     str::FromStr,
 };
 
 use serde::{Deserialize, Deserializer, Serialize, de};
 
-use crate::{Stat, error, util::statmap::StatMap};
+use crate::{
+    Stat, error,
+    util::{solve, statarray::StatArray, statmap::StatMap},
+};
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Reducability {
     Reducible,
@@ -88,19 +91,33 @@ impl Atom {
 
     #[must_use]
     pub fn satisfied_by(&self, stats: &StatMap) -> bool {
+        self.satisfied_with_total(stats, stats.cost())
+    }
+
+    /// Like [`Atom::satisfied_by`], with [`Stat::Total`] standing for `total`
+    fn satisfied_with_total(&self, stats: &StatMap, total: i64) -> bool {
+        self.satisfied_within(stats, total, 0)
+    }
+
+    /// Like [`Atom::satisfied_with_total`], but each summed stat may fall `tolerance` short
+    #[allow(
+        clippy::cast_possible_wrap,
+        reason = "an atom never sums more than a handful of stats"
+    )]
+    fn satisfied_within(&self, stats: &StatMap, total: i64, tolerance: i64) -> bool {
         let sum: i64 = self
             .stats
             .iter()
             .map(|s| {
                 if s == &Stat::Total {
-                    stats.cost()
+                    total
                 } else {
                     stats.get(s)
                 }
             })
             .sum();
 
-        sum >= self.value
+        sum + tolerance * self.stats.len() as i64 >= self.value
     }
 
     #[must_use]
@@ -108,6 +125,26 @@ impl Atom {
     pub fn is_empty(&self) -> bool {
         self.stats.is_empty() && self.value == 0
     }
+
+    /// How much of the value `stats` has, from 0 to 1, see [`Requirement::satisfaction`]
+    #[allow(clippy::cast_precision_loss, reason = "stat values are small")]
+    fn ratio(&self, stats: &StatMap, total: i64) -> f64 {
+        if self.value <= 0 {
+            return 1.0;
+        }
+        let have: i64 = self
+            .stats
+            .iter()
+            .map(|s| {
+                if s == &Stat::Total {
+                    total
+                } else {
+                    stats.get(s)
+                }
+            })
+            .sum();
+        (have as f64 / self.value as f64).clamp(0.0, 1.0)
+    }
 }
 
 impl fmt::Display for Atom {
@@ -139,8 +176,18 @@ impl fmt::Display for Atom {
 pub enum ClauseType {
     And,
     Or,
+    /// Holds a single atom, and is met when that atom isn't, e.g. `NOT 20 SDW` for a talent
+    /// that locks out Shadowcast. Reducability doesn't matter to it.
+    Not,
 }
 
+/// A group of atoms, all of which (`And`) or any of which (`Or`) must be met, or (`Not`) a
+/// single atom that must not be.
+///
+/// The parser gives every comma separated atom an `And` clause of its own, while atoms joined
+/// by an explicit `AND` share one. Either way [`Display`](fmt::Display) reads back as the same
+/// clauses. The one shape that doesn't is an `Or` holding a single atom, which prints like
+/// (and reads back as) an `And`. [`Clause::try_finish`] checks for it.
 #[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Clause {
     pub clause_type: ClauseType,
@@ -172,6 +219,14 @@ impl Clause {
         }
     }
 
+    #[must_use]
+    pub fn not() -> Self {
+        Self {
+            clause_type: ClauseType::Not,
+            atoms: BTreeSet::new(),
+        }
+    }
+
     #[must_use]
     pub fn clause_type(mut self, ct: ClauseType) -> Self {
         self.clause_type = ct;
@@ -190,28 +245,82 @@ impl Clause {
     #[must_use]
     pub fn insert(mut self, stats: StatSet, mut atom: Atom) -> Self {
         atom.stats = stats;
-        self.atoms.insert(atom);
+        self.add_atom(atom);
         self
     }
 
     #[must_use]
     pub fn atom(mut self, atom: Atom) -> Self {
-        self.atoms.insert(atom);
+        self.add_atom(atom);
         self
     }
 
+    /// Adds an atom. A NOT clause only ever negates one, so on one this replaces the atom
+    /// instead.
     pub fn add_atom(&mut self, atom: Atom) {
+        if self.clause_type == ClauseType::Not {
+            self.atoms.clear();
+        }
         self.atoms.insert(atom);
     }
 
+    /// Checks the clause reads back the same from its [`Display`](fmt::Display) (see
+    /// [`Clause`]), for clauses built atom by atom rather than parsed. Empty atoms don't count.
+    /// A NOT clause must hold exactly one atom.
+    pub fn try_finish(self) -> error::Result<Self> {
+        let atoms = self.atoms.iter().filter(|a| !a.is_empty()).count();
+
+        if self.clause_type == ClauseType::Or && atoms == 1 {
+            return Err(error::DeepError::Clause(format!(
+                "an OR clause needs more than one atom, but '{self}' has {atoms}. \
+                Make it an AND clause instead"
+            )));
+        }
+
+        if self.clause_type == ClauseType::Not && atoms != 1 {
+            return Err(error::DeepError::Clause(format!(
+                "a NOT clause negates exactly one atom, but '{self}' has {atoms}. \
+                Use a NOT clause per atom instead"
+            )));
+        }
+
+        Ok(self)
+    }
+
     #[must_use]
     pub fn satisfied_by(&self, stats: &StatMap) -> bool {
+        self.satisfied_with_total(stats, stats.cost())
+    }
+
+    fn satisfied_with_total(&self, stats: &StatMap, total: i64) -> bool {
+        self.satisfied_within(stats, total, 0)
+    }
+
+    /// `tolerance` only loosens what must be met. Staying under a NOT is checked exactly, as
+    /// a stat rounded up by the shrine can land on the value it must stay under.
+    ///
+    /// A NOT clause whose `atoms` were filled by hand with more than one is read the way it
+    /// [displays](fmt::Display) and [`Reqfile::compile`](super::reqfile::Reqfile::compile)
+    /// checks it: as a NOT per atom, met when none of them are.
+    fn satisfied_within(&self, stats: &StatMap, total: i64, tolerance: i64) -> bool {
+        let met = |atom: &Atom| atom.satisfied_within(stats, total, tolerance);
         match self.clause_type {
-            ClauseType::And => self.atoms.iter().all(|atom| atom.satisfied_by(stats)),
-            ClauseType::Or => self.atoms.iter().any(|atom| atom.satisfied_by(stats)),
+            ClauseType::And => self.atoms.iter().all(met),
+            ClauseType::Or => self.atoms.iter().any(met),
+            ClauseType::Not => !self
+                .atoms
+                .iter()
+                .any(|atom| atom.satisfied_within(stats, total, 0)),
         }
     }
 
+    /// Number of non-empty atoms, the ones that [display](fmt::Display). 0 exactly when
+    /// [`Clause::is_empty`].
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.atoms.iter().filter(|a| !a.is_empty()).count()
+    }
+
     #[must_use]
     pub fn is_empty(&self) -> bool {
         !self.atoms().iter().any(|a| !a.is_empty())
@@ -221,8 +330,9 @@ impl Clause {
 impl fmt::Display for Clause {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let joiner = match self.clause_type {
-            ClauseType::And => ", ",
+            ClauseType::And => " AND ",
             ClauseType::Or => " OR ",
+            ClauseType::Not => " AND NOT ",
         };
 
         let atom_strs: Vec<String> = self
@@ -232,6 +342,9 @@ impl fmt::Display for Clause {
             .map(|atom| format!("{atom}"))
             .collect();
 
+        if self.clause_type == ClauseType::Not {
+            write!(f, "NOT ")?;
+        }
         write!(f, "{}", atom_strs.join(joiner))
     }
 }
@@ -330,6 +443,21 @@ impl Requirement {
         crate::parse::req::parse_req(input)
     }
 
+    /// Parses newline separated requirements, each line on its own, skipping blank lines and
+    /// `#`/`//` comments. No reqfile headers, optionals or dependency statements.
+    ///
+    /// Fails with a [`DeepError::Reqs`](crate::error::DeepError::Reqs) listing every bad line
+    /// rather than just the first.
+    pub fn parse_many(input: &str) -> error::Result<Vec<Self>> {
+        crate::parse::req::parse_reqs(input)
+    }
+
+    /// Same as [`Requirement::parse`], also returning any warnings about likely mistakes,
+    /// e.g. an OR clause mixing explicit and implicit reducibility.
+    pub fn parse_verbose(input: &str) -> error::Result<(Self, Vec<String>)> {
+        crate::parse::req::parse_req_verbose(input)
+    }
+
     #[must_use]
     pub fn new() -> Self {
         Self {
@@ -339,6 +467,125 @@ impl Requirement {
         }
     }
 
+    /// A nameless requirement made of `clauses`. Empty atoms and clauses are dropped, since
+    /// they require nothing.
+    #[must_use]
+    pub fn with_clauses(clauses: impl IntoIterator<Item = Clause>) -> Self {
+        Self {
+            name: None,
+            prereqs: BTreeSet::new(),
+            clauses: clauses
+                .into_iter()
+                .map(|mut clause| {
+                    clause.atoms.retain(|a| !a.is_empty());
+                    clause
+                })
+                .filter(|clause| !clause.is_empty())
+                .collect(),
+        }
+    }
+
+    /// The (reducible) stats needed to reach the given mantra levels, keyed by attunement.
+    /// Level 1 only needs a point in the stat, every level after that takes 20 more.
+    #[must_use]
+    pub fn from_mantra_levels(levels: &StatMap) -> Self {
+        Requirement::with_clauses(levels.iter().map(|(stat, lvl)| {
+            let value = match (*lvl).max(1) {
+                1 => 1,
+                lvl => (lvl - 1) * 20,
+            };
+
+            Clause::and().atom(Atom::reducible().stat(*stat).value(value))
+        }))
+    }
+
+    /// A requirement demanding both `self` and `other`: their clauses together (empty ones
+    /// dropped, duplicates collapsed) and their prereqs unioned, keeping `self`'s name.
+    #[must_use]
+    pub fn merge_and(&self, other: &Requirement) -> Requirement {
+        Requirement {
+            name: self.name.clone(),
+            prereqs: self.prereqs.union(&other.prereqs).cloned().collect(),
+            ..Requirement::with_clauses(self.clauses.iter().chain(&other.clauses).cloned())
+        }
+    }
+
+    /// Drops what can never decide whether the requirement is met:
+    /// - atoms (and clauses) that are trivially met
+    /// - OR alternatives with a cheaper alternative over the same stats, like the `30 STR` in
+    ///   `25 STR OR 30 STR`
+    /// - AND atoms another AND atom over the same stats already demands more than, like the
+    ///   `25 STR` in `25 STR, 30 STR`
+    ///
+    /// Atoms are only compared when their reducability matches. An OR left with one
+    /// alternative becomes an AND clause. A clause with no atoms at all is dropped too, even an
+    /// (unsatisfiable) empty OR.
+    pub fn normalize(&mut self) {
+        let same_kind = |a: &Atom, b: &Atom| a.stats == b.stats && a.reducability == b.reducability;
+
+        let mut clauses: Vec<Clause> = vec![];
+        for mut clause in std::mem::take(&mut self.clauses) {
+            match clause.clause_type {
+                ClauseType::And => clause.atoms.retain(|a| !a.is_empty()),
+                ClauseType::Or => {
+                    if clause.atoms.iter().any(Atom::is_empty) {
+                        continue;
+                    }
+
+                    let atoms = clause.atoms.clone();
+                    clause
+                        .atoms
+                        .retain(|a| !atoms.iter().any(|b| same_kind(a, b) && b.value < a.value));
+
+                    if clause.atoms.len() == 1 {
+                        clause.clause_type = ClauseType::And;
+                    }
+                }
+                ClauseType::Not => {}
+            }
+
+            clauses.push(clause);
+        }
+
+        let and_atoms: Vec<Atom> = clauses
+            .iter()
+            .filter(|c| c.clause_type == ClauseType::And)
+            .flat_map(|c| c.atoms.iter().cloned())
+            .collect();
+        for clause in clauses
+            .iter_mut()
+            .filter(|c| c.clause_type == ClauseType::And)
+        {
+            clause.atoms.retain(|a| {
+                !and_atoms
+                    .iter()
+                    .any(|b| same_kind(a, b) && b.value > a.value)
+            });
+        }
+
+        self.clauses = clauses
+            .into_iter()
+            .filter(|c| !c.atoms.is_empty())
+            .collect();
+    }
+
+    /// A [`Requirement::normalize`]d copy
+    #[must_use]
+    pub fn normalized(&self) -> Requirement {
+        let mut req = self.clone();
+        req.normalize();
+        req
+    }
+
+    #[must_use]
+    pub fn clauses(&self) -> &BTreeSet<Clause> {
+        &self.clauses
+    }
+
+    pub fn clauses_mut(&mut self) -> &mut BTreeSet<Clause> {
+        &mut self.clauses
+    }
+
     pub fn add_clause(&mut self, clause: Clause) -> &mut Self {
         self.clauses.insert(clause);
         self
@@ -378,6 +625,12 @@ impl Requirement {
             .filter(|c| c.clause_type == ClauseType::Or)
     }
 
+    pub fn not_iter(&self) -> impl Iterator<Item = &Clause> {
+        self.clauses
+            .iter()
+            .filter(|c| c.clause_type == ClauseType::Not)
+    }
+
     pub fn atoms(&self) -> impl Iterator<Item = &Atom> {
         self.clauses.iter().flat_map(|clause| clause.atoms.iter())
     }
@@ -418,6 +671,83 @@ impl Requirement {
         self
     }
 
+    /// A copy of the requirement with every atom set to the given reducability, e.g. to
+    /// forbid the Shrine of Mastery from lowering it.
+    #[must_use]
+    pub fn with_reducability(&self, reducability: Reducability) -> Self {
+        Requirement {
+            name: self.name.clone(),
+            prereqs: self.prereqs.clone(),
+            clauses: self
+                .clauses
+                .iter()
+                .map(|clause| Clause {
+                    clause_type: clause.clause_type.clone(),
+                    atoms: clause
+                        .atoms
+                        .iter()
+                        .cloned()
+                        .map(|a| a.reducability(reducability))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// A copy keeping only the [strict](Reducability::Strict) atoms, i.e. what the Shrine of
+    /// Mastery can't lower. Clauses left without atoms are dropped.
+    ///
+    /// An OR is kept only if every alternative is strict. One mixing both kinds is met by
+    /// either, so keeping just one side would demand more than the original does, and it's
+    /// left out of both views. Anything meeting the requirement meets either view.
+    #[must_use]
+    pub fn strict_only(&self) -> Requirement {
+        self.only_reducability(Reducability::Strict)
+    }
+
+    /// The [reducible](Reducability::Reducible) counterpart of [`Requirement::strict_only`]
+    #[must_use]
+    pub fn reducible_only(&self) -> Requirement {
+        self.only_reducability(Reducability::Reducible)
+    }
+
+    fn only_reducability(&self, reducability: Reducability) -> Requirement {
+        Requirement {
+            name: self.name.clone(),
+            prereqs: self.prereqs.clone(),
+            clauses: self
+                .clauses
+                .iter()
+                .filter_map(|clause| {
+                    if clause.clause_type == ClauseType::Or {
+                        return clause
+                            .atoms
+                            .iter()
+                            .filter(|a| !a.is_empty())
+                            .all(|a| a.reducability == reducability)
+                            .then(|| clause.clone());
+                    }
+
+                    let atoms: BTreeSet<Atom> = clause
+                        .atoms
+                        .iter()
+                        .filter(|a| !a.is_empty() && a.reducability == reducability)
+                        .cloned()
+                        .collect();
+
+                    if atoms.is_empty() {
+                        return None;
+                    }
+
+                    Some(Clause {
+                        clause_type: clause.clause_type.clone(),
+                        atoms,
+                    })
+                })
+                .collect(),
+        }
+    }
+
     pub fn strict_atoms(&self) -> impl Iterator<Item = &Atom> {
         self.clauses.iter().flat_map(|clause| {
             clause
@@ -428,7 +758,7 @@ impl Requirement {
     }
 
     #[must_use]
-    /// Grab all the stats present in a requirement
+    /// Grab all the stats present in a requirement, negated ones included
     pub fn used_stats(&self) -> HashSet<Stat> {
         self.atoms().fold(HashSet::new(), |mut acc, atom| {
             for stat in &atom.stats {
@@ -442,16 +772,325 @@ impl Requirement {
         })
     }
 
+    /// [`Requirement::used_stats`] in [`Stat::GAME_ORDER`], for stable output.
+    #[must_use]
+    pub fn used_stats_sorted(&self) -> Vec<Stat> {
+        let used = self.used_stats();
+        Stat::iter_game_order()
+            .filter(|s| used.contains(s))
+            .collect()
+    }
+
     #[must_use]
     pub fn satisfied_by(&self, stats: &StatMap) -> bool {
         self.clauses.iter().all(|clause| clause.satisfied_by(stats))
     }
 
+    /// Like [`Requirement::satisfied_by`], but every stat an atom sums may fall `tolerance`
+    /// points short, e.g. 1 to allow for the flooring the Shrine of Order does
+    /// ([`StatMap::shrine_order`]). [`Stat::Total`] counts as a single stat.
+    #[must_use]
+    pub fn satisfied_by_within(&self, stats: &StatMap, tolerance: i64) -> bool {
+        let total = stats.cost();
+        self.clauses
+            .iter()
+            .all(|clause| clause.satisfied_within(stats, total, tolerance))
+    }
+
+    /// Like [`Requirement::satisfied_by`], but for a character at power `level`. Power gates
+    /// ([`Stat::Total`]) count the points earned by then ([`StatMap::points_at_level`]), or
+    /// the points invested in `stats` if that's more, rather than only what's invested.
+    #[must_use]
+    pub fn satisfied_by_at_level(&self, stats: &StatMap, level: u32) -> bool {
+        let total = stats.cost().max(StatMap::points_at_level(level));
+        self.clauses
+            .iter()
+            .all(|clause| clause.satisfied_with_total(stats, total))
+    }
+
+    /// Which clauses and atoms `stats` meets, in the order they [display](fmt::Display), for
+    /// a checklist. Empty clauses and atoms are left out like they are when displayed.
+    #[must_use]
+    pub fn coverage(&self, stats: &StatMap) -> Vec<ClauseCoverage> {
+        self.clauses
+            .iter()
+            .filter(|clause| !clause.is_empty())
+            .map(|clause| ClauseCoverage {
+                clause_type: clause.clause_type.clone(),
+                met: clause.satisfied_by(stats),
+                atoms: clause
+                    .atoms
+                    .iter()
+                    .filter(|atom| !atom.is_empty())
+                    .map(|atom| {
+                        let met = atom.satisfied_by(stats);
+                        if clause.clause_type == ClauseType::Not {
+                            (!met, format!("NOT {atom}"))
+                        } else {
+                            (met, atom.to_string())
+                        }
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// How close `stats` gets to meeting the requirement, from 0 to 1 (met), e.g. to rank
+    /// near-miss builds. Each atom scores the share of its value `stats` has; an AND clause
+    /// averages its atoms, an OR takes its best one and a NOT is all or nothing. The score is
+    /// the average over the non-empty clauses, so an empty requirement scores 1.
+    ///
+    /// Unlike [`worst_requirement_ratio`](crate::formulas::worst_requirement_ratio), one far
+    /// off atom doesn't sink the whole score.
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "a requirement has a handful of clauses"
+    )]
+    pub fn satisfaction(&self, stats: &StatMap) -> f64 {
+        let total = stats.cost();
+        let scores: Vec<f64> = self
+            .clauses
+            .iter()
+            .filter(|clause| !clause.is_empty())
+            .map(|clause| {
+                let ratios: Vec<f64> = clause
+                    .atoms
+                    .iter()
+                    .filter(|atom| !atom.is_empty())
+                    .map(|atom| atom.ratio(stats, total))
+                    .collect();
+                match clause.clause_type {
+                    ClauseType::And => ratios.iter().sum::<f64>() / ratios.len() as f64,
+                    ClauseType::Or => ratios.into_iter().fold(0.0, f64::max),
+                    ClauseType::Not => {
+                        if clause.satisfied_by(stats) {
+                            1.0
+                        } else {
+                            0.0
+                        }
+                    }
+                }
+            })
+            .collect();
+
+        if scores.is_empty() {
+            return 1.0;
+        }
+        scores.iter().sum::<f64>() / scores.len() as f64
+    }
+
+    /// Number of non-empty clauses, the ones that [display](fmt::Display). 0 exactly when
+    /// [`Requirement::is_empty`].
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.clauses.iter().filter(|c| !c.is_empty()).count()
+    }
+
     #[must_use]
     /// The requirement requires nothing and is therefore trivially satisfied (wow!)
     pub fn is_empty(&self) -> bool {
         !self.clauses.iter().any(|c| !c.is_empty())
     }
+
+    /// Whether every clause has an atom that can be met with no stat above `per_stat_cap`.
+    /// A sum can reach at most `cap` per stat it adds up, and [`Stat::Total`] counts every
+    /// stat at `cap`.
+    ///
+    /// Atoms are checked on their own, so two atoms that compete for the same stat can still
+    /// pass despite not fitting together.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_wrap,
+        reason = "there are only a handful of stats"
+    )]
+    pub fn satisfiable_within(&self, per_stat_cap: i64) -> bool {
+        let atom_fits = |atom: &Atom| {
+            let reach: i64 = atom
+                .stats
+                .iter()
+                .map(|s| {
+                    if *s == Stat::Total {
+                        Stat::all_investable().len() as i64 * per_stat_cap
+                    } else {
+                        per_stat_cap
+                    }
+                })
+                .sum();
+
+            atom.value <= reach
+        };
+
+        self.clauses
+            .iter()
+            .filter(|c| !c.is_empty())
+            .all(|clause| match clause.clause_type {
+                ClauseType::And => clause.atoms.iter().all(atom_fits),
+                ClauseType::Or => clause.atoms.iter().any(atom_fits),
+                // staying under a value always fits, unless there's no room under it
+                ClauseType::Not => clause.atoms.iter().all(|a| a.value > 0),
+            })
+    }
+
+    /// A cheap stat map meeting this requirement alone, see [`solve::min_statmap`]: each OR
+    /// takes its cheapest alternative, a sum's value lands on a single stat (the first of the
+    /// cheapest, in stat order) and [`Stat::Total`] gates are topped up last, once every other
+    /// point is counted. An empty requirement gives an empty map. NOT clauses never call for
+    /// points, so they're only met as long as nothing else pushes their stat up.
+    #[must_use]
+    pub fn min_statmap(&self) -> StatMap {
+        solve::min_statmap([self])
+    }
+
+    /// Most disjuncts [`Requirement::to_dnf`] will expand to
+    pub const DNF_CAP: usize = 4096;
+
+    /// The requirement in disjunctive normal form: one disjunct per combination of OR
+    /// alternatives, each holding every AND atom plus the chosen alternatives. Meeting every
+    /// atom of any one disjunct meets the requirement. Empty atoms are left out, as are ORs
+    /// that an empty atom already meets; an empty requirement is a single empty disjunct.
+    ///
+    /// # Errors
+    /// [`DeepError::Dnf`](error::DeepError::Dnf) if there would be more than [`Requirement::DNF_CAP`] disjuncts,
+    /// or the requirement has a NOT clause, which plain atoms can't express.
+    pub fn to_dnf(&self) -> error::Result<Vec<Vec<Atom>>> {
+        if let Some(not) = self.not_iter().next() {
+            return Err(error::DeepError::Dnf(format!(
+                "'{not}' in '{self}' has no disjunctive normal form over plain atoms"
+            )));
+        }
+
+        let and_atoms: Vec<&Atom> = self
+            .and_iter()
+            .flat_map(|c| c.atoms.iter())
+            .filter(|a| !a.is_empty())
+            .collect();
+        let or_clauses: Vec<&BTreeSet<Atom>> = self
+            .or_iter()
+            .map(|c| &c.atoms)
+            .filter(|atoms| !atoms.is_empty() && !atoms.iter().any(Atom::is_empty))
+            .collect();
+
+        let count = or_clauses
+            .iter()
+            .fold(1_usize, |n, atoms| n.saturating_mul(atoms.len()));
+        if count > Self::DNF_CAP {
+            return Err(error::DeepError::Dnf(format!(
+                "'{self}' expands to {count} disjuncts, past the cap of {}",
+                Self::DNF_CAP
+            )));
+        }
+
+        let mut disjuncts: Vec<BTreeSet<&Atom>> = vec![and_atoms.into_iter().collect()];
+        for atoms in or_clauses {
+            disjuncts = disjuncts
+                .iter()
+                .flat_map(|disjunct| {
+                    atoms.iter().map(|atom| {
+                        let mut disjunct = disjunct.clone();
+                        disjunct.insert(atom);
+                        disjunct
+                    })
+                })
+                .collect();
+        }
+
+        Ok(disjuncts
+            .into_iter()
+            .map(|disjunct| disjunct.into_iter().cloned().collect())
+            .collect())
+    }
+
+    /// The cost of the cheapest stat map found that satisfies the requirement. Every disjunct
+    /// of the [`Requirement::to_dnf`] is tried, sums are topped up greedily so overlapping
+    /// sums may overshoot slightly. NOT clauses cost nothing. Past [`Requirement::DNF_CAP`]
+    /// disjuncts this falls back to the greedy [`Requirement::min_statmap`]. See
+    /// [`MinCostCache`] for repeated queries.
+    #[must_use]
+    pub fn min_cost(&self) -> i64 {
+        let positive = Requirement::with_clauses(
+            self.iter()
+                .filter(|c| c.clause_type != ClauseType::Not)
+                .cloned(),
+        );
+
+        match positive.to_dnf() {
+            Ok(disjuncts) => disjuncts
+                .iter()
+                .map(|disjunct| cheapest_cost(disjunct.iter()))
+                .min()
+                .unwrap_or(0),
+            Err(_) => self.min_statmap().cost(),
+        }
+    }
+}
+
+/// Cost of the cheapest stat map meeting every one of `atoms`
+fn cheapest_cost<'a>(atoms: impl Iterator<Item = &'a Atom>) -> i64 {
+    let mut map = StatArray::new();
+    let mut power = 0;
+    let mut sums = vec![];
+
+    for atom in atoms {
+        if atom.stats.contains(&Stat::Total) {
+            power = power.max(atom.value);
+        } else if let [stat] = atom.stats.iter().collect::<Vec<_>>()[..] {
+            let cur = map.get(stat);
+            map.insert(*stat, cur.max(atom.value));
+        } else if !atom.stats.is_empty() {
+            sums.push(atom);
+        }
+    }
+
+    for atom in sums {
+        solve::satisfy_atom(&mut map, atom);
+    }
+
+    map.cost().max(power)
+}
+
+/// One clause of a [`Requirement::coverage`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClauseCoverage {
+    pub clause_type: ClauseType,
+    /// Whether the clause as a whole is met, i.e. all (`And`) or any (`Or`) of its atoms
+    pub met: bool,
+    /// Each atom's display form, and whether it's met
+    pub atoms: Vec<(bool, String)>,
+}
+
+/// Memoizes [`Requirement::min_cost`] by requirement name, so a lookup only hashes the name.
+/// Anonymous requirements aren't cached. Mutating a named requirement doesn't reach the
+/// cache, so [`MinCostCache::invalidate`] it afterwards.
+#[derive(Clone, Debug, Default)]
+pub struct MinCostCache {
+    costs: HashMap<String, i64>,
+}
+
+impl MinCostCache {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn min_cost(&mut self, req: &Requirement) -> i64 {
+        let Some(name) = &req.name else {
+            return req.min_cost();
+        };
+
+        if let Some(cost) = self.costs.get(name) {
+            return *cost;
+        }
+
+        let cost = req.min_cost();
+        self.costs.insert(name.clone(), cost);
+        cost
+    }
+
+    /// Drops the cached cost of the requirement called `name`
+    pub fn invalidate(&mut self, name: &str) {
+        self.costs.remove(name);
+    }
 }
 
 impl Default for Requirement {
@@ -494,11 +1133,22 @@ impl fmt::Display for Requirement {
         if self.is_empty() {
             write!(f, "()")
         } else {
-            let clause_strs: Vec<String> = self
-                .clauses
+            let clauses: Vec<&Clause> = self.clauses.iter().filter(|c| !c.is_empty()).collect();
+
+            // an OR next to other clauses is parenthesized so it reads back as one clause
+            let clause_strs: Vec<String> = clauses
                 .iter()
-                .filter(|clause| !clause.is_empty())
-                .map(ToString::to_string)
+                .map(|clause| {
+                    let nested = clauses.len() > 1
+                        && clause.clause_type == ClauseType::Or
+                        && clause.atoms.iter().filter(|a| !a.is_empty()).count() > 1;
+
+                    if nested {
+                        format!("({clause})")
+                    } else {
+                        clause.to_string()
+                    }
+                })
                 .collect();
 
             write!(f, "{}", clause_strs.join(", "))
@@ -533,12 +1183,23 @@ impl Serialize for Requirement {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Timing {
     Free,
     Post,
 }
 
+impl Timing {
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Timing::Free => "free",
+            Timing::Post => "post",
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -563,7 +1224,99 @@ mod tests {
         // an OR clause lowers each of its atoms
         let mut req: Requirement = "kindred_edict := 50r MED, 30r STR OR 30r FTD".parse().unwrap();
         req.add_to_stat_atoms(-3);
-        assert_eq!(req.to_string(), "kindred_edict := 47r MED, 27r STR OR 27r FTD");
+        assert_eq!(
+            req.to_string(),
+            "kindred_edict := 47r MED, (27r STR OR 27r FTD)"
+        );
+    }
+
+    #[test]
+    fn with_reducability_rewrites_every_atom() {
+        let req: Requirement = "thing := 40r HVY, 25 STR OR 25 AGL".parse().unwrap();
+
+        let strict = req.with_reducability(Reducability::Strict);
+        assert_eq!(strict.to_string(), "thing := 40s HVY, (25s STR OR 25s AGL)");
+        assert_eq!(strict.name, req.name);
+
+        let reducible = strict.with_reducability(Reducability::Reducible);
+        assert_eq!(
+            reducible.to_string(),
+            "thing := 40r HVY, (25r STR OR 25r AGL)"
+        );
+    }
+
+    #[test]
+    fn min_cost_picks_cheapest_alternative() {
+        let req: Requirement = "40 STR, 30 STR OR 20 AGL, 90 TTL".parse().unwrap();
+        assert_eq!(req.min_cost(), 90);
+
+        let req: Requirement = "40 STR, 30 STR OR 20 AGL, HVY + STR = 60".parse().unwrap();
+        assert_eq!(req.min_cost(), 60);
+
+        // the second attunement is free
+        let req: Requirement = "20 FLM, 20 ICE".parse().unwrap();
+        assert_eq!(req.min_cost(), 39);
+
+        assert_eq!(Requirement::new().min_cost(), 0);
+    }
+
+    #[test]
+    fn min_cost_cache_is_stable_until_invalidated() {
+        let mut cache = MinCostCache::new();
+        let mut req: Requirement = "x := 40 STR, 30 FTD OR 25 AGL".parse().unwrap();
+
+        assert_eq!(cache.min_cost(&req), req.min_cost());
+        assert_eq!(cache.min_cost(&req), 65);
+
+        // keyed by name, so a mutation reads the old cost until it's invalidated
+        req.add_to_all(10);
+        assert_eq!(cache.min_cost(&req), 65);
+        cache.invalidate("x");
+        assert_eq!(cache.min_cost(&req), 85);
+
+        req.add_clause(Clause::and().atom(Atom::strict().value(10).stat(Stat::Charisma)));
+        cache.invalidate("x");
+        assert_eq!(cache.min_cost(&req), 95);
+
+        // anonymous requirements are always recomputed
+        let mut anon: Requirement = "40 STR".parse().unwrap();
+        assert_eq!(cache.min_cost(&anon), 40);
+        anon.add_to_all(5);
+        assert_eq!(cache.min_cost(&anon), 45);
+    }
+
+    #[test]
+    fn min_cost_falls_back_past_the_dnf_cap() {
+        // 2^13 combinations of alternatives, past the cap
+        let clauses: Vec<String> = (0..13)
+            .map(|i| format!("{v} STR OR {v} AGL", v = 10 + i))
+            .collect();
+        let req: Requirement = clauses.join(", ").parse().unwrap();
+        assert!(req.to_dnf().is_err());
+
+        let start = std::time::Instant::now();
+        assert_eq!(req.min_cost(), 22);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+
+        // NOTs are left out of the expansion rather than failing it
+        let req: Requirement = "40 STR, 30 FTD OR 25 AGL, NOT 20 SDW".parse().unwrap();
+        assert_eq!(req.min_cost(), 65);
+    }
+
+    #[test]
+    fn with_clauses_drops_empty_clauses() {
+        let req = Requirement::with_clauses([
+            Clause::and().atom(Atom::strict().value(40).stat(Stat::HeavyWeapon)),
+            Clause::or()
+                .atom(Atom::reducible().value(25).stat(Stat::Strength))
+                .atom(Atom::reducible().value(25).stat(Stat::Agility)),
+            Clause::and().atom(Atom::strict()),
+            Clause::or(),
+        ]);
+
+        assert_eq!(req.clauses().len(), 2);
+        assert_eq!(req.to_string(), "40s HVY, (25r STR OR 25r AGL)");
+        assert_eq!(req, "40 HVY, 25r STR OR 25r AGL".parse().unwrap());
     }
 
     #[test]
@@ -572,4 +1325,351 @@ mod tests {
         req.add_to_stat_atoms(-3);
         assert_eq!(req.to_string(), "thing := 0r STR");
     }
+
+    #[test]
+    fn satisfiable_within_cap() {
+        let fits: Requirement = "100 STR, LHT + MED + HVY = 250, 150 STR OR 90 AGL"
+            .parse()
+            .unwrap();
+        assert!(fits.satisfiable_within(100));
+        assert!(!fits.satisfiable_within(80));
+
+        let over: Requirement = "STR = 150".parse().unwrap();
+        assert!(!over.satisfiable_within(100));
+
+        let over_sum: Requirement = "40 FTD, STR + AGL = 201".parse().unwrap();
+        assert!(!over_sum.satisfiable_within(100));
+    }
+
+    #[test]
+    fn power_gate_at_level() {
+        let req: Requirement = "40 STR, 200 TTL".parse().unwrap();
+        let stats = StatMap::from(HashMap::from([(Stat::Strength, 40)]));
+
+        assert!(!req.satisfied_by(&stats));
+        assert!(!req.satisfied_by_at_level(&stats, 5));
+        // 12 power is 195 points, 13 is 210
+        assert!(!req.satisfied_by_at_level(&stats, 12));
+        assert!(req.satisfied_by_at_level(&stats, 13));
+
+        // the level doesn't stand in for actual stats
+        assert!(!req.satisfied_by_at_level(&StatMap::new(), 20));
+    }
+
+    #[test]
+    fn merge_and_demands_both() {
+        let ftd: Requirement = "a => armor := 90 FTD".parse().unwrap();
+        let either: Requirement = "b => 25 STR OR 25 AGL".parse().unwrap();
+        let merged = ftd.merge_and(&either);
+
+        assert_eq!(merged.name.as_deref(), Some("armor"));
+        assert_eq!(merged.prereqs.len(), 2);
+        assert_eq!(
+            merged.to_string(),
+            "a, b => armor := 90s FTD, (25r STR OR 25r AGL)"
+        );
+
+        for (ftd_v, str_v, agl_v) in [(90, 25, 0), (90, 0, 25), (89, 25, 25), (90, 24, 24)] {
+            let stats = StatMap::from(HashMap::from([
+                (Stat::Fortitude, ftd_v),
+                (Stat::Strength, str_v),
+                (Stat::Agility, agl_v),
+            ]));
+            assert_eq!(
+                merged.satisfied_by(&stats),
+                ftd.satisfied_by(&stats) && either.satisfied_by(&stats)
+            );
+        }
+    }
+
+    #[test]
+    fn clauses_read_back() {
+        let single = Clause::and().atom(Atom::strict().stat(Stat::Strength).value(40));
+        assert!(single.try_finish().is_ok());
+
+        let multi = Clause::and()
+            .atom(Atom::strict().stat(Stat::Strength).value(40))
+            .atom(Atom::strict().stat(Stat::Agility).value(40));
+        let req = Requirement::with_clauses([multi.try_finish().unwrap()]);
+        assert_eq!(req, "40 STR AND 40 AGL".parse().unwrap());
+        assert_eq!(req, req.to_string().parse().unwrap());
+
+        let lone_or = Clause::or().atom(Atom::reducible().stat(Stat::Strength).value(40));
+        assert!(matches!(
+            lone_or.clone().try_finish(),
+            Err(error::DeepError::Clause(_))
+        ));
+        // it reads back as an AND
+        let req = Requirement::with_clauses([lone_or]);
+        assert_ne!(req, req.to_string().parse().unwrap());
+    }
+
+    #[test]
+    fn not_negates_one_atom() {
+        let str30 = Atom::strict().stat(Stat::Strength).value(30);
+        let agl30 = Atom::strict().stat(Stat::Agility).value(30);
+
+        // the builders replace a NOT's atom rather than adding a second
+        let not = Clause::not().atom(str30.clone()).atom(agl30.clone());
+        assert_eq!(not.atoms().len(), 1);
+        assert_eq!(not.to_string(), "NOT 30s AGL");
+
+        // filled by hand, a NOT per atom: met only when none of them are
+        let mut both = Clause::not();
+        both.atoms_mut().extend([str30, agl30]);
+        assert_eq!(both.to_string(), "NOT 30s STR AND NOT 30s AGL");
+        let req = Requirement::with_clauses([both]);
+        let one = StatMap::from(HashMap::from([(Stat::Strength, 30)]));
+        assert!(!req.satisfied_by(&one));
+        assert!(req.satisfied_by(&StatMap::new()));
+
+        // the shrine's rounding doesn't loosen a NOT
+        let req: Requirement = "NOT 30 STR".parse().unwrap();
+        let rounded = StatMap::from(HashMap::from([(Stat::Strength, 29)]));
+        assert!(req.satisfied_by_within(&rounded, 1));
+    }
+
+    #[test]
+    fn bladeharper_coverage() {
+        let req: Requirement = "25 STR OR 25 AGL, 75 MED OR (LHT + MED + HVY = 90)"
+            .parse()
+            .unwrap();
+        let stats = StatMap::from(HashMap::from([
+            (Stat::Agility, 30),
+            (Stat::MediumWeapon, 40),
+            (Stat::LightWeapon, 20),
+        ]));
+
+        let coverage = req.coverage(&stats);
+        assert_eq!(
+            coverage,
+            [
+                ClauseCoverage {
+                    clause_type: ClauseType::Or,
+                    met: true,
+                    atoms: vec![
+                        (false, "25r STR".to_string()),
+                        (true, "25r AGL".to_string())
+                    ],
+                },
+                ClauseCoverage {
+                    clause_type: ClauseType::Or,
+                    met: false,
+                    atoms: vec![
+                        (false, "75r MED".to_string()),
+                        (false, "HVY + MED + LHT = 90r".to_string())
+                    ],
+                },
+            ]
+        );
+        assert_eq!(coverage.iter().all(|c| c.met), req.satisfied_by(&stats));
+    }
+
+    #[test]
+    fn satisfaction_scores_progress() {
+        let req: Requirement = "40 STR, 20 AGL OR 20 FTD".parse().unwrap();
+        let full = StatMap::from(HashMap::from([(Stat::Strength, 40), (Stat::Fortitude, 30)]));
+        assert!((req.satisfaction(&full) - 1.0).abs() < f64::EPSILON);
+
+        let half = StatMap::from(HashMap::from([(Stat::Strength, 20), (Stat::Agility, 10)]));
+        assert!((req.satisfaction(&half) - 0.5).abs() < f64::EPSILON);
+
+        // an AND clause averages its atoms
+        let and: Requirement = "40 STR, 40 AGL".parse().unwrap();
+        let one_side = StatMap::from(HashMap::from([(Stat::Strength, 40)]));
+        assert!((and.satisfaction(&one_side) - 0.5).abs() < f64::EPSILON);
+
+        assert!((Requirement::new().satisfaction(&StatMap::new()) - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn dnf_expands_each_or() {
+        let req: Requirement = "40 STR, (20 FLM OR 20 ICE), (30 AGL OR 30 FTD)"
+            .parse()
+            .unwrap();
+
+        let dnf = req.to_dnf().unwrap();
+        assert_eq!(dnf.len(), 4);
+        for disjunct in &dnf {
+            assert_eq!(disjunct.len(), 3);
+            let stats = disjunct.iter().fold(StatMap::new(), |mut map, atom| {
+                let stat = *atom.stats.first().unwrap();
+                map.insert(stat, atom.value);
+                map
+            });
+            assert!(req.satisfied_by(&stats));
+        }
+
+        let empty: Requirement = "()".parse().unwrap();
+        assert_eq!(empty.to_dnf().unwrap(), [Vec::<Atom>::new()]);
+
+        // 2^13 combinations
+        let mut wide = Requirement::new();
+        *wide.clauses_mut() = (0..13)
+            .map(|i| {
+                Clause::or()
+                    .atom(Atom::reducible().stat(Stat::Strength).value(i + 1))
+                    .atom(Atom::reducible().stat(Stat::Agility).value(i + 1))
+            })
+            .collect();
+        assert!(matches!(wide.to_dnf(), Err(error::DeepError::Dnf(_))));
+    }
+
+    #[test]
+    fn lengths_skip_empty_parts() {
+        let req: Requirement = "40 STR, 20 FLM OR 20 ICE OR 20 LTN".parse().unwrap();
+        assert_eq!(req.len(), 2);
+        let or = req.or_iter().next().unwrap();
+        assert_eq!(or.len(), 3);
+
+        let mut padded = req.clone();
+        padded.add_clause(Clause::and().atom(Atom::strict()));
+        assert_eq!(padded.len(), 2);
+
+        let empty: Requirement = "()".parse().unwrap();
+        assert_eq!(empty.len(), 0);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn strict_and_reducible_split() {
+        let req: Requirement =
+            "x := 40 STR, 30r FTD, (25 FLM OR 25 ICE OR 25s LTN), 20s WND OR 20s SDW"
+                .parse()
+                .unwrap();
+
+        // the mixed OR is in neither view
+        assert_eq!(
+            req.strict_only().to_string(),
+            "x := 40s STR, (20s WND OR 20s SDW)"
+        );
+        assert_eq!(req.reducible_only().to_string(), "x := 30r FTD");
+
+        // so meeting the OR on its strict side leaves the reducible view met
+        let stats = StatMap::from(HashMap::from([
+            (Stat::Strength, 40),
+            (Stat::Fortitude, 30),
+            (Stat::Thundercall, 25),
+            (Stat::Shadowcast, 20),
+        ]));
+        assert!(req.satisfied_by(&stats));
+        assert!(req.strict_only().satisfied_by(&stats));
+        assert!(req.reducible_only().satisfied_by(&stats));
+
+        // a single-sided OR stays whole
+        let req: Requirement = "25r FLM OR 25r ICE".parse().unwrap();
+        assert_eq!(req.reducible_only(), req);
+        assert!(req.strict_only().is_empty());
+
+        assert!(
+            req.strict_only()
+                .atoms()
+                .all(|a| a.reducability == Reducability::Strict)
+        );
+        assert_eq!(
+            req.strict_only().atoms().count(),
+            req.strict_atoms().count()
+        );
+    }
+
+    #[test]
+    fn normalize_keeps_meaning() {
+        let reqs: Vec<Requirement> = [
+            "25 STR OR 30 STR",
+            "25 STR OR 30 STR OR 20 AGL, 25 STR, 30 STR",
+            "25 STR, 30 STR AND 10 AGL",
+            "25r STR OR 30s STR",
+            "STR + AGL = 40 OR STR + AGL = 50, 20 STR OR 20 AGL",
+            "40 STR, 25 STR OR 25 AGL",
+            "()",
+        ]
+        .iter()
+        .map(|r| r.parse().unwrap())
+        .collect();
+
+        for req in &reqs {
+            let normalized = req.normalized();
+            assert_eq!(
+                normalized.normalized(),
+                normalized,
+                "{req} isn't idempotent"
+            );
+
+            for str_v in (0..=50).step_by(5) {
+                for agl_v in (0..=50).step_by(5) {
+                    let stats = StatMap::from(HashMap::from([
+                        (Stat::Strength, str_v),
+                        (Stat::Agility, agl_v),
+                    ]));
+                    assert_eq!(
+                        normalized.satisfied_by(&stats),
+                        req.satisfied_by(&stats),
+                        "{req} normalized to {normalized}, at {str_v} STR {agl_v} AGL"
+                    );
+                }
+            }
+        }
+
+        let normalized: Vec<String> = reqs.iter().map(|r| r.normalized().to_string()).collect();
+        assert_eq!(
+            normalized,
+            [
+                "25r STR",
+                "30s STR, (20r AGL OR 25r STR)",
+                "10s AGL AND 30s STR",
+                "25r STR OR 30s STR",
+                "STR + AGL = 40r, (20r STR OR 20r AGL)",
+                "40s STR, (25r STR OR 25r AGL)",
+                "()",
+            ]
+        );
+    }
+
+    #[test]
+    fn min_statmap_picks_cheapest() {
+        assert!(Requirement::new().min_statmap().is_empty());
+
+        let req: Requirement = "25 STR OR 30 AGL, LHT + MED + HVY = 90, 150 TTL"
+            .parse()
+            .unwrap();
+        let map = req.min_statmap();
+
+        assert!(req.satisfied_by(&map));
+        // the power gate's remaining 35 points top up the stats already invested
+        assert_eq!(map.get(&Stat::Strength), 60);
+        assert_eq!(map.get(&Stat::Agility), 0);
+        // the sum isn't split
+        assert_eq!(
+            [Stat::LightWeapon, Stat::MediumWeapon, Stat::HeavyWeapon].map(|s| map.get(&s)),
+            [0, 0, 90]
+        );
+        assert_eq!(map.cost(), 150);
+    }
+
+    #[test]
+    fn mantra_levels_block() {
+        let levels = StatMap::from(HashMap::from([(Stat::Flamecharm, 4), (Stat::Frostdraw, 1)]));
+        let req = Requirement::from_mantra_levels(&levels);
+
+        assert_eq!(req.to_string(), "1r ICE, 60r FLM");
+        assert_eq!(req, "60r FLM, 1r ICE".parse().unwrap());
+    }
+
+    #[test]
+    fn used_stats_in_game_order() {
+        let req: Requirement = "40 WND, LHT + STR = 50, 20 CHA OR 20 AGL, 300 TTL"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            req.used_stats_sorted(),
+            [
+                Stat::Strength,
+                Stat::Agility,
+                Stat::Charisma,
+                Stat::LightWeapon,
+                Stat::Galebreathe,
+            ]
+        );
+    }
 }
@@ -1,16 +1,30 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     ops::{Add, AddAssign},
     str::FromStr,
 };
 
-use serde::{Deserialize, Deserializer, de};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
 
 use std::path::Path;
 
 use crate::{
-    error, model::data::DeepData, model::opt::OptionalGroup, model::req::Requirement,
+    Stat,
+    constants::{MAX_TOTAL, POINTS_PER_LEVEL, STAT_CAP},
+    error::{self, DeepError},
+    model::data::{
+        Aspect, DeepData, Enchant, Equipment, Mantra, Outfit, Resonance, Talent, Weapon,
+    },
+    model::opt::OptionalGroup,
+    model::req::{Atom, Clause, ClauseType, PrereqGroup, Reducability, Requirement, Timing},
     model::stat::StatRange,
+    util::{
+        algos::{self, BuildPlan},
+        lint::ReqfileLint,
+        name_to_identifier, solve,
+        statmap::StatMap,
+        traits::{ReqVecExt, positive_atoms},
+    },
 };
 
 /// The parsed representation of a reqfile
@@ -25,34 +39,100 @@ pub struct Reqfile {
 
     /// Implicit talent reqs, keyed by identifier.
     pub implicit: HashMap<String, Requirement>,
+
+    /// Requirements marked force-required (`+`), by `name_or_default`.
+    pub forced: HashSet<String>,
+
+    /// Names excluded with `- name`, removed from any reqfile this one is merged into (see
+    /// [`Reqfile::exclude`]).
+    pub excluded: HashSet<String>,
+
+    /// Build context from `@key value` directives, like `@race Khan`. See
+    /// [`Reqfile::DIRECTIVES`] for the keys that mean something.
+    pub metadata: HashMap<String, String>,
+
+    /// `# label` comments the requirements were written under, by `name_or_default`. Only
+    /// kept so [`Reqfile::generate`] can group the output the same way, they mean nothing to
+    /// the build.
+    ///
+    /// A `#` comment is a label when the line right after it is a requirement, and applies
+    /// to every requirement below it until the next label or section header. Write a `//`
+    /// comment to keep a note above a requirement from becoming a label.
+    pub labels: HashMap<String, String>,
+}
+
+/// What a reqfile requirement refers to in the game data, e.g. to show an item card for it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResolvedItem {
+    /// The requirement is named after an item in the data
+    Item {
+        /// The requirement's name as written in the reqfile
+        requirement: String,
+        namespace: &'static str,
+        /// The item's in-game name
+        name: String,
+        category: Option<String>,
+        rarity: Option<String>,
+    },
+    /// Nothing in the data matched, so the requirement is just text
+    FreeText(String),
+}
+
+/// How well a build meets a reqfile, see [`Reqfile::check_build`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildReport {
+    /// Required requirements checked (`Free:` and `Post:`)
+    pub total: usize,
+    pub satisfied: usize,
+    /// Names (or bodies, for anonymous ones) of the required requirements not met
+    pub unmet: Vec<String>,
+    /// Cost of [`Reqfile::solve`], roughly what meeting every required requirement takes
+    pub required_cost: i64,
+    /// Cost of the build that was checked
+    pub build_cost: i64,
+    /// The build spends no more than [`MAX_TOTAL`] points
+    pub within_max_total: bool,
+    pub optional_groups: usize,
+    /// Optional groups whose every member is met
+    pub optional_satisfied: usize,
+}
+
+impl BuildReport {
+    /// Every required requirement is met within the point budget
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.unmet.is_empty() && self.within_max_total
+    }
+}
+
+/// How hard a reqfile is to build, roughly, see [`Reqfile::difficulty`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DifficultyTier {
+    Easy,
+    Medium,
+    Hard,
+    /// Within a level's worth of points of [`MAX_TOTAL`], or past it
+    Maxroll,
+}
+
+/// A [`DifficultyTier`] along with the metrics it was rated on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Difficulty {
+    pub tier: DifficultyTier,
+    /// Cost of [`Reqfile::solve`]
+    pub cost: i64,
+    /// Attunements the solved build invests in
+    pub attunements: usize,
+    /// Points left over out of [`MAX_TOTAL`], negative if the reqfile can't fit
+    pub remaining: i64,
 }
 
 impl Add for Reqfile {
     type Output = Reqfile;
 
-    fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            general: self
-                .general
-                .iter()
-                .chain(rhs.general.iter())
-                .cloned()
-                .collect(),
-            post: self.post.iter().chain(rhs.post.iter()).cloned().collect(),
-            final_ranges: self
-                .final_ranges
-                .iter()
-                .chain(rhs.final_ranges.iter())
-                .cloned()
-                .collect(),
-            optional: self
-                .optional
-                .iter()
-                .chain(rhs.optional.iter())
-                .cloned()
-                .collect(),
-            implicit: self.implicit.into_iter().chain(rhs.implicit).collect(),
-        }
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += rhs;
+        self
     }
 }
 
@@ -63,14 +143,38 @@ impl AddAssign for Reqfile {
         self.final_ranges.extend(rhs.final_ranges);
         self.optional.extend(rhs.optional);
         self.implicit.extend(rhs.implicit);
+        self.forced.extend(rhs.forced);
+        for (name, label) in rhs.labels {
+            self.labels.entry(name).or_insert(label);
+        }
+
+        // the reqfile being added onto keeps its own build context
+        for (key, value) in rhs.metadata {
+            self.metadata.entry(key).or_insert(value);
+        }
+
+        for name in rhs.excluded.into_iter().chain(self.excluded.clone()) {
+            self.exclude(&name);
+        }
     }
 }
 
 impl Reqfile {
+    /// The `@` directives that are applied when planning (see [`Reqfile::plan`]): the race
+    /// (an aspect) and the outfit, by in-game name or identifier.
+    pub const DIRECTIVES: [&'static str; 2] = ["race", "outfit"];
+
     pub fn parse_str(content: &str) -> error::Result<Self> {
         crate::parse::reqfile::parse_reqfile_str(content)
     }
 
+    /// Runs the same parse and validations as [`Reqfile::parse_str`], but discards the result.
+    ///
+    /// Cheaper than a full parse, for validating on every keystroke in an editor.
+    pub fn check(content: &str) -> error::Result<()> {
+        crate::parse::reqfile::check_reqfile_str(content)
+    }
+
     pub fn resolve_implicit(&mut self, data: &DeepData) {
         self.implicit.extend(data.implicit_requirements());
     }
@@ -84,10 +188,842 @@ impl Reqfile {
         crate::parse::reqfile::gen_reqfile(self)
     }
 
+    /// A stable ID for what the reqfile asks for, e.g. as the cache key for its solve
+    /// results. Reqfiles that only differ in line order, formatting, comments or `# label`s
+    /// share an ID.
+    ///
+    /// This is a 128-bit FNV-1a hash of a canonical listing of the reqfile (hex encoded), so
+    /// it's fine for caching but not for anything adversarial.
+    #[must_use]
+    pub fn content_id(&self) -> String {
+        fn sorted<T: ToString>(section: &str, items: impl IntoIterator<Item = T>) -> String {
+            let mut items: Vec<String> = items.into_iter().map(|i| i.to_string()).collect();
+            items.sort();
+            format!("{section}\n{}\n", items.join("\n"))
+        }
+
+        let mut groups: Vec<String> = self
+            .optional
+            .iter()
+            .map(|g| {
+                format!(
+                    "{}{}{}",
+                    g.weight,
+                    sorted("free", &g.general),
+                    sorted("post", &g.post)
+                )
+            })
+            .collect();
+        groups.sort();
+
+        let canonical = [
+            sorted("free", &self.general),
+            sorted("post", &self.post),
+            sorted(
+                "ranges",
+                self.final_ranges
+                    .iter()
+                    .map(|r| format!("{:?} {}..={}", r.stat, r.range.start(), r.range.end())),
+            ),
+            sorted("optional", groups),
+            sorted(
+                "implicit",
+                self.implicit.iter().map(|(k, req)| format!("{k} {req}")),
+            ),
+            sorted("forced", &self.forced),
+            sorted("excluded", &self.excluded),
+            sorted(
+                "metadata",
+                self.metadata.iter().map(|(k, v)| format!("{k} {v}")),
+            ),
+        ]
+        .concat();
+
+        format!("{:032x}", fnv1a_128(canonical.as_bytes()))
+    }
+
+    /// Whether the requirement was force-required with a `+` line, rather than being required
+    /// on its own. `name` is the requirement's `name_or_default`.
+    #[must_use]
+    pub fn is_forced(&self, name: &str) -> bool {
+        self.forced.contains(name)
+    }
+
+    /// Soft warnings about things that are valid, but likely unintended.
+    #[must_use]
+    pub fn lint(&self) -> Vec<ReqfileLint> {
+        crate::util::lint::lint_reqfile(self)
+    }
+
+    /// [`Reqfile::lint`], plus lints that need the data to tell what requirements refer to.
+    ///
+    /// Pass `allow_weapons_preshrine` when weapons are meant to be in `Free:`, like
+    /// [`BuildConfig::allow_weapons_preshrine`](crate::util::algos::BuildConfig).
+    #[must_use]
+    pub fn lint_with_data(
+        &self,
+        data: &DeepData,
+        allow_weapons_preshrine: bool,
+    ) -> Vec<ReqfileLint> {
+        crate::util::lint::lint_reqfile_with_data(self, data, allow_weapons_preshrine)
+    }
+
+    /// Matches each requirement (including optional ones) to the data item it is named after.
+    ///
+    /// Names may be qualified (`talent:neuroplasticity`) or bare, in which case talents,
+    /// mantras, weapons, equipment, outfits, enchants and resonances are tried in that order.
+    #[must_use]
+    pub fn resolve_items(&self, data: &DeepData) -> Vec<ResolvedItem> {
+        let mut seen = HashSet::new();
+
+        self.req_iter()
+            .chain(self.optional_req_iter())
+            .filter(|req| seen.insert(req.name_or_default()))
+            .map(|req| {
+                req.name
+                    .as_deref()
+                    .and_then(|name| resolve_item(data, name))
+                    .unwrap_or_else(|| ResolvedItem::FreeText(req.name_or_default()))
+            })
+            .collect()
+    }
+
+    /// The aspect named by `@race`, if any.
+    pub fn race<'a>(&self, data: &'a DeepData) -> error::Result<Option<&'a Aspect>> {
+        self.metadata
+            .get("race")
+            .map(|race| {
+                data.get_aspect(&name_to_identifier(race))
+                    .ok_or_else(|| DeepError::ReqfileBuild(format!("Race not found: {race}")))
+            })
+            .transpose()
+    }
+
+    /// The outfit named by `@outfit`, if any, along with its identifier.
+    pub fn outfit<'a>(&self, data: &'a DeepData) -> error::Result<Option<(String, &'a Outfit)>> {
+        self.metadata
+            .get("outfit")
+            .map(|outfit| {
+                let key = name_to_identifier(outfit);
+                data.get_outfit(&key)
+                    .map(|o| (key, o))
+                    .ok_or_else(|| DeepError::ReqfileBuild(format!("Outfit not found: {outfit}")))
+            })
+            .transpose()
+    }
+
+    /// Plans the build (see [`BuildConfig::plan`](crate::util::algos::BuildConfig::plan))
+    /// with the reqfile's own build context, the same way a
+    /// [`BuildConfig`](crate::util::algos::BuildConfig) with that race and outfit would: the
+    /// `@race`'s innates are there from the start (and a Khan's lower the weapon and
+    /// equipment requirements), and the `@outfit`'s requirement joins the `Free:` ones.
+    pub fn plan(&self, data: &DeepData) -> error::Result<BuildPlan> {
+        let mut reqfile = self.clone();
+
+        if let Some((key, outfit)) = self.outfit(data)? {
+            let name = format!("{}:{key}", Outfit::NAMESPACE);
+            if !reqfile
+                .req_iter()
+                .any(|r| r.name.as_deref() == Some(name.as_str()))
+            {
+                let mut req = outfit.requirement(&key);
+                req.prereqs.clear();
+                req.name = Some(name);
+                reqfile.general.push(req);
+            }
+        }
+
+        let race = self.race(data)?;
+        if race.is_some_and(algos::is_khan) {
+            for req in reqfile.general.iter_mut().chain(&mut reqfile.post) {
+                let name = req.name_or_default();
+                algos::khan_reduce(&name, req);
+            }
+        }
+
+        Ok(algos::plan_reqfile(reqfile, race))
+    }
+
+    /// A cheap stat map meeting every required requirement, see [`solve::min_statmap`] and
+    /// [`Requirement::min_statmap`] for a single one.
+    #[must_use]
+    pub fn solve(&self) -> StatMap {
+        solve::min_statmap(self.req_iter())
+    }
+
+    /// Like [`Reqfile::solve`], but building on top of `fixed` (innates, points that won't be
+    /// respecced), only adding points where the requirements demand more.
+    ///
+    /// Errors like [`Reqfile::solve_with_attribution`] if the result couldn't be had in game.
+    pub fn solve_from(&self, fixed: &StatMap) -> error::Result<StatMap> {
+        let stats = solve::min_statmap_from(fixed, self.req_iter());
+        check_solved(self.req_iter(), &stats)?;
+        Ok(stats)
+    }
+
+    /// Like [`Reqfile::solve`], but first lowering every [reducible](Reducability::Reducible)
+    /// atom by up to its stat's cap in `budget`, e.g. what a Shrine of Mastery lets each stat
+    /// drop by. Stats missing from `budget` aren't lowered, and atoms never go below 0.
+    ///
+    /// Only single-stat atoms are lowered: a sum or a [`Stat::Total`] gate has no one stat to
+    /// charge it to. `NOT` clauses are left alone, lowering their threshold would only make
+    /// them harder to meet.
+    #[must_use]
+    pub fn solve_with_reduction(&self, budget: &HashMap<Stat, i64>) -> StatMap {
+        let reduced: Vec<Requirement> = self
+            .req_iter()
+            .map(|req| reduce_within(req, budget))
+            .collect();
+        solve::min_statmap(reduced.iter())
+    }
+
+    /// How high each stat may have to go for the required requirements, assuming the worst
+    /// OR branch is picked every time: unlike [`Reqfile::solve`], every alternative counts, so
+    /// this is an upper bound for planning rather than a build.
+    ///
+    /// - A single-stat atom asks for its value of that stat.
+    /// - A sum atom (`FLM + ICE = 40`) asks each of its stats for whatever the others' own
+    ///   floors leave of its value, so a stat only ever seen in sums gets the whole sum.
+    /// - [`Stat::Total`] gates are kept as the highest gate, under [`Stat::Total`].
+    ///
+    /// `NOT` clauses are skipped, they never call for points. Unlike
+    /// [`ReqIterExt::max_map`](crate::util::traits::ReqIterExt::max_map), sum atoms and
+    /// [`Stat::Total`] are accounted for, and the optional groups are left out.
+    #[must_use]
+    pub fn stat_envelope(&self) -> StatMap {
+        let atoms: Vec<&Atom> = self.req_iter().flat_map(positive_atoms).collect();
+
+        let mut envelope = StatMap::new();
+        for atom in &atoms {
+            if let [stat] = atom.stats.iter().collect::<Vec<_>>()[..] {
+                let value = envelope.get(stat).max(atom.value);
+                envelope.insert(*stat, value);
+            }
+        }
+
+        let mut sums = StatMap::new();
+        let mut total = 0;
+        for atom in &atoms {
+            if atom.stats.contains(&Stat::Total) {
+                total = total.max(atom.value);
+                continue;
+            }
+            if atom.stats.len() < 2 {
+                continue;
+            }
+            for stat in &atom.stats {
+                let others: i64 = atom
+                    .stats
+                    .iter()
+                    .filter(|other| *other != stat)
+                    .map(|other| envelope.get(other))
+                    .sum();
+                let share = sums.get(stat).max(atom.value - others);
+                sums.insert(*stat, share);
+            }
+        }
+
+        for (stat, share) in sums.iter() {
+            let value = envelope.get(stat).max(*share);
+            envelope.insert(*stat, value);
+        }
+        if total > 0 {
+            envelope.insert(Stat::Total, total);
+        }
+        envelope.retain(|_, value| *value > 0);
+        envelope
+    }
+
+    /// The average [`Requirement::satisfaction`] of the required requirements, 1 when
+    /// `stats` meets them all (or there are none). E.g. to rank candidate builds by how close
+    /// they get.
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "a reqfile has a handful of requirements"
+    )]
+    pub fn satisfaction(&self, stats: &StatMap) -> f64 {
+        let scores: Vec<f64> = self.req_iter().map(|req| req.satisfaction(stats)).collect();
+        if scores.is_empty() {
+            return 1.0;
+        }
+        scores.iter().sum::<f64>() / scores.len() as f64
+    }
+
+    /// The requirements `pre` meets that its Shrine of Order result ([`StatMap::shrine_order`])
+    /// no longer does. The shrine floors every stat it evens out, so a stat is allowed to end
+    /// up 1 point short; anything listed was actually broken by the shrine and belongs in
+    /// `Post:`.
+    #[must_use]
+    pub fn verify_after_shrine(&self, pre: &StatMap, racial: &StatMap) -> Vec<&Requirement> {
+        let shrined = pre.shrine_order(racial);
+        self.req_iter()
+            .filter(|req| req.satisfied_by(pre) && !req.satisfied_by_within(&shrined, 1))
+            .collect()
+    }
+
+    /// Like [`Reqfile::solve`], but also says which requirement pinned each stat: the one that
+    /// stops being met if that stat drops by a point, the first by name on ties. Anonymous
+    /// requirements go by their body. Stats nothing pins on its own (e.g. one side of an OR
+    /// that another stat also meets) are left out.
+    ///
+    /// Errors if the solved stats couldn't be had in game: a stat past [`STAT_CAP`], or a
+    /// requirement left unmet (a power gate past what every stat capped adds up to).
+    pub fn solve_with_attribution(&self) -> error::Result<(StatMap, HashMap<Stat, String>)> {
+        let stats = self.solve();
+        check_solved(self.req_iter(), &stats)?;
+
+        let mut attribution = HashMap::new();
+        for (stat, value) in stats.iter() {
+            let mut lowered = stats.clone();
+            lowered.insert(*stat, value - 1);
+
+            let binding = self
+                .req_iter()
+                .filter(|req| !req.satisfied_by(&lowered))
+                .map(Requirement::name_or_default)
+                .min();
+
+            if let Some(name) = binding {
+                attribution.insert(*stat, name);
+            }
+        }
+
+        Ok((stats, attribution))
+    }
+
+    /// Rates how hard the reqfile is to build, for sorting builds by it. Goes off the solved
+    /// cost, bumped up a tier for every attunement past the first, since each one is another
+    /// set of mantras to level.
+    #[must_use]
+    pub fn difficulty(&self) -> Difficulty {
+        let stats = self.solve();
+        let cost = stats.cost();
+        let attunements = stats.attunement_count();
+        let remaining = MAX_TOTAL - cost;
+
+        let tier = if remaining <= POINTS_PER_LEVEL {
+            DifficultyTier::Maxroll
+        } else {
+            let by_cost = match cost {
+                c if c >= MAX_TOTAL * 2 / 3 => 2,
+                c if c >= MAX_TOTAL / 3 => 1,
+                _ => 0,
+            };
+
+            match by_cost + attunements.saturating_sub(1) {
+                0 => DifficultyTier::Easy,
+                1 => DifficultyTier::Medium,
+                _ => DifficultyTier::Hard,
+            }
+        };
+
+        Difficulty {
+            tier,
+            cost,
+            attunements,
+            remaining,
+        }
+    }
+
+    /// Past this many optional groups [`Reqfile::max_feasible_optionals`] stops enumerating
+    /// and greedily builds a single combination instead
+    pub const MAX_ENUMERATED_OPTIONALS: usize = 16;
+
+    /// The largest combinations of optional groups (indices into `optional`, ascending) that
+    /// can be built alongside the required reqs: solved together they stay within
+    /// [`MAX_TOTAL`] and [`STAT_CAP`]. No other group can be added to a returned combination
+    /// without breaking it. Empty if the required reqs don't fit on their own.
+    ///
+    /// The feasibility layer under an optimizer choosing optional groups by weight. Walks
+    /// combinations depth-first, never extending one that already doesn't fit, and taking
+    /// every remaining group at once when they all fit, so it's only exponential in how many
+    /// groups conflict. With more than [`Reqfile::MAX_ENUMERATED_OPTIONALS`] groups it returns
+    /// one combination, adding groups heaviest first while they fit.
+    #[must_use]
+    pub fn max_feasible_optionals(&self) -> Vec<Vec<usize>> {
+        let fits = |combo: &[usize]| {
+            let members: Vec<&Requirement> = combo
+                .iter()
+                .map(|i| &self.optional[*i])
+                .flat_map(|group| group.general.iter().chain(&group.post))
+                .collect();
+            let reqs = || self.req_iter().chain(members.iter().copied());
+
+            let stats = solve::min_statmap(reqs());
+            stats.cost() <= MAX_TOTAL
+                && stats.values().all(|v| *v <= STAT_CAP)
+                && reqs().all(|req| req.satisfied_by(&stats))
+        };
+
+        if !fits(&[]) {
+            return vec![];
+        }
+
+        if self.optional.len() > Self::MAX_ENUMERATED_OPTIONALS {
+            let mut by_weight: Vec<usize> = (0..self.optional.len()).collect();
+            by_weight.sort_by_key(|i| std::cmp::Reverse(self.optional[*i].weight));
+
+            let mut combo = vec![];
+            for i in by_weight {
+                combo.push(i);
+                if !fits(&combo) {
+                    combo.pop();
+                }
+            }
+            combo.sort_unstable();
+            return vec![combo];
+        }
+
+        let mut found = vec![];
+        let mut stack: Vec<(usize, Vec<usize>)> = vec![(0, vec![])];
+        while let Some((next, combo)) = stack.pop() {
+            if next < self.optional.len() {
+                // nothing left to choose if the rest all fit
+                let mut rest = combo.clone();
+                rest.extend(next..self.optional.len());
+                if fits(&rest) {
+                    stack.push((self.optional.len(), rest));
+                    continue;
+                }
+
+                stack.push((next + 1, combo.clone()));
+
+                let mut with = combo;
+                with.push(next);
+                if fits(&with) {
+                    stack.push((next + 1, with));
+                }
+                continue;
+            }
+
+            let maximal = (0..self.optional.len())
+                .filter(|i| !combo.contains(i))
+                .all(|i| {
+                    let mut extended = combo.clone();
+                    extended.push(i);
+                    extended.sort_unstable();
+                    !fits(&extended)
+                });
+
+            if maximal {
+                found.push(combo);
+            }
+        }
+
+        found.sort();
+        found
+    }
+
+    /// Checks `stats` against every requirement at once, e.g. for a "validate my build"
+    /// button. Timing isn't considered, `stats` is taken as the final build.
+    #[must_use]
+    pub fn check_build(&self, stats: &StatMap) -> BuildReport {
+        let unmet: Vec<String> = self
+            .req_iter()
+            .filter(|req| !req.satisfied_by(stats))
+            .map(Requirement::name_or_default)
+            .collect();
+
+        let total = self.req_iter().count();
+        let build_cost = stats.cost();
+
+        BuildReport {
+            total,
+            satisfied: total - unmet.len(),
+            unmet,
+            required_cost: self.solve().cost(),
+            build_cost,
+            within_max_total: build_cost <= MAX_TOTAL,
+            optional_groups: self.optional.len(),
+            optional_satisfied: self
+                .optional
+                .iter()
+                .filter(|g| {
+                    g.general
+                        .iter()
+                        .chain(&g.post)
+                        .all(|r| r.satisfied_by(stats))
+                })
+                .count(),
+        }
+    }
+
+    /// How many points adding `req` costs on top of `base_solution`, this reqfile's
+    /// [`Reqfile::solve`], e.g. for an "adding this weapon costs +12" hint. Taking the
+    /// solution rather than solving again lets a caller pricing many candidates solve once.
+    /// Errors if `req` depends on something the reqfile doesn't have.
+    pub fn cost_to_add(&self, base_solution: &StatMap, req: &Requirement) -> error::Result<i64> {
+        let known: HashSet<String> = self
+            .req_iter()
+            .chain(self.optional_req_iter())
+            .map(Requirement::name_or_default)
+            .chain(self.implicit.keys().cloned())
+            .collect();
+
+        if let Some(missing) = req
+            .prereqs
+            .iter()
+            .find(|g| !g.alternatives().any(|name| known.contains(name)))
+        {
+            return Err(DeepError::ReqfileBuild(format!(
+                "'{}' depends on {missing}, which is not in the reqfile",
+                req.name_or_default()
+            )));
+        }
+
+        Ok(solve::min_statmap_from(base_solution, [req]).cost() - base_solution.cost())
+    }
+
+    /// Adjusts `base_solution`, a solution to this reqfile's required requirements, after
+    /// `changed` is added (`added`) or removed, rather than solving again from scratch. E.g.
+    /// for an editor toggling requirements on and off.
+    ///
+    /// Adding tops `base_solution` up until `changed` is met. Removing clears the stats
+    /// `changed` uses and tops them back up for what's left, every other stat is kept (a
+    /// requirement gating on [`Stat::Total`] touches every stat, so that re-solves fully).
+    /// `changed` is matched by `name_or_default`, whether or not it's in the reqfile.
+    ///
+    /// The result meets every requirement, but unlike [`Reqfile::solve`] isn't necessarily
+    /// the cheapest map that does. Errors like [`Reqfile::solve_from`] if it can't be had in
+    /// game.
+    pub fn resolve_after_change(
+        &self,
+        base_solution: &StatMap,
+        changed: &Requirement,
+        added: bool,
+    ) -> error::Result<StatMap> {
+        let key = changed.name_or_default();
+        let others: Vec<&Requirement> = self
+            .req_iter()
+            .filter(|req| req.name_or_default() != key)
+            .collect();
+
+        let gates_total = changed
+            .atoms()
+            .any(|atom| atom.stats.contains(&Stat::Total));
+        let stats = if added {
+            solve::min_statmap_from(base_solution, [changed])
+        } else if gates_total {
+            solve::min_statmap(others.iter().copied())
+        } else {
+            let mut floor = base_solution.clone();
+            for stat in changed.used_stats() {
+                floor.remove(&stat);
+            }
+            solve::min_statmap_from(&floor, others.iter().copied())
+        };
+
+        let mut reqs = others;
+        if added {
+            reqs.push(changed);
+        }
+        check_solved(reqs, &stats)?;
+        Ok(stats)
+    }
+
+    /// Renames every requirement with `f`, across both sections, the optional groups and the
+    /// implicit reqs, along with every prereq reference to it. E.g. prefixing a preset's names
+    /// before merging it so they can't collide. Anonymous requirements are left alone.
+    pub fn rename(&mut self, f: impl Fn(&str) -> String) {
+        let named: HashSet<String> = self
+            .req_iter()
+            .chain(self.optional_req_iter())
+            .filter_map(|r| r.name.clone())
+            .collect();
+
+        self.general.map_names(&f);
+        self.post.map_names(&f);
+
+        for group in &mut self.optional {
+            for set in [&mut group.general, &mut group.post] {
+                let mut reqs: Vec<Requirement> = set.drain().collect();
+                reqs.map_names(&f);
+                set.extend(reqs);
+            }
+        }
+
+        self.implicit = std::mem::take(&mut self.implicit)
+            .into_iter()
+            .map(|(name, req)| {
+                let mut reqs = vec![req];
+                reqs.map_names(&f);
+                (f(&name), reqs.remove(0))
+            })
+            .collect();
+
+        self.forced = std::mem::take(&mut self.forced)
+            .into_iter()
+            .map(|name| {
+                if named.contains(&name) {
+                    f(&name)
+                } else {
+                    name
+                }
+            })
+            .collect();
+
+        self.excluded = self.excluded.iter().map(|name| f(name)).collect();
+
+        self.labels = std::mem::take(&mut self.labels)
+            .into_iter()
+            .map(|(name, label)| {
+                if named.contains(&name) {
+                    (f(&name), label)
+                } else {
+                    (name, label)
+                }
+            })
+            .collect();
+    }
+
+    /// Removes the requirement named `name` wherever it is, required or optional, and
+    /// remembers the exclusion so later merges (`+=`) drop it too. Dependents are kept, they
+    /// just no longer need it: it's taken out of their prereq groups, and groups left with no
+    /// alternatives are dropped.
+    pub fn exclude(&mut self, name: &str) {
+        let keep = |req: &Requirement| req.name.as_deref() != Some(name);
+        let unlink = |req: &mut Requirement| {
+            req.prereqs = std::mem::take(&mut req.prereqs)
+                .into_iter()
+                .map(|g| PrereqGroup::any(g.alternatives().filter(|alt| *alt != name)))
+                .filter(|g| g.alternatives().next().is_some())
+                .collect();
+        };
+
+        for reqs in [&mut self.general, &mut self.post] {
+            reqs.retain(keep);
+            reqs.iter_mut().for_each(unlink);
+        }
+
+        for group in &mut self.optional {
+            for set in [&mut group.general, &mut group.post] {
+                *set = set
+                    .drain()
+                    .filter(keep)
+                    .map(|mut req| {
+                        unlink(&mut req);
+                        req
+                    })
+                    .collect();
+            }
+        }
+        self.optional
+            .retain(|group| !group.general.is_empty() || !group.post.is_empty());
+
+        self.forced.remove(name);
+        self.excluded.insert(name.to_string());
+    }
+
+    /// Just the `Free:` half of the reqfile, optional groups keeping only their free members.
+    /// Lets the pre-shrine build be solved on its own.
+    #[must_use]
+    pub fn free_only(&self) -> Reqfile {
+        self.only(Timing::Free)
+    }
+
+    /// Just the `Post:` half of the reqfile (final ranges included), optional groups keeping
+    /// only their post members.
+    #[must_use]
+    pub fn post_only(&self) -> Reqfile {
+        self.only(Timing::Post)
+    }
+
+    fn only(&self, timing: Timing) -> Reqfile {
+        let (general, post, final_ranges) = match timing {
+            Timing::Free => (self.general.clone(), vec![], vec![]),
+            Timing::Post => (vec![], self.post.clone(), self.final_ranges.clone()),
+        };
+
+        let optional: Vec<OptionalGroup> = self
+            .optional
+            .iter()
+            .map(|group| {
+                let mut group = group.clone();
+                match timing {
+                    Timing::Free => group.post.clear(),
+                    Timing::Post => group.general.clear(),
+                }
+                group
+            })
+            .filter(|group| !group.general.is_empty() || !group.post.is_empty())
+            .collect();
+
+        let forced = general
+            .iter()
+            .chain(&post)
+            .map(Requirement::name_or_default)
+            .filter(|name| self.forced.contains(name))
+            .collect();
+
+        Reqfile {
+            general,
+            post,
+            final_ranges,
+            optional,
+            implicit: self.implicit.clone(),
+            forced,
+            excluded: self.excluded.clone(),
+            metadata: self.metadata.clone(),
+            labels: self.labels.clone(),
+        }
+    }
+
     /// Retrieve an iterator containing the required requirements
     pub fn req_iter(&self) -> impl Iterator<Item = &Requirement> {
         self.general.iter().chain(self.post.iter())
     }
+
+    /// Retrieve an iterator over the members of every optional group. Members shared by
+    /// several groups come up once per group.
+    pub fn optional_req_iter(&self) -> impl Iterator<Item = &Requirement> {
+        self.optional
+            .iter()
+            .flat_map(|g| g.general.iter().chain(g.post.iter()))
+    }
+
+    /// Merges optional groups with exactly the same members, e.g. after `+=` brings in a
+    /// preset that shares groups with this reqfile. Meeting one of them always meets the
+    /// others, so the merged group's weight is their sum (capped at the top of
+    /// [`OptionalGroup::WEIGHT_RANGE`]), and the builds that can be expressed stay the same.
+    ///
+    /// Groups that are a strict subset of another are kept: merging them into the bigger one
+    /// would lose the builds that only take the smaller.
+    pub fn coalesce_optional(&mut self) {
+        let mut merged: Vec<OptionalGroup> = vec![];
+
+        for group in std::mem::take(&mut self.optional) {
+            match merged
+                .iter_mut()
+                .find(|g| g.general == group.general && g.post == group.post)
+            {
+                Some(existing) => {
+                    existing.weight =
+                        (existing.weight + group.weight).min(*OptionalGroup::WEIGHT_RANGE.end());
+                }
+                None => merged.push(group),
+            }
+        }
+
+        self.optional = merged;
+    }
+
+    /// Pairs `(redundant, by)` of indices into [`Reqfile::req_iter`], where every stat map
+    /// meeting requirement `by` also meets `redundant`, e.g. `25 STR` next to `40 STR`. Only
+    /// requirements in the same section are compared, and optional ones never are. Of two
+    /// requirements that meet each other, the later one is the redundant one.
+    ///
+    /// A requirement is checked against the minimum stat map of each way of meeting `by`
+    /// (see [`Requirement::to_dnf`]). That's exact for single stat atoms, but a `by` summing
+    /// stats or gating on [`Stat::Total`] can be met in too many ways to tell, so it's never
+    /// counted as covering anything.
+    #[must_use]
+    pub fn redundant_requirements(&self) -> Vec<(usize, usize)> {
+        let offset = self.general.len();
+        let mut pairs = redundant_within(&self.general);
+        pairs.extend(
+            redundant_within(&self.post)
+                .into_iter()
+                .map(|(i, j)| (i + offset, j + offset)),
+        );
+        pairs
+    }
+
+    /// Drops the anonymous requirements [`Reqfile::redundant_requirements`] reports. Named
+    /// ones are kept even when redundant, since they stand for something (a talent, an item)
+    /// and others may depend on them.
+    pub fn prune_redundant(&mut self) {
+        let redundant: HashSet<usize> = self
+            .redundant_requirements()
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut i = 0;
+        let mut keep = |req: &Requirement| {
+            i += 1;
+            req.name.is_some() || !redundant.contains(&(i - 1))
+        };
+        self.general.retain(&mut keep);
+        self.post.retain(&mut keep);
+    }
+
+    /// Same as [`Reqfile::req_iter`], with each requirement tagged by the section it's in
+    pub fn timed_req_iter(&self) -> impl Iterator<Item = (Timing, &Requirement)> {
+        let general = self.general.iter().map(|r| (Timing::Free, r));
+        general.chain(self.post.iter().map(|r| (Timing::Post, r)))
+    }
+
+    /// How many requirements [`Reqfile::req_iter`] yields
+    #[must_use]
+    pub fn requirement_count(&self) -> usize {
+        self.general.len() + self.post.len()
+    }
+
+    /// The `index`th requirement of [`Reqfile::timed_req_iter`], without walking the ones
+    /// before it
+    #[must_use]
+    pub fn requirement_at(&self, index: usize) -> Option<(Timing, &Requirement)> {
+        match index.checked_sub(self.general.len()) {
+            None => Some((Timing::Free, &self.general[index])),
+            Some(index) => self.post.get(index).map(|r| (Timing::Post, r)),
+        }
+    }
+
+    /// Flattens the required requirements into a predicate that checks whether a stat map
+    /// meets all of them.
+    ///
+    /// Single stat atoms of AND clauses are merged into one threshold per stat, so the
+    /// returned closure walks a flat list rather than every requirement's clause tree.
+    /// Prefer this over [`Requirement::satisfied_by`] when checking many candidate builds.
+    pub fn compile(&self) -> impl Fn(&StatMap) -> bool + use<> {
+        let mut mins: BTreeMap<Stat, i64> = BTreeMap::new();
+        let mut sums: BTreeSet<Atom> = BTreeSet::new();
+        let mut ors: BTreeSet<BTreeSet<Atom>> = BTreeSet::new();
+        let mut nots: BTreeSet<Atom> = BTreeSet::new();
+
+        for clause in self.req_iter().flat_map(Requirement::iter) {
+            match clause.clause_type {
+                ClauseType::And => {
+                    for atom in &clause.atoms {
+                        if let Some(stat) = atom.stats.first()
+                            && atom.stats.len() == 1
+                        {
+                            mins.entry(*stat)
+                                .and_modify(|cur| *cur = (*cur).max(atom.value))
+                                .or_insert(atom.value);
+                        } else {
+                            sums.insert(atom.clone());
+                        }
+                    }
+                }
+                ClauseType::Or => {
+                    ors.insert(clause.atoms.clone());
+                }
+                ClauseType::Not => nots.extend(clause.atoms.iter().cloned()),
+            }
+        }
+
+        let mins: Vec<(Stat, i64)> = mins.into_iter().collect();
+        let sums: Vec<Atom> = sums.into_iter().collect();
+        let ors: Vec<Vec<Atom>> = ors.into_iter().map(|o| o.into_iter().collect()).collect();
+        let nots: Vec<Atom> = nots.into_iter().collect();
+
+        move |stats: &StatMap| {
+            mins.iter().all(|(stat, min)| {
+                let value = if *stat == Stat::Total {
+                    stats.cost()
+                } else {
+                    stats.get(stat)
+                };
+                value >= *min
+            }) && sums.iter().all(|atom| atom.satisfied_by(stats))
+                && ors
+                    .iter()
+                    .all(|atoms| atoms.iter().any(|atom| atom.satisfied_by(stats)))
+                && nots.iter().all(|atom| !atom.satisfied_by(stats))
+        }
+    }
 }
 
 impl FromStr for Reqfile {
@@ -108,3 +1044,958 @@ impl<'de> Deserialize<'de> for Reqfile {
         s.parse().map_err(de::Error::custom)
     }
 }
+
+/// As reqfile text, the counterpart of the [`Deserialize`] impl
+impl Serialize for Reqfile {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.generate())
+    }
+}
+
+/// See [`Reqfile::solve_with_reduction`]
+fn reduce_within(req: &Requirement, budget: &HashMap<Stat, i64>) -> Requirement {
+    let clauses = req
+        .clauses
+        .iter()
+        .map(|clause| {
+            if clause.clause_type == ClauseType::Not {
+                return clause.clone();
+            }
+            let atoms = clause
+                .atoms
+                .iter()
+                .cloned()
+                .map(|mut atom| {
+                    if atom.reducability == Reducability::Reducible
+                        && let [stat] = atom.stats.iter().collect::<Vec<_>>()[..]
+                    {
+                        let cap = budget.get(stat).copied().unwrap_or(0).max(0);
+                        atom.value = (atom.value - cap).max(0);
+                    }
+                    atom
+                })
+                .collect();
+            Clause {
+                clause_type: clause.clause_type.clone(),
+                atoms,
+            }
+        })
+        .collect();
+
+    Requirement {
+        name: req.name.clone(),
+        prereqs: req.prereqs.clone(),
+        clauses,
+    }
+}
+
+/// See [`Reqfile::redundant_requirements`], for one section
+fn redundant_within(reqs: &[Requirement]) -> Vec<(usize, usize)> {
+    let mut pairs = vec![];
+
+    for (i, redundant) in reqs.iter().enumerate() {
+        for (j, by) in reqs.iter().enumerate() {
+            if i == j || !implies(by, redundant) {
+                continue;
+            }
+            // requirements that meet each other: only the later one goes
+            if i < j && implies(redundant, by) {
+                continue;
+            }
+            pairs.push((i, j));
+        }
+    }
+
+    pairs
+}
+
+/// Whether every map meeting `by` meets `req`
+fn implies(by: &Requirement, req: &Requirement) -> bool {
+    // raising stats can break a NOT, so meeting it at the minimum says nothing
+    if req.not_iter().next().is_some() {
+        return false;
+    }
+    let Ok(disjuncts) = by.to_dnf() else {
+        return false;
+    };
+
+    disjuncts.iter().all(|atoms| {
+        let mut floor = StatMap::new();
+        for atom in atoms {
+            let [stat] = atom.stats.iter().collect::<Vec<_>>()[..] else {
+                return false;
+            };
+            if *stat == Stat::Total {
+                return false;
+            }
+            let value = floor.get(stat).max(atom.value);
+            floor.insert(*stat, value);
+        }
+        req.satisfied_by(&floor)
+    })
+}
+
+fn fnv1a_128(bytes: &[u8]) -> u128 {
+    const OFFSET: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+    bytes.iter().fold(OFFSET, |hash, byte| {
+        (hash ^ u128::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Errors if `stats` misses any of `reqs`, or puts a stat past [`STAT_CAP`]
+fn check_solved<'a>(
+    reqs: impl IntoIterator<Item = &'a Requirement>,
+    stats: &StatMap,
+) -> error::Result<()> {
+    if let Some(unmet) = reqs.into_iter().find(|req| !req.satisfied_by(stats)) {
+        return Err(DeepError::ReqfileBuild(format!(
+            "'{}' can't be met",
+            unmet.name_or_default()
+        )));
+    }
+
+    if let Some((stat, value)) = stats.iter().find(|(_, v)| **v > STAT_CAP) {
+        return Err(DeepError::ReqfileBuild(format!(
+            "{} would need {value} points, past the cap of {STAT_CAP}",
+            stat.name()
+        )));
+    }
+
+    Ok(())
+}
+
+fn resolve_item(data: &DeepData, requirement: &str) -> Option<ResolvedItem> {
+    let (namespace, key) = match requirement.split_once(':') {
+        Some((namespace, key)) => (Some(namespace), key),
+        None => (None, requirement),
+    };
+    let wants = |ns: &str| namespace.is_none_or(|n| n == ns);
+
+    let item = |namespace, name: &String, category: Option<&str>, rarity: Option<&str>| {
+        ResolvedItem::Item {
+            requirement: requirement.to_string(),
+            namespace,
+            name: name.clone(),
+            category: category.map(ToString::to_string),
+            rarity: rarity.map(ToString::to_string),
+        }
+    };
+
+    if wants(Talent::NAMESPACE)
+        && let Some(t) = data.get_talent(key)
+    {
+        return Some(item(
+            Talent::NAMESPACE,
+            &t.name,
+            Some(&t.category),
+            Some(t.rarity.name()),
+        ));
+    }
+    if wants(Mantra::NAMESPACE)
+        && let Some(m) = data.get_mantra(key)
+    {
+        return Some(item(Mantra::NAMESPACE, &m.name, Some(&m.category), None));
+    }
+    if wants(Weapon::NAMESPACE)
+        && let Some(w) = data.get_weapon(key)
+    {
+        let category = Some(w.weapon_type.name());
+        return Some(item(
+            Weapon::NAMESPACE,
+            &w.name,
+            category,
+            Some(w.rarity.name()),
+        ));
+    }
+    if wants(Equipment::NAMESPACE)
+        && let Some(e) = data.get_equipment(key)
+    {
+        let category = Some(e.equipment_type.name());
+        return Some(item(
+            Equipment::NAMESPACE,
+            &e.name,
+            category,
+            Some(e.rarity.name()),
+        ));
+    }
+    if wants(Outfit::NAMESPACE)
+        && let Some(o) = data.get_outfit(key)
+    {
+        return Some(item(Outfit::NAMESPACE, &o.name, Some(&o.category), None));
+    }
+    if wants(Enchant::NAMESPACE)
+        && let Some(e) = data.get_enchant(key)
+    {
+        return Some(item(Enchant::NAMESPACE, &e.name, Some(&e.category), None));
+    }
+    if wants(Resonance::NAMESPACE)
+        && let Some(r) = data.get_resonance(key)
+    {
+        return Some(item(Resonance::NAMESPACE, &r.name, None, Some(&r.rarity)));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::lcg::Lcg;
+
+    #[test]
+    fn compiled_agrees_with_satisfied_by() {
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            base := 25 STR
+            base => heavy := 40 STR, 30 FTD
+            35cha OR 35wll OR 35int
+            lht + med + hvy = 60
+            120 TTL
+
+            Post:
+            75r hvy
+            20r ftd, 20r flm, 20r ltn
+            ",
+        )
+        .unwrap();
+
+        let compiled = rf.compile();
+
+        let mut rng = Lcg::new(0x2545_f491_4f6c_dd1d);
+        let stats = [
+            Stat::Strength,
+            Stat::Fortitude,
+            Stat::Charisma,
+            Stat::Willpower,
+            Stat::Intelligence,
+            Stat::LightWeapon,
+            Stat::MediumWeapon,
+            Stat::HeavyWeapon,
+            Stat::Flamecharm,
+            Stat::Thundercall,
+        ];
+
+        let mut satisfied = 0;
+        for _ in 0..2000 {
+            let map = rng.statmap(stats);
+
+            let expected = rf.req_iter().all(|r| r.satisfied_by(&map));
+            assert_eq!(compiled(&map), expected, "disagreement on {map:?}");
+            satisfied += usize::from(expected);
+        }
+
+        // make sure both branches were exercised
+        assert!(satisfied > 0 && satisfied < 2000);
+    }
+
+    #[test]
+    fn resolve_items_matches_talents() {
+        let data = DeepData::from_json(
+            r#"{"talents":{"neuroplasticity":{"name":"Neuroplasticity","desc":"",
+                "rarity":"Common","category":"Intelligence","reqs":"30 INT",
+                "count_towards_talent_total":true,"vaulted":false,"voi":false}}}"#,
+        )
+        .unwrap();
+
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            neuroplasticity := 30 INT
+            talent:neuroplasticity := 30 INT
+            mystery := 20 STR
+            40 AGL
+            ",
+        )
+        .unwrap();
+
+        let resolved = rf.resolve_items(&data);
+        let expected = |requirement: &str| ResolvedItem::Item {
+            requirement: requirement.to_string(),
+            namespace: Talent::NAMESPACE,
+            name: "Neuroplasticity".to_string(),
+            category: Some("Intelligence".to_string()),
+            rarity: Some("Common".to_string()),
+        };
+
+        assert_eq!(
+            resolved,
+            vec![
+                expected("neuroplasticity"),
+                expected("talent:neuroplasticity"),
+                ResolvedItem::FreeText("mystery".to_string()),
+                ResolvedItem::FreeText("40s AGL".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn cost_to_add_is_marginal() {
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            plate := 40 FTD
+            30 STR OR 30 AGL
+            ",
+        )
+        .unwrap();
+
+        let base = rf.solve();
+
+        let covered: Requirement = "30 FTD".parse().unwrap();
+        assert_eq!(rf.cost_to_add(&base, &covered).unwrap(), 0);
+
+        let heavier: Requirement = "50 FTD, 20 STR".parse().unwrap();
+        assert_eq!(rf.cost_to_add(&base, &heavier).unwrap(), 10);
+
+        let dependent: Requirement = "plate => helm := 45 FTD".parse().unwrap();
+        assert_eq!(rf.cost_to_add(&base, &dependent).unwrap(), 5);
+
+        let orphan: Requirement = "gauntlets => helm := 45 FTD".parse().unwrap();
+        assert!(rf.cost_to_add(&base, &orphan).is_err());
+    }
+
+    #[test]
+    fn content_id_ignores_layout() {
+        let rf = Reqfile::parse_str(
+            r"
+            @race Khan
+            Free:
+            plate := 40 FTD
+            30 STR OR 30 AGL
+            3; helm := 50 FTD
+
+            Post:
+            60 ICE
+            ",
+        )
+        .unwrap();
+
+        let reordered = Reqfile::parse_str(
+            r"
+            # Armor
+            Free:
+            30agl OR 30 str
+            3;helm := 50 ftd
+            plate:=40 ftd
+
+            Post:
+            ice = 60
+            @race Khan
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(rf.content_id(), reordered.content_id());
+        assert_eq!(rf.content_id().len(), 32);
+        assert_eq!(
+            Reqfile::parse_str(&rf.generate()).unwrap().content_id(),
+            rf.content_id()
+        );
+
+        let changed = Reqfile::parse_str(
+            r"
+            @race Khan
+            Free:
+            plate := 40 FTD
+            30 STR OR 30 AGL
+            4; helm := 50 FTD
+
+            Post:
+            60 ICE
+            ",
+        )
+        .unwrap();
+        assert_ne!(rf.content_id(), changed.content_id());
+    }
+
+    #[test]
+    fn dominated_requirements_are_found() {
+        let mut rf = Reqfile::parse_str(
+            r"
+            Free:
+            25 STR
+            40 STR, 30 FTD
+            plate := 20 FTD
+            30 FTD OR 30 AGL
+            50 FTD
+            40 STR, 30 FTD
+
+            Post:
+            10 STR
+            ",
+        )
+        .unwrap();
+
+        let mut pairs = rf.redundant_requirements();
+        pairs.sort_unstable();
+
+        // the OR is only covered by what forces FTD, the repeat by its first copy, and the
+        // post section stands alone
+        assert_eq!(
+            pairs,
+            [
+                (0, 1),
+                (0, 5),
+                (2, 1),
+                (2, 4),
+                (2, 5),
+                (3, 1),
+                (3, 4),
+                (3, 5),
+                (5, 1)
+            ]
+        );
+
+        rf.prune_redundant();
+        let left: Vec<String> = rf.req_iter().map(Requirement::name_or_default).collect();
+        assert_eq!(
+            left,
+            ["30s FTD, 40s STR", "plate", "50s FTD", "10s STR"],
+            "{left:?}"
+        );
+    }
+
+    #[test]
+    fn resolve_after_change_converges_back() {
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            plate := 40 FTD
+            30 STR OR 30 AGL
+            35 ICE
+            ",
+        )
+        .unwrap();
+        let base = rf.solve();
+
+        let added: Requirement = "helm := 60 FTD, 40 WLL".parse().unwrap();
+        let with = rf.resolve_after_change(&base, &added, true).unwrap();
+        assert!(rf.req_iter().chain([&added]).all(|r| r.satisfied_by(&with)));
+        assert_eq!(with.get(&Stat::Fortitude), 60);
+        assert_eq!(with.get(&Stat::Willpower), 40);
+
+        let without = rf.resolve_after_change(&with, &added, false).unwrap();
+        assert_eq!(without, base);
+
+        let too_much: Requirement = "120 FTD".parse().unwrap();
+        assert!(rf.resolve_after_change(&base, &too_much, true).is_err());
+    }
+
+    #[test]
+    fn timed_req_iter_tags_sections() {
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            base := 25 STR
+
+            Post:
+            flame := 80r FLM
+            ",
+        )
+        .unwrap();
+
+        let timed: Vec<(&str, String)> = rf
+            .timed_req_iter()
+            .map(|(t, r)| (t.name(), r.name_or_default()))
+            .collect();
+
+        assert_eq!(
+            timed,
+            [("free", "base".to_string()), ("post", "flame".to_string())]
+        );
+    }
+
+    #[test]
+    fn split_by_timing() {
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            base := 25 STR
+            + plate := 40 FTD
+            2; plate => helm := 50 FTD
+
+            Post:
+            flame := 80r FLM
+            2; helm => cape := 60r FLM
+            5; 90r LTN
+            10 <= STR <= 30
+            ",
+        )
+        .unwrap();
+
+        let names = |reqs: &[Requirement]| {
+            reqs.iter()
+                .map(Requirement::name_or_default)
+                .collect::<Vec<_>>()
+        };
+
+        let free = rf.free_only();
+        assert_eq!(names(&free.general), ["base", "plate"]);
+        assert!(free.post.is_empty() && free.final_ranges.is_empty());
+        assert!(free.forced.contains("plate"));
+        // helm's group keeps helm, cape's group keeps its free prereq, the LTN group is gone
+        assert_eq!(free.optional.len(), 2);
+        assert!(
+            free.optional
+                .iter()
+                .all(|g| g.post.is_empty() && g.weight == 2)
+        );
+
+        let post = rf.post_only();
+        assert!(post.general.is_empty() && post.forced.is_empty());
+        assert_eq!(names(&post.post), ["flame"]);
+        assert_eq!(post.final_ranges.len(), 1);
+        assert_eq!(post.optional.len(), 2);
+        assert!(post.optional.iter().all(|g| g.general.is_empty()));
+        assert_eq!(
+            post.optional.iter().map(|g| g.weight).collect::<Vec<_>>(),
+            [2, 5]
+        );
+    }
+
+    #[test]
+    fn indexed_requirements_match_iteration() {
+        let rf = Reqfile::parse_str("Free:\n25 STR\n30 AGL\nPost:\n40 FLM").unwrap();
+
+        assert_eq!(rf.requirement_count(), rf.req_iter().count());
+        let indexed: Vec<(Timing, &Requirement)> = (0..rf.requirement_count())
+            .map(|i| rf.requirement_at(i).unwrap())
+            .collect();
+        assert_eq!(indexed, rf.timed_req_iter().collect::<Vec<_>>());
+        assert!(rf.requirement_at(rf.requirement_count()).is_none());
+    }
+
+    #[test]
+    fn shrine_rounding_is_tolerated() {
+        let rf = Reqfile::parse_str("Free:\nstr + agl = 67\n30 FTD\n40 STR").unwrap();
+        let pre = StatMap::from(HashMap::from([
+            (Stat::Strength, 40),
+            (Stat::Agility, 30),
+            (Stat::Fortitude, 30),
+        ]));
+
+        // 100 points over 3 stats floor to 33 each, leaving the sum a point short
+        let shrined = pre.shrine_order(&StatMap::new());
+        assert_eq!(shrined.cost(), 99);
+        let sum = &rf.general[0];
+        assert!(!sum.satisfied_by(&shrined));
+        assert!(sum.satisfied_by_within(&shrined, 1));
+
+        let broken = rf.verify_after_shrine(&pre, &StatMap::new());
+        assert_eq!(broken, [&rf.general[2]]);
+    }
+
+    #[test]
+    fn only_some_optional_combinations_fit() {
+        let rf = Reqfile::parse_str(
+            "Free:\n100 STR, 100 FTD\n1; agl := 80 AGL\n1; cha := 60 CHA\n1; int := 40 INT",
+        )
+        .unwrap();
+
+        let named: Vec<Vec<String>> = rf
+            .max_feasible_optionals()
+            .iter()
+            .map(|combo| {
+                let mut names: Vec<String> = combo
+                    .iter()
+                    .flat_map(|i| rf.optional[*i].general.iter())
+                    .map(Requirement::name_or_default)
+                    .collect();
+                names.sort();
+                names
+            })
+            .collect();
+
+        // agl and cha together are 340 points, past the 330 available
+        assert_eq!(named.len(), 2);
+        assert!(named.contains(&vec!["agl".to_string(), "int".to_string()]));
+        assert!(named.contains(&vec!["cha".to_string(), "int".to_string()]));
+
+        let impossible = Reqfile::parse_str("Free:\n100 STR, 100 FTD, 100 AGL, 40 CHA").unwrap();
+        assert!(impossible.max_feasible_optionals().is_empty());
+
+        let no_optionals = Reqfile::parse_str("Free:\n40 STR").unwrap();
+        assert_eq!(no_optionals.max_feasible_optionals(), [Vec::<usize>::new()]);
+    }
+
+    #[test]
+    fn many_optional_groups_finish_quickly() {
+        let optionals = |count: usize, line: &dyn Fn(usize) -> String| {
+            let lines: Vec<String> = (0..count).map(line).collect();
+            Reqfile::parse_str(&format!("Free:\n10 STR\n{}", lines.join("\n"))).unwrap()
+        };
+
+        // every group fits, so that's the only combination
+        let all_fit = optionals(Reqfile::MAX_ENUMERATED_OPTIONALS, &|i| {
+            format!("1; opt_{i} := {} STR", 40 + i)
+        });
+        let start = std::time::Instant::now();
+        assert_eq!(
+            all_fit.max_feasible_optionals(),
+            [(0..Reqfile::MAX_ENUMERATED_OPTIONALS).collect::<Vec<_>>()]
+        );
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+
+        // past the limit, the heaviest groups are taken while they fit: three stats at 100
+        // leave no room for the lightest, WLL
+        let stats = ["AGL", "FTD", "CHA", "WLL"];
+        let crowded = optionals(30, &|i| {
+            format!("{}; opt_{i} := 100 {}", 4 - i % 4, stats[i % 4])
+        });
+        let start = std::time::Instant::now();
+        let combos = crowded.max_feasible_optionals();
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+
+        let expected: Vec<usize> = (0..30).filter(|i| i % 4 != 3).collect();
+        assert_eq!(combos, [expected]);
+    }
+
+    #[test]
+    fn difficulty_tiers() {
+        let rate = |content: &str| Reqfile::parse_str(content).unwrap().difficulty();
+
+        let easy = rate("Free:\n40 STR");
+        assert_eq!(easy.tier, DifficultyTier::Easy);
+        assert_eq!(easy.cost, 40);
+        assert_eq!(easy.remaining, MAX_TOTAL - 40);
+
+        assert_eq!(rate("Free:\n40 STR, 80 FTD").tier, DifficultyTier::Medium);
+        // same points, but spread over three attunements
+        let attuned = rate("Free:\n40 FLM, 40 ICE, 40 LTN");
+        assert_eq!(attuned.attunements, 3);
+        assert_eq!(attuned.tier, DifficultyTier::Hard);
+
+        let maxroll = rate("Free:\n100 STR, 100 FTD, 100 AGL, 20 CHA");
+        assert_eq!(maxroll.tier, DifficultyTier::Maxroll);
+        assert_eq!(maxroll.remaining, 10);
+        assert!(maxroll.tier > attuned.tier);
+    }
+
+    #[test]
+    fn build_report_counts_unmet() {
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            base := 25 STR
+            base => heavy := 40 STR, 30 FTD
+            3; 40 INT
+
+            Post:
+            flame := 80r FLM
+            5; 20 CHA
+            ",
+        )
+        .unwrap();
+
+        let stats = StatMap::from(HashMap::from([
+            (Stat::Strength, 40),
+            (Stat::Fortitude, 20),
+            (Stat::Flamecharm, 80),
+            (Stat::Charisma, 20),
+        ]));
+
+        let report = rf.check_build(&stats);
+        assert_eq!(report.total, 3);
+        assert_eq!(report.satisfied, 2);
+        assert_eq!(report.unmet, ["heavy"]);
+        assert_eq!(report.required_cost, 150);
+        assert_eq!(report.build_cost, 160);
+        assert!(report.within_max_total);
+        assert_eq!((report.optional_groups, report.optional_satisfied), (2, 1));
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn rename_keeps_prereqs_consistent() {
+        let mut rf = Reqfile::parse_str(
+            r"
+            Free:
+            + base := 25 STR
+            base => heavy := 40 STR
+            3; base => helm := 50 FTD
+
+            Post:
+            heavy => flame := 80r FLM
+            40 INT
+            ",
+        )
+        .unwrap();
+
+        rf.rename(|name| format!("preset_{name}"));
+
+        let names: HashSet<String> = rf
+            .req_iter()
+            .chain(rf.optional_req_iter())
+            .filter_map(|r| r.name.clone())
+            .collect();
+        assert_eq!(
+            names,
+            HashSet::from(
+                ["preset_base", "preset_heavy", "preset_helm", "preset_flame"].map(String::from)
+            )
+        );
+
+        for req in rf.req_iter().chain(rf.optional_req_iter()) {
+            for alt in req
+                .prereqs
+                .iter()
+                .flat_map(crate::model::req::PrereqGroup::alternatives)
+            {
+                assert!(names.contains(alt), "dangling prereq {alt}");
+            }
+        }
+
+        assert!(rf.is_forced("preset_base"));
+        assert!(rf.req_iter().any(|r| r.name.is_none()));
+
+        // still a valid reqfile
+        Reqfile::parse_str(&rf.generate()).unwrap();
+    }
+
+    #[test]
+    fn exclusions_apply_when_merging() {
+        let mut base = Reqfile::parse_str(
+            r"
+            Free:
+            base := 25 STR
+            base => heavy := 40 STR
+            heavy => brute := 60 STR
+            2; base => helm := 50 FTD
+            ",
+        )
+        .unwrap();
+
+        let preset = Reqfile::parse_str(
+            r"
+            Free:
+            - heavy
+            - helm
+            40 AGL
+            ",
+        )
+        .unwrap();
+        assert_eq!(preset.general.len(), 1);
+        // survives a round trip
+        assert_eq!(
+            Reqfile::parse_str(&preset.generate()).unwrap().excluded,
+            preset.excluded
+        );
+
+        base += preset;
+
+        let names: Vec<String> = base.req_iter().map(Requirement::name_or_default).collect();
+        assert!(!names.contains(&"heavy".to_string()));
+        assert!(names.contains(&"brute".to_string()));
+        assert!(
+            base.optional_req_iter()
+                .all(|r| r.name_or_default() != "helm")
+        );
+
+        // brute stays, it just no longer waits on heavy
+        let brute = base.general.iter().find(|r| r.name_or_default() == "brute");
+        assert!(brute.unwrap().prereqs.is_empty());
+
+        let err = Reqfile::parse_str("Free:\nheavy := 40 STR\n- heavy").unwrap_err();
+        assert!(matches!(err, DeepError::Reqfile { line: 3, .. }));
+    }
+
+    #[test]
+    fn solve_attributes_binding_requirement() {
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            base := 40 FTD, 25 STR
+            reinforced_armor := 90 FTD
+            plate := 90 FTD
+            30 AGL OR 30 CHA
+            ",
+        )
+        .unwrap();
+
+        let (stats, attribution) = rf.solve_with_attribution().unwrap();
+        assert_eq!(stats.get(&Stat::Fortitude), 90);
+
+        // tied with reinforced_armor, plate wins by name
+        assert_eq!(attribution[&Stat::Fortitude], "plate");
+        assert_eq!(attribution[&Stat::Strength], "base");
+        assert_eq!(attribution.len(), 3);
+
+        for impossible in ["Free:\nSTR = 150", "Free:\n1700 TTL"] {
+            let rf = Reqfile::parse_str(impossible).unwrap();
+            assert!(rf.solve_with_attribution().is_err());
+        }
+    }
+
+    #[test]
+    fn labels_survive_a_round_trip() {
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            25 STR
+            # Weapons
+            weapon:kyrsewinter := 60 ICE, 30 STR
+            // not a label
+            weapon:pyre_keeper := 50 FLM
+            ## Mantras
+            mantra:ice_lance := 20 ICE
+
+            Post:
+            # only a note, with nothing right under it
+
+            90 STR
+            // only a note too
+            80 FTD
+            # Mantras
+            3; mantra:ice_daggers := 40 ICE
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(rf.labels["weapon:kyrsewinter"], "Weapons");
+        assert_eq!(rf.labels["weapon:pyre_keeper"], "Weapons");
+        assert_eq!(rf.labels["mantra:ice_lance"], "Mantras");
+        assert_eq!(rf.labels["mantra:ice_daggers"], "Mantras");
+        // headers end a label, and a comment without a requirement right under it isn't one
+        assert_eq!(rf.labels.len(), 4);
+
+        let generated = rf.generate();
+        let regenerated = Reqfile::parse_str(&generated).unwrap();
+        assert_eq!(regenerated.labels, rf.labels, "{generated}");
+        assert_eq!(regenerated.generate(), generated);
+    }
+
+    #[test]
+    fn directives_are_kept_and_applied() {
+        let rf = Reqfile::parse_str(
+            r"
+            @race Khan
+            @outfit 11th Legion Plate
+            @theme dark
+
+            Free:
+            25 STR, 10 AGL
+
+            Post:
+            weapon:adretian_axe := 35r HVY
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(rf.metadata["race"], "Khan");
+        assert_eq!(rf.metadata["theme"], "dark");
+        let regenerated = Reqfile::parse_str(&rf.generate()).unwrap();
+        assert_eq!(regenerated.metadata, rf.metadata);
+
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/all.json");
+        let data = DeepData::from_json(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let plan = rf.plan(&data).unwrap();
+
+        // khan's 3 STR and 2 AGL come for free
+        assert_eq!(plan.cost.racial, 5);
+
+        // the outfit's power and fortitude reqs are met before the shrine, like an outfit
+        // picked in a BuildConfig
+        assert!(plan.pre_shrine.cost() >= 90);
+        assert!(plan.pre_shrine.get(&Stat::Fortitude) >= 10);
+        assert!(
+            plan.reqfile
+                .general
+                .iter()
+                .any(|r| r.name.as_deref() == Some("outfit:11th_legion_plate"))
+        );
+
+        // and khan lowers the weapon's requirement
+        let axe = plan
+            .reqfile
+            .post
+            .iter()
+            .find(|r| r.name.as_deref() == Some("weapon:adretian_axe"))
+            .unwrap();
+        assert_eq!(axe.to_string(), "weapon:adretian_axe := 32r HVY");
+        assert!(plan.post_shrine.get(&Stat::HeavyWeapon) >= 32);
+
+        let err = Reqfile::parse_str("@race Khan\n@race Celtor").unwrap_err();
+        assert!(matches!(err, DeepError::Reqfile { line: 2, .. }));
+    }
+
+    #[test]
+    fn solve_from_keeps_fixed_stats() {
+        let rf = Reqfile::parse_str("Free:\n90 FTD, 25 STR").unwrap();
+        let fresh = rf.solve_from(&StatMap::new()).unwrap();
+        assert_eq!(fresh.cost(), 115);
+
+        let fixed = StatMap::from(HashMap::from([(Stat::Fortitude, 60), (Stat::Agility, 10)]));
+        let stats = rf.solve_from(&fixed).unwrap();
+
+        assert_eq!(stats.get(&Stat::Fortitude), 90);
+        assert_eq!(stats.get(&Stat::Agility), 10);
+        // 30 FTD and 25 STR on top of what was already there
+        assert_eq!(stats.cost() - fixed.cost(), 55);
+
+        let capped = StatMap::from(HashMap::from([(Stat::Strength, 120)]));
+        assert!(rf.solve_from(&capped).is_err());
+    }
+
+    #[test]
+    fn solve_with_reduction_caps_each_stat() {
+        let rf = Reqfile::parse_str("Free:\n40r FTD, 40r STR\n30s AGL").unwrap();
+        let budget = HashMap::from([(Stat::Fortitude, 10), (Stat::Strength, 0)]);
+        let stats = rf.solve_with_reduction(&budget);
+
+        assert_eq!(stats.get(&Stat::Fortitude), 30);
+        assert_eq!(stats.get(&Stat::Strength), 40);
+        // strict atoms aren't lowered, and neither are stats missing from the budget
+        assert_eq!(stats.get(&Stat::Agility), 30);
+        assert_eq!(stats.cost(), rf.solve().cost() - 10);
+
+        let generous = HashMap::from([(Stat::Agility, 50), (Stat::Fortitude, 50)]);
+        let stats = rf.solve_with_reduction(&generous);
+        assert_eq!(stats.get(&Stat::Fortitude), 0);
+        assert_eq!(stats.get(&Stat::Agility), 30);
+    }
+
+    #[test]
+    fn stat_envelope_takes_the_worst_case() {
+        let rf = Reqfile::parse_str(
+            "Free:\n\
+             25 STR OR 40 AGL\n\
+             FLM + ICE = 30\n\
+             20 FLM\n\
+             SDW + BLD = 50\n\
+             165 TTL\n\
+             NOT 10 INT\n\
+             Post:\n\
+             60 STR",
+        )
+        .unwrap();
+        let envelope = rf.stat_envelope();
+
+        // both sides of the OR count
+        assert_eq!(envelope.get(&Stat::Strength), 60);
+        assert_eq!(envelope.get(&Stat::Agility), 40);
+        // FLM's own 20 leaves ICE the last 10 of the sum, while FLM could still carry it all
+        assert_eq!(envelope.get(&Stat::Flamecharm), 30);
+        assert_eq!(envelope.get(&Stat::Frostdraw), 10);
+        // stats only seen in a sum get the whole sum
+        assert_eq!(envelope.get(&Stat::Shadowcast), 50);
+        assert_eq!(envelope.get(&Stat::Bloodrend), 50);
+        assert_eq!(envelope.get(&Stat::Total), 165);
+        assert!(!envelope.contains_key(&Stat::Intelligence));
+
+        assert!(Reqfile::parse_str("").unwrap().stat_envelope().is_empty());
+    }
+
+    #[test]
+    fn satisfaction_averages_requirements() {
+        let rf = Reqfile::parse_str("Free:\n40 STR\nPost:\n20 AGL").unwrap();
+        let stats = StatMap::from(HashMap::from([(Stat::Strength, 40)]));
+        assert!((rf.satisfaction(&stats) - 0.5).abs() < f64::EPSILON);
+        assert!((rf.satisfaction(&rf.solve()) - 1.0).abs() < f64::EPSILON);
+    }
+}
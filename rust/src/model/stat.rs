@@ -68,6 +68,45 @@ pub fn category(name: &str) -> Option<&'static [Stat]> {
 pub use crate::constants::MAX_TOTAL;
 
 impl Stat {
+    /// Every stat in the order the game lists them, which is also discriminant order, with
+    /// [`Stat::Total`] last.
+    pub const GAME_ORDER: [Stat; 17] = [
+        Stat::Strength,
+        Stat::Fortitude,
+        Stat::Agility,
+        Stat::Intelligence,
+        Stat::Willpower,
+        Stat::Charisma,
+        Stat::HeavyWeapon,
+        Stat::MediumWeapon,
+        Stat::LightWeapon,
+        Stat::Frostdraw,
+        Stat::Flamecharm,
+        Stat::Thundercall,
+        Stat::Galebreathe,
+        Stat::Shadowcast,
+        Stat::Ironsing,
+        Stat::Bloodrend,
+        Stat::Total,
+    ];
+
+    /// Iterates [`Stat::GAME_ORDER`]
+    pub fn iter_game_order() -> impl Iterator<Item = Stat> {
+        Self::GAME_ORDER.into_iter()
+    }
+
+    /// Every variant in declaration order, same as [`Stat::GAME_ORDER`]
+    #[must_use]
+    pub const fn all() -> [Stat; 17] {
+        Self::GAME_ORDER
+    }
+
+    /// [`Stat::all`] without [`Stat::Total`], the stats points can be put into
+    #[must_use]
+    pub fn all_investable() -> &'static [Stat] {
+        &Self::GAME_ORDER[..Self::GAME_ORDER.len() - 1]
+    }
+
     #[must_use]
     pub fn from_u32_unchecked(value: u32) -> Self {
         // LOL
@@ -120,6 +159,61 @@ impl Stat {
         }
     }
 
+    /// Informal spellings accepted by [`Stat::from_name`], [`Stat::from_short_name`] and
+    /// parsing, besides the stat's [`Stat::short_name`] and [`Stat::name`]. Lowercase, though
+    /// matching is case-insensitive. Canonical names and short names are tried first, so they
+    /// win over an alias on a conflict.
+    ///
+    /// | Stat        | Aliases                 |
+    /// |-------------|-------------------------|
+    /// | Fortitude   | `fort`                  |
+    /// | Agility     | `agi`, `agil`           |
+    /// | Intelligence| `intel`                 |
+    /// | Willpower   | `wil`, `will`           |
+    /// | Charisma    | `char`                  |
+    /// | Heavy       | `heavy`                 |
+    /// | Medium      | `medium`                |
+    /// | Light       | `light`                 |
+    /// | Frostdraw   | `frost`                 |
+    /// | Flamecharm  | `fir`, `fire`, `flame`  |
+    /// | Thundercall | `thunder`, `lightning`  |
+    /// | Galebreathe | `gale`, `wind`          |
+    /// | Shadowcast  | `shadow`                |
+    /// | Ironsing    | `iron`, `metal`         |
+    /// | Bloodrend   | `blood`                 |
+    /// | Total       | `tot`                   |
+    #[must_use]
+    pub fn all_aliases(&self) -> &'static [&'static str] {
+        match self {
+            Stat::Strength => &[],
+            Stat::Fortitude => &["fort"],
+            Stat::Agility => &["agi", "agil"],
+            Stat::Intelligence => &["intel"],
+            Stat::Willpower => &["wil", "will"],
+            Stat::Charisma => &["char"],
+            Stat::HeavyWeapon => &["heavy"],
+            Stat::MediumWeapon => &["medium"],
+            Stat::LightWeapon => &["light"],
+            Stat::Frostdraw => &["frost"],
+            Stat::Flamecharm => &["fir", "fire", "flame"],
+            Stat::Thundercall => &["thunder", "lightning"],
+            Stat::Galebreathe => &["gale", "wind"],
+            Stat::Shadowcast => &["shadow"],
+            Stat::Ironsing => &["iron", "metal"],
+            Stat::Bloodrend => &["blood"],
+            Stat::Total => &["tot"],
+        }
+    }
+
+    fn from_alias(alias: &str) -> Option<Self> {
+        Self::iter_game_order().find(|stat| {
+            stat.all_aliases()
+                .iter()
+                .any(|a| a.eq_ignore_ascii_case(alias))
+        })
+    }
+
+    /// Full name (`Frostdraw`), or one of [`Stat::all_aliases`]
     #[must_use]
     pub fn from_name(name: &str) -> Option<Self> {
         let name = name.to_uppercase();
@@ -141,10 +235,11 @@ impl Stat {
             "IRONSING" => Some(Stat::Ironsing),
             "BLOODREND" => Some(Stat::Bloodrend),
             "TOTAL" => Some(Stat::Total),
-            _ => None,
+            _ => Self::from_alias(&name),
         }
     }
 
+    /// Short name (`ICE`), or one of [`Stat::all_aliases`]
     #[must_use]
     pub fn from_short_name(short: &str) -> Option<Self> {
         let short = short.to_uppercase();
@@ -168,7 +263,7 @@ impl Stat {
             "MTL" => Some(Stat::Ironsing),
             "BLD" => Some(Stat::Bloodrend),
             "TTL" | "TOT" => Some(Stat::Total),
-            _ => None,
+            _ => Self::from_alias(&short),
         }
     }
 
@@ -186,6 +281,14 @@ impl Stat {
         )
     }
 
+    #[must_use]
+    pub const fn is_weapon(&self) -> bool {
+        matches!(
+            self,
+            Stat::HeavyWeapon | Stat::MediumWeapon | Stat::LightWeapon
+        )
+    }
+
     #[must_use]
     pub const fn as_u32(self) -> u32 {
         self as u32
@@ -301,4 +404,56 @@ pub struct StatRange {
     pub stat: Stat,
     /// Inclusive on both ends: the stat's post value must lie within `[start, end]`.
     pub range: RangeInclusive<u32>
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_order_is_discriminant_order() {
+        let position = |stat| Stat::GAME_ORDER.iter().position(|s| *s == stat);
+        assert!(position(Stat::Strength) < position(Stat::Fortitude));
+        assert!(position(Stat::Fortitude) < position(Stat::Agility));
+
+        for (i, stat) in Stat::iter_game_order().enumerate() {
+            assert_eq!(stat.as_u32() as usize, i);
+        }
+        assert_eq!(Stat::GAME_ORDER.last(), Some(&Stat::Total));
+    }
+
+    #[test]
+    fn all_covers_every_variant() {
+        let short_names: std::collections::HashSet<&str> =
+            Stat::all().iter().map(Stat::short_name).collect();
+        assert_eq!(Stat::all().len(), short_names.len());
+
+        assert_eq!(Stat::all_investable().len(), Stat::all().len() - 1);
+        assert!(!Stat::all_investable().contains(&Stat::Total));
+        let weapons: Vec<Stat> = Stat::all().into_iter().filter(Stat::is_weapon).collect();
+        assert_eq!(weapons, WEAPON);
+    }
+
+    #[test]
+    fn aliases_parse() {
+        assert_eq!("frost".parse::<Stat>(), Ok(Stat::Frostdraw));
+        assert_eq!("THUNDER".parse::<Stat>(), Ok(Stat::Thundercall));
+        assert_eq!(Stat::from_name("Fort"), Some(Stat::Fortitude));
+
+        assert_eq!(
+            crate::model::req::Requirement::parse("40 flame, 30 str")
+                .unwrap()
+                .to_string(),
+            "30s STR, 40s FLM"
+        );
+
+        // the canonical forms are never shadowed by another stat's alias
+        for stat in Stat::iter_game_order() {
+            assert_eq!(Stat::from_short_name(stat.short_name()), Some(stat));
+            assert_eq!(Stat::from_name(stat.name()), Some(stat));
+            for alias in stat.all_aliases() {
+                assert_eq!(alias.parse::<Stat>(), Ok(stat), "{alias}");
+            }
+        }
+    }
+}
@@ -3,3 +3,6 @@ pub(crate) mod reqfile;
 
 #[cfg(test)]
 mod tests;
+
+#[cfg(test)]
+mod roundtrip;
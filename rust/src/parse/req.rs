@@ -1,13 +1,16 @@
+use std::cell::RefCell;
 use std::collections::BTreeSet;
 
 use crate::Stat;
 use crate::error::{DeepError, Result};
 use crate::model::req::{Atom, Clause, PrereqGroup, Reducability, Requirement};
 use log::warn;
-use winnow::ascii::{Caseless, alpha1, digit1, multispace0};
-use winnow::combinator::{alt, delimited, not, opt, preceded, repeat, separated};
+use winnow::ascii::{Caseless, alpha1, digit1, multispace0, multispace1};
+use winnow::combinator::{alt, cut_err, delimited, fail, not, opt, preceded, repeat, separated};
+use winnow::error::{ContextError, ParseError, StrContext, StrContextValue};
 use winnow::prelude::*;
-use winnow::token::one_of;
+use winnow::stream::Stream;
+use winnow::token::{one_of, take_while};
 
 /// Parse a string into a Requirement
 ///
@@ -21,6 +24,12 @@ use winnow::token::one_of;
 /// - "FTD = 90" -> Same thing but diff syntax, "ftd=90", "90ftd" also are valid
 /// - "25 STR OR 25 AGL" -> OR clause with reducible atoms
 /// - "25S STR OR 25 AGL" -> OR clause with asymmetric reducability
+/// - "25 STR AND 25 AGL" -> AND clause holding both atoms, same as "25 STR, 25 AGL" when met
+/// - "25 STR AND 25 AGL OR 25 CHA" -> AND binds tighter, so distributed into the OR clauses
+///   "25s STR OR 25r CHA" and "25s AGL OR 25r CHA". Atoms keep the defaults of the group they
+///   were written in
+/// - "(25 STR OR 25 AGL) AND 40 FTD" -> parens group any subexpression, same as
+///   "25 STR OR 25 AGL, 40 FTD"
 /// - "(LHT + MED + HVY = 90)" -> AND clause with sum atom (reducible by default)
 /// - "(LHT + MED + HVY = 90S)" -> Any stat that make up the sum cannot be reduced
 /// - "25S STR" -> strict atom
@@ -28,11 +37,112 @@ use winnow::token::one_of;
 /// - "reinforced = 90 FTD" -> named requirement (assignment syntax)
 /// - "base, armor => reinforced := 90 FTD" -> named requirement with prerequisites
 /// - "base => 90 FTD" -> anonymous requirement with a prerequisite
+///
+/// Since the defaults differ, an OR clause mixing explicit and implicit reducibility like
+/// "25S STR OR 25 AGL" leaves AGL reducible, which is easy to misread. That gets a warning.
 pub(crate) fn parse_req(input: &str) -> Result<Requirement> {
-    let input = input.trim();
+    let trimmed = input.trim();
     requirement
-        .parse(input)
-        .map_err(|e| DeepError::Req(e.to_string()))
+        .parse(trimmed)
+        .map_err(|e| parse_error(input, &e))
+}
+
+/// Parses newline separated requirements, each line on its own. Blank lines and `#`/`//`
+/// comments are skipped. Unlike a reqfile there are no headers, optionals or dependency
+/// statements, every line is just a [`parse_req`].
+///
+/// Fails with a [`DeepError::Reqs`] listing every line that didn't parse.
+pub(crate) fn parse_reqs(input: &str) -> Result<Vec<Requirement>> {
+    let mut reqs = vec![];
+    let mut errors = vec![];
+
+    for (i, raw) in input.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+
+        match parse_req(raw) {
+            Ok(req) => reqs.push(req),
+            Err(DeepError::Req {
+                message,
+                offset,
+                snippet,
+            }) => errors.push(DeepError::Reqfile {
+                line: i + 1,
+                column: Some(raw[..offset].chars().count() + 1),
+                message: format!("{message} (near '{snippet}')"),
+            }),
+            Err(e) => errors.push(DeepError::Reqfile {
+                line: i + 1,
+                column: None,
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(reqs)
+    } else {
+        Err(DeepError::Reqs(errors))
+    }
+}
+
+/// Turns a failure parsing `input.trim()` into a [`DeepError::Req`], with the offset pointing
+/// into the untrimmed `input`
+pub(crate) fn parse_error(input: &str, e: &ParseError<&str, ContextError>) -> DeepError {
+    const AROUND: usize = 12;
+
+    let lead = input.len() - input.trim_start().len();
+    let offset = lead + e.offset();
+
+    let before: String = {
+        let chars: Vec<char> = input[..offset].chars().rev().take(AROUND).collect();
+        chars.into_iter().rev().collect()
+    };
+    let after: String = input[offset..].chars().take(AROUND).collect();
+
+    let context = e.inner().to_string();
+    let message = if !context.is_empty() {
+        context
+    } else if let Some(token) = input[offset..].split_whitespace().next() {
+        format!("unexpected '{token}'")
+    } else {
+        "unexpected end of input".to_string()
+    };
+
+    DeepError::Req {
+        message,
+        offset,
+        snippet: format!("{before}{after}").trim().to_string(),
+    }
+}
+
+thread_local! {
+    static WARNINGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Logs a parse warning, and records it if a [`parse_req_verbose`] is collecting them
+fn parse_warning(message: String) {
+    warn!("{message}");
+
+    WARNINGS.with_borrow_mut(|warnings| {
+        // backtracking can parse the same clause twice
+        if let Some(warnings) = warnings
+            && !warnings.contains(&message)
+        {
+            warnings.push(message);
+        }
+    });
+}
+
+/// Same as [`parse_req`], but also returns the warnings raised while parsing
+pub(crate) fn parse_req_verbose(input: &str) -> Result<(Requirement, Vec<String>)> {
+    WARNINGS.with_borrow_mut(|warnings| *warnings = Some(vec![]));
+    let req = parse_req(input);
+    let warnings = WARNINGS.with_borrow_mut(Option::take).unwrap_or_default();
+
+    Ok((req?, warnings))
 }
 
 // requirement = prefix? bare_requirement
@@ -77,10 +187,9 @@ fn name_prefix(input: &mut &str) -> ModalResult<(Vec<PrereqGroup>, Option<String
 }
 
 pub(crate) fn parse_prereq_group(input: &str) -> Result<PrereqGroup> {
-    let input = input.trim();
     prereq_group_full
-        .parse(input)
-        .map_err(|e| DeepError::Req(e.to_string()))
+        .parse(input.trim())
+        .map_err(|e| parse_error(input, &e))
 }
 
 fn prereq_group_full(input: &mut &str) -> ModalResult<PrereqGroup> {
@@ -112,26 +221,40 @@ pub(crate) fn identifier(input: &mut &str) -> ModalResult<String> {
     }
 }
 
+// a segment may start with a digit (`11th_legion_plate`), but a purely numeric one would
+// be indistinguishable from a requirement value like the `90` in `90 => x`
 fn segment(input: &mut &str) -> ModalResult<String> {
-    let id: String =
-        repeat(1.., one_of(('A'..='Z', 'a'..='z', '0'..='9', '_'))).parse_next(input)?;
-    Ok(id)
+    let id = take_while(1.., is_identifier_char)
+        .verify(|id: &str| !id.chars().all(char::is_numeric))
+        .parse_next(input)?;
+    Ok(id.to_string())
+}
+
+/// `[A-Za-z0-9_]`, or any Unicode letter or digit with the `unicode-identifiers` feature.
+/// Stats stay ASCII either way.
+fn is_identifier_char(c: char) -> bool {
+    if cfg!(feature = "unicode-identifiers") {
+        c.is_alphanumeric() || c == '_'
+    } else {
+        c.is_ascii_alphanumeric() || c == '_'
+    }
 }
 
 fn ns_segment(input: &mut &str) -> ModalResult<String> {
     preceded((':', not('=')), segment).parse_next(input)
 }
 
-// requirement = '(' ')' | clause (',' clause)*
+// requirement = empty | clause (',' clause)*
 fn bare_requirement(input: &mut &str) -> ModalResult<Requirement> {
     let clauses = alt((
         // if () then its an empty req
-        ('(', multispace0, ')').map(|_| Vec::new()),
+        empty.map(|()| Vec::new()),
         // Normal: 1+ clauses (clauses can have their own parens)
         separated(1.., clause, (multispace0, ',', multispace0)),
     ))
     .parse_next(input)?
     .into_iter()
+    .flatten()
     .collect::<BTreeSet<Clause>>();
 
     Ok(Requirement {
@@ -141,17 +264,29 @@ fn bare_requirement(input: &mut &str) -> ModalResult<Requirement> {
     })
 }
 
-// clause = '(' clause_inner ')' | clause_inner
-// clause_inner = atom ('OR' atom)*
-// TODO! this is lacking an explicit 'AND', though you
-// can just implicitly create new ANDs by making a new single atom clause!
-fn clause(input: &mut &str) -> ModalResult<Clause> {
+// empty = '(' empty? ')'
+// whitespace allowed anywhere, so `( )` and `(())` are empty too. Anything else inside the
+// parens (`(,)`, `(OR)`) has to parse as a clause, which it won't
+fn empty(input: &mut &str) -> ModalResult<()> {
+    let _ = ('(', multispace0).parse_next(input)?;
+    let _ = opt(empty).parse_next(input)?;
+    let _ = (multispace0, ')').parse_next(input)?;
+    Ok(())
+}
+
+// clause = not_clause | expr
+// expr = term ('OR' term)*
+// term = factor ('AND' factor)*
+// factor = atom | '(' expr ')'
+// AND binds tighter than OR, and parens group any subexpression, so redundant ones nest any
+// number of times (`((X))` is the same clause as `X`). A clause is a flat list of atoms, so
+// an expression mixing the two is distributed into several clauses, see `expr`
+fn clause(input: &mut &str) -> ModalResult<Vec<Clause>> {
     let _ = multispace0.parse_next(input)?;
 
-    // try (clause) first
     let result = alt((
-        delimited(('(', multispace0), clause_inner, (multispace0, ')')),
-        clause_inner,
+        not_clause.map(|clause| vec![clause]),
+        expr.map(|node| into_clauses(node.into_cnf(false))),
     ))
     .parse_next(input)?;
 
@@ -160,28 +295,142 @@ fn clause(input: &mut &str) -> ModalResult<Clause> {
     Ok(result)
 }
 
-fn clause_inner(input: &mut &str) -> ModalResult<Clause> {
-    let first = atom.parse_next(input)?;
-    let rest: Vec<ParsedAtom> = repeat(
-        0..,
-        preceded((multispace0, Caseless("OR"), multispace0), atom),
-    )
-    .parse_next(input)?;
+// not_clause = ('!' | 'NOT') atom
+// negates a single atom, so it can't be part of an AND or OR
+fn not_clause(input: &mut &str) -> ModalResult<Clause> {
+    alt(('!'.void(), (Caseless("NOT"), multispace1).void())).parse_next(input)?;
+    let parsed = atom.parse_next(input)?;
 
-    if rest.is_empty() {
-        // single atom -> AND clause
-        let atom = first.into_atom(false);
-        Ok(Clause::and().atom(atom))
-    } else {
-        // multiple atoms -> OR clause (no AND support YET..)
-        let mut clause = Clause::or();
-        clause = clause.atom(first.into_atom(true));
-        for parsed in rest {
-            clause = clause.atom(parsed.into_atom(true));
+    Ok(Clause::not().atom(parsed.into_atom(false)))
+}
+
+// a parsed (sub)expression
+enum Node {
+    // a lone atom, which takes its default reducability from whatever it ends up joined by
+    Atom(ParsedAtom),
+    // atom sets that all have to be met, each by any one of its atoms
+    Cnf(Vec<BTreeSet<Atom>>),
+}
+
+impl Node {
+    fn into_cnf(self, is_or: bool) -> Vec<BTreeSet<Atom>> {
+        match self {
+            Node::Atom(parsed) => vec![BTreeSet::from([parsed.into_atom(is_or)])],
+            Node::Cnf(cnf) => cnf,
+        }
+    }
+}
+
+// the single atom sets are gathered into one AND clause, the rest are OR clauses
+fn into_clauses(cnf: Vec<BTreeSet<Atom>>) -> Vec<Clause> {
+    let mut and = Clause::and();
+    let mut clauses = vec![];
+
+    for atoms in cnf {
+        if atoms.len() == 1 {
+            for atom in atoms {
+                and.add_atom(atom);
+            }
+        } else {
+            clauses.push(atoms.into_iter().fold(Clause::or(), Clause::atom));
+        }
+    }
+
+    if !and.is_empty() {
+        clauses.push(and);
+    }
+    clauses
+}
+
+fn factor(input: &mut &str) -> ModalResult<Node> {
+    alt((
+        atom.map(Node::Atom),
+        delimited(
+            ('(', multispace0),
+            expr,
+            (
+                multispace0,
+                ')'.context(StrContext::Expected(StrContextValue::CharLiteral(')'))),
+                multispace0,
+            ),
+        ),
+    ))
+    .parse_next(input)
+}
+
+fn term(input: &mut &str) -> ModalResult<Node> {
+    let mut factors: Vec<Node> =
+        separated(1.., factor, (multispace0, Caseless("AND"), multispace0)).parse_next(input)?;
+
+    if factors.len() == 1 {
+        return Ok(factors.remove(0));
+    }
+
+    // every factor has to hold, so their atom sets just add up. Atoms joined by AND get the
+    // same defaults as comma separated ones
+    Ok(Node::Cnf(
+        factors
+            .into_iter()
+            .flat_map(|factor| factor.into_cnf(false))
+            .collect(),
+    ))
+}
+
+fn expr(input: &mut &str) -> ModalResult<Node> {
+    let start = input.checkpoint();
+    let mut terms: Vec<Node> =
+        separated(1.., term, (multispace0, Caseless("OR"), multispace0)).parse_next(input)?;
+
+    if terms.len() == 1 {
+        return Ok(terms.remove(0));
+    }
+
+    let alternatives: Vec<&ParsedAtom> = terms
+        .iter()
+        .filter_map(|term| match term {
+            Node::Atom(parsed) => Some(parsed),
+            Node::Cnf(_) => None,
+        })
+        .collect();
+    let explicit = alternatives
+        .iter()
+        .filter(|a| a.reducability.is_some())
+        .count();
+    if explicit > 0 && explicit < alternatives.len() {
+        parse_warning(format!(
+            "OR clause mixes explicit and implicit reducibility, the unmarked atoms \
+            default to reducible: {}",
+            alternatives
+                .iter()
+                .map(|a| a.describe())
+                .collect::<Vec<_>>()
+                .join(" OR ")
+        ));
+    }
+
+    // (a AND b) OR c == (a OR c) AND (b OR c): one atom set per way of picking a set from
+    // each term. That grows exponentially, so it's capped like `Requirement::to_dnf`
+    let mut cnf: BTreeSet<BTreeSet<Atom>> = BTreeSet::from([BTreeSet::new()]);
+    for term in terms {
+        let term = term.into_cnf(true);
+        if cnf.len() * term.len() > Requirement::DNF_CAP {
+            input.reset(&start);
+            return cut_err(fail.context(StrContext::Label(
+                "AND/OR nesting, it expands to too many clauses",
+            )))
+            .parse_next(input);
         }
 
-        Ok(clause)
+        cnf = cnf
+            .iter()
+            .flat_map(|pick| {
+                term.iter()
+                    .map(move |atoms| pick.union(atoms).cloned().collect())
+            })
+            .collect();
     }
+
+    Ok(Node::Cnf(cnf.into_iter().collect()))
 }
 
 // intermediate atom structure
@@ -192,6 +441,21 @@ struct ParsedAtom {
 }
 
 impl ParsedAtom {
+    fn describe(&self) -> String {
+        let stats = self
+            .stats
+            .iter()
+            .map(Stat::short_name)
+            .collect::<Vec<_>>()
+            .join(" + ");
+        let marker = self
+            .reducability
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        format!("{}{marker} {stats}", self.value)
+    }
+
     fn into_atom(self, is_or: bool) -> Atom {
         let reducability = self.reducability.unwrap_or({
             if is_or {
@@ -207,10 +471,11 @@ impl ParsedAtom {
         });
 
         if reducability == Reducability::Strict && self.stats.len() > 1 {
-            warn!(
+            parse_warning(
                 "You have specified a strict SUM requirement, please note that \
                 strict SUM requirements' semantics are not properly defined currently. \
                 You probably don't need it anyways."
+                    .to_string(),
             );
         }
 
@@ -349,6 +614,7 @@ pub(crate) fn stat(input: &mut &str) -> ModalResult<Stat> {
 #[cfg(test)]
 mod tests {
     use crate::model::req::ClauseType;
+    use crate::util::statmap::StatMap;
 
     use super::*;
 
@@ -411,6 +677,193 @@ mod tests {
         assert_eq!(second_clause.atoms.len(), 2);
     }
 
+    #[test]
+    fn explicit_and_variants() {
+        let variants = [
+            "25 STR AND 25 AGL, 75 MED OR (LHT + MED + HVY = 90)",
+            "(25 STR AND 25 AGL), (75 MED OR (LHT + MED + HVY = 90))",
+            "STR = 25 AND AGL = 25, 75 MED OR (LHT + MED + HVY = 90)",
+            "(STR = 25 and AGL = 25),(75 MED OR (LHT + MED + HVY = 90))",
+            "STR=25 And AGL= 25,med=75 OR (lht + MED +hvy = 90)",
+        ];
+
+        let parsed: Vec<Requirement> = variants
+            .iter()
+            .map(|s| parse_req(s).unwrap_or_else(|_| panic!("Failed to parse: {s}")))
+            .collect();
+
+        for i in 1..parsed.len() {
+            assert_eq!(parsed[0], parsed[i], "variant 0 should equal variant {i}");
+        }
+
+        let req = &parsed[0];
+        assert_eq!(req.clauses.len(), 2);
+
+        let mut clauses = req.clauses.iter();
+        // first clause: 25 STR AND 25 AGL, binding tighter than the comma
+        let first_clause = clauses.next().unwrap();
+        assert_eq!(first_clause.clause_type, ClauseType::And);
+        assert_eq!(first_clause.atoms.len(), 2);
+        assert!(
+            first_clause
+                .atoms
+                .iter()
+                .all(|a| a.reducability == Reducability::Strict)
+        );
+
+        let second_clause = clauses.next().unwrap();
+        assert_eq!(second_clause.clause_type, ClauseType::Or);
+
+        // prints with the keyword and reads back the same
+        assert_eq!(
+            req.to_string(),
+            "25s STR AND 25s AGL, (75r MED OR HVY + MED + LHT = 90r)"
+        );
+        assert_eq!(&parse_req(&req.to_string()).unwrap(), req);
+
+        // met the same as separate clauses
+        let split = parse_req("25 STR, 25 AGL").unwrap();
+        let joined = parse_req("25 STR AND 25 AGL").unwrap();
+        for (str_v, agl_v) in [(0, 0), (25, 0), (0, 25), (25, 25)] {
+            let stats = StatMap::from(std::collections::HashMap::from([
+                (Stat::Strength, str_v),
+                (Stat::Agility, agl_v),
+            ]));
+            assert_eq!(split.satisfied_by(&stats), joined.satisfied_by(&stats));
+        }
+
+        assert!(parse_req("25 STR AND").is_err());
+        assert!(parse_req("25STRAND25AGL").is_err());
+    }
+
+    #[test]
+    fn or_next_to_and_round_trips() {
+        let variants = [
+            "25 STR OR 25 AGL, 40 FTD",
+            "(25 STR OR 25 AGL), 40 FTD",
+            "40 FTD, LHT + MED + HVY = 90 OR 75 MED, 20 CHA OR 20 WLL",
+        ];
+
+        for variant in variants {
+            let req = parse_req(variant).unwrap();
+            let shown = req.to_string();
+            assert_eq!(
+                parse_req(&shown).unwrap(),
+                req,
+                "{variant} displayed as {shown}"
+            );
+        }
+
+        let req = parse_req("25 STR OR 25 AGL, 40 FTD").unwrap();
+        assert_eq!(req.to_string(), "40s FTD, (25r STR OR 25r AGL)");
+
+        // a lone OR needs no parens
+        let req = parse_req("25 STR OR 25 AGL").unwrap();
+        assert_eq!(req.to_string(), "25r STR OR 25r AGL");
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        // (a AND b) OR c == (a OR c) AND (b OR c)
+        let mixed = parse_req("25 STR AND 25 AGL OR 30 CHA").unwrap();
+        assert_eq!(
+            mixed,
+            parse_req("25s STR OR 30r CHA, 25s AGL OR 30r CHA").unwrap()
+        );
+        assert_eq!(parse_req("30 CHA OR 25 STR AND 25 AGL").unwrap(), mixed);
+        assert_eq!(parse_req(&mixed.to_string()).unwrap(), mixed);
+
+        // distributed atoms keep the defaults of where they were written: strict in the AND
+        // group, reducible as an OR alternative
+        assert_eq!(
+            mixed.to_string(),
+            "(30r CHA OR 25s STR), (30r CHA OR 25s AGL)"
+        );
+        let marked = parse_req("25r STR AND 25 AGL OR 30s CHA").unwrap();
+        assert_eq!(
+            marked.to_string(),
+            "(25r STR OR 30s CHA), (25s AGL OR 30s CHA)"
+        );
+
+        for (str_v, agl_v, cha_v) in [(25, 25, 0), (0, 0, 30), (25, 0, 0), (0, 25, 0)] {
+            let stats = StatMap::from(std::collections::HashMap::from([
+                (Stat::Strength, str_v),
+                (Stat::Agility, agl_v),
+                (Stat::Charisma, cha_v),
+            ]));
+            let expected = (str_v >= 25 && agl_v >= 25) || cha_v >= 30;
+            assert_eq!(mixed.satisfied_by(&stats), expected);
+        }
+
+        // (a AND b) OR (c AND d) takes an OR clause per pick of one atom from each side
+        let req = parse_req("25 STR AND 25 AGL OR 25 CHA AND 25 WLL").unwrap();
+        assert_eq!(req.len(), 4);
+        assert!(req.iter().all(|c| c.clause_type == ClauseType::Or));
+
+        // picking the same atom twice leaves just that atom
+        let req = parse_req("25r STR AND 25 AGL OR 25r STR").unwrap();
+        assert_eq!(req, parse_req("25r STR, 25s AGL OR 25r STR").unwrap());
+    }
+
+    #[test]
+    fn parenthesized_groups() {
+        // an AND group nested in an OR distributes the same as without the parens
+        let req = parse_req("(25 STR AND 25 AGL) OR 30 CHA").unwrap();
+        assert_eq!(req, parse_req("25 STR AND 25 AGL OR 30 CHA").unwrap());
+        assert_eq!(
+            req.to_string(),
+            "(30r CHA OR 25s STR), (30r CHA OR 25s AGL)"
+        );
+
+        // an OR group inside an AND stays one OR clause next to the rest
+        let req = parse_req("(25 STR OR 25 AGL) AND 40 FTD").unwrap();
+        assert_eq!(req, parse_req("25 STR OR 25 AGL, 40 FTD").unwrap());
+        assert_eq!(req.to_string(), "40s FTD, (25r STR OR 25r AGL)");
+
+        let req = parse_req("40 FTD AND (25 STR OR 25 AGL)").unwrap();
+        assert_eq!(req, parse_req("40 FTD, 25 STR OR 25 AGL").unwrap());
+
+        // groups on both sides, and nested further
+        let req = parse_req("(25 STR AND 25 AGL) OR (30 CHA AND 30 WLL)").unwrap();
+        assert_eq!(
+            req,
+            parse_req("25 STR AND 25 AGL OR 30 CHA AND 30 WLL").unwrap()
+        );
+        let req = parse_req("((25 STR OR 25 AGL) AND 40 FTD) OR 50 CHA").unwrap();
+        assert_eq!(
+            req,
+            parse_req("25r STR OR 25r AGL OR 50r CHA, 40s FTD OR 50r CHA").unwrap()
+        );
+        assert_eq!(parse_req(&req.to_string()).unwrap(), req);
+
+        assert!(parse_req("(25 STR AND 25 AGL OR 30 CHA").is_err());
+        assert!(parse_req("(25 STR AND) OR 30 CHA").is_err());
+        assert!(parse_req("25 STR AND (!20 SDW)").is_err());
+    }
+
+    #[test]
+    fn distribution_is_capped() {
+        let terms = |n: i64| {
+            (0..n)
+                .map(|i| format!("{v} STR AND {v} AGL", v = 20 + i))
+                .collect::<Vec<_>>()
+                .join(" OR ")
+        };
+        let input = terms(18);
+
+        let start = std::time::Instant::now();
+        let err = parse_req(&input).unwrap_err();
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        assert!(
+            matches!(&err, DeepError::Req { offset: 0, message, .. } if message.contains("too many clauses")),
+            "unexpected error: {err}"
+        );
+
+        // right at the cap is fine
+        let input = terms(Requirement::DNF_CAP.ilog2().into());
+        assert_eq!(parse_req(&input).unwrap().len(), Requirement::DNF_CAP);
+    }
+
     #[test]
     fn bunch_of_random_stuff() {
         // silentheart reqs
@@ -486,6 +939,153 @@ mod tests {
         assert_eq!(req.clauses.len(), 2);
     }
 
+    #[test]
+    fn mixed_or_reducibility_warns() {
+        let (req, warnings) = parse_req_verbose("40 HVY, 25S STR OR 25 AGL").unwrap();
+        assert_eq!(req.to_string(), "40s HVY, (25r AGL OR 25s STR)");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("25s STR OR 25 AGL"), "{}", warnings[0]);
+
+        // all explicit or all implicit is unambiguous
+        let (_, warnings) = parse_req_verbose("25S STR OR 25R AGL").unwrap();
+        assert!(warnings.is_empty());
+        let (_, warnings) = parse_req_verbose("(25 STR OR 25 AGL)").unwrap();
+        assert!(warnings.is_empty());
+
+        assert!(parse_req_verbose("25S STR OR").is_err());
+    }
+
+    #[test]
+    fn errors_point_at_the_failure() {
+        let err = parse_req("  40 FTD, 25 STR OR").unwrap_err();
+        let DeepError::Req {
+            message,
+            offset,
+            snippet,
+        } = &err
+        else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(*offset, 17);
+        assert_eq!(message, "unexpected 'OR'");
+        assert_eq!(snippet, "FTD, 25 STR OR");
+
+        // a one-liner for the CLI
+        assert!(!err.to_string().contains('\n'), "{err}");
+
+        let err = parse_req("(35 cha").unwrap_err();
+        assert!(
+            matches!(&err, DeepError::Req { offset: 7, message, .. } if message == "expected `)`"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn many_reports_every_bad_line() {
+        let reqs = parse_reqs("# talents\n40 STR\n\n// mantras\n  25 FLM OR 25 ICE\n").unwrap();
+        assert_eq!(reqs.len(), 2);
+
+        let err = parse_reqs("40 STR\n25 AGL OR\n30 FTD\n  (35 cha").unwrap_err();
+        let DeepError::Reqs(errors) = &err else {
+            panic!("unexpected error: {err}");
+        };
+        let at: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                DeepError::Reqfile { line, column, .. } => (*line, *column),
+                _ => panic!("unexpected error: {e}"),
+            })
+            .collect();
+        assert_eq!(at, [(2, Some(8)), (4, Some(10))]);
+    }
+
+    #[test]
+    fn negated_atoms() {
+        let req = parse_req("40 STR, !20 SDW").unwrap();
+        assert_eq!(req, parse_req("40 STR, NOT 20 SDW").unwrap());
+        assert_eq!(req.to_string(), "40s STR, NOT 20s SDW");
+        assert_eq!(parse_req(&req.to_string()).unwrap(), req);
+        assert_eq!(
+            req.used_stats(),
+            std::collections::HashSet::from([Stat::Strength, Stat::Shadowcast])
+        );
+
+        let stats = |pairs: &[(Stat, i64)]| StatMap(pairs.iter().copied().collect());
+        assert!(req.satisfied_by(&stats(&[(Stat::Strength, 40), (Stat::Shadowcast, 19)])));
+        assert!(!req.satisfied_by(&stats(&[(Stat::Strength, 40), (Stat::Shadowcast, 20)])));
+        assert!(!req.satisfied_by(&stats(&[(Stat::Strength, 39)])));
+
+        // an OR next to a NOT: either alternative, as long as SDW stays low
+        let req = parse_req("25 STR OR 25 AGL, not 20 SDW").unwrap();
+        assert_eq!(req.to_string(), "(25r STR OR 25r AGL), NOT 20s SDW");
+        assert!(req.satisfied_by(&stats(&[(Stat::Agility, 25)])));
+        assert!(!req.satisfied_by(&stats(&[(Stat::Agility, 25), (Stat::Shadowcast, 30)])));
+        assert!(!req.satisfied_by(&stats(&[(Stat::Shadowcast, 5)])));
+
+        // the solver never raises a negated stat, and min_cost ignores it
+        assert_eq!(req.min_statmap().get(&Stat::Shadowcast), 0);
+        assert_eq!(req.min_cost(), 25);
+        assert!(req.to_dnf().is_err());
+
+        // NOT takes a single atom, never a whole AND or OR
+        assert!(parse_req("25 STR OR !20 SDW").is_err());
+        assert!(parse_req("NOT20 SDW").is_err());
+    }
+
+    #[test]
+    fn nested_parens_flatten() {
+        let groups = [
+            [
+                "25 STR OR 25 AGL",
+                "(25 STR OR 25 AGL)",
+                "((25 STR OR 25 AGL))",
+                "(((25 STR OR 25 AGL)))",
+            ],
+            ["90 FTD", "(90 FTD)", "((90 FTD))", "(((90 FTD)))"],
+            [
+                "40 STR, 25 STR OR 25 AGL",
+                "(40 STR), ((25 STR OR 25 AGL))",
+                "((40 STR)), (((25 STR OR 25 AGL)))",
+                "(((40 STR))), (25 STR OR 25 AGL)",
+            ],
+            [
+                "(LHT + MED + HVY = 90)",
+                "((LHT + MED + HVY = 90))",
+                "(((LHT + MED + HVY = 90)))",
+                "LHT + MED + HVY = 90",
+            ],
+        ];
+
+        for variants in groups {
+            let flat = parse_req(variants[0]).unwrap();
+            for input in &variants[1..] {
+                let req = parse_req(input).unwrap_or_else(|e| panic!("{input}: {e}"));
+                assert_eq!(req, flat, "{input}");
+            }
+        }
+    }
+
+    #[test]
+    fn empty_parens_normalize() {
+        for input in ["()", "( )", "(())", "( ( ) )", "x := (())"] {
+            let req = parse_req(input).unwrap_or_else(|e| panic!("{input}: {e}"));
+            assert!(req.is_empty(), "{input}");
+            assert!(req.clauses.is_empty(), "{input}");
+        }
+
+        for input in [
+            "(,)",
+            "(OR)",
+            "(()",
+            "())",
+            "((),)",
+            "(() 25 STR)",
+            "(), 25 STR",
+        ] {
+            assert!(parse_req(input).is_err(), "{input} should be rejected");
+        }
+    }
+
     #[test]
     fn qualified_identifiers() {
         let req = parse_req("origin:castaway => talent:voidwalker_contract := 90 FTD").unwrap();
@@ -510,6 +1110,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn numeric_tokens_are_not_identifiers() {
+        // a value can't be a prereq or a name
+        assert!(parse_req("90 => x").is_err());
+        assert!(parse_req("9 := 40 str").is_err());
+        assert!(parse_req("talent:9 := 40 str").is_err());
+
+        let req = parse_req("90 str").unwrap();
+        assert!(req.name.is_none());
+
+        // a leading digit is still fine as long as it isn't only digits
+        let req = parse_req("11th_legion_plate => 9th_ring := 40 str").unwrap();
+        assert_eq!(req.name, Some("9th_ring".to_string()));
+        assert_eq!(
+            req.prereqs,
+            BTreeSet::from([PrereqGroup::single("11th_legion_plate")])
+        );
+    }
+
     #[test]
     fn assign_disambiguation() {
         let req = parse_req("foo:= 90 FTD").unwrap();
@@ -4,16 +4,18 @@ use crate::model::opt::OptionalGroup;
 use crate::model::req::{PrereqGroup, Requirement, Timing};
 use crate::model::reqfile::Reqfile;
 use crate::model::stat::StatRange;
+use crate::util::edit_distance;
 use crate::util::reqtree::ReqTree;
 use crate::util::traits::ReqVecExt;
 use std::collections::{HashMap, HashSet};
 use std::ops::RangeInclusive;
 use std::path::Path;
-use winnow::ascii::{digit1, multispace0};
-use winnow::combinator::{alt, eof, separated};
+use winnow::ascii::{digit1, multispace0, multispace1};
+use winnow::combinator::{alt, eof, not, separated};
 use winnow::prelude::*;
+use winnow::token::{rest, take_while};
 
-use super::req::{identifier, prereq_group, requirement, stat};
+use super::req::{identifier, parse_error, parse_req, prereq_group, requirement, stat};
 
 enum BaseReqfileLine {
     Requirement(Requirement),
@@ -42,6 +44,12 @@ enum ReqfileLine {
         stat: Stat,
         range: RangeInclusive<u32>,
     },
+    /// A line of the form '- identifier'
+    /// Removes the named requirement from whatever reqfile this one gets merged into.
+    Exclude(String),
+    /// A line of the form '@key value', build context like the race (see
+    /// [`Reqfile::metadata`])
+    Directive { key: String, value: String },
 }
 
 impl ReqfileLine {
@@ -50,7 +58,9 @@ impl ReqfileLine {
             ReqfileLine::Unspecified(base)
             | ReqfileLine::ForceRequired(base)
             | ReqfileLine::Optional { base, .. } => Some(base),
-            ReqfileLine::RangeSpecifier { .. } => None,
+            ReqfileLine::RangeSpecifier { .. }
+            | ReqfileLine::Exclude(_)
+            | ReqfileLine::Directive { .. } => None,
         }
     }
 
@@ -59,7 +69,9 @@ impl ReqfileLine {
             ReqfileLine::Unspecified(base)
             | ReqfileLine::ForceRequired(base)
             | ReqfileLine::Optional { base, .. } => Some(base),
-            ReqfileLine::RangeSpecifier { .. } => None,
+            ReqfileLine::RangeSpecifier { .. }
+            | ReqfileLine::Exclude(_)
+            | ReqfileLine::Directive { .. } => None,
         }
     }
 
@@ -68,11 +80,11 @@ impl ReqfileLine {
     }
 }
 
-fn parse_reqfile_line(input: &str) -> std::result::Result<ReqfileLine, String> {
-    let input = input.trim();
+/// Fails with a [`DeepError::Req`] whose offset points into `input`
+fn parse_reqfile_line(input: &str) -> Result<ReqfileLine> {
     reqfile_line
-        .parse(input)
-        .map_err(|e| format!("Parse error: {e}"))
+        .parse(input.trim())
+        .map_err(|e| parse_error(input, &e))
 }
 
 fn reqfile_line(input: &mut &str) -> ModalResult<ReqfileLine> {
@@ -80,6 +92,8 @@ fn reqfile_line(input: &mut &str) -> ModalResult<ReqfileLine> {
     alt((
         optional_line,
         force_required_line,
+        exclude_line,
+        directive_line,
         range_specifier,
         base_reqfile_line.map(ReqfileLine::Unspecified),
     ))
@@ -90,7 +104,7 @@ fn reqfile_line(input: &mut &str) -> ModalResult<ReqfileLine> {
 fn optional_line(input: &mut &str) -> ModalResult<ReqfileLine> {
     let weight = digit1
         .try_map(|s: &str| s.parse::<i64>())
-        .verify(|&n| (1..=20).contains(&n))
+        .verify(|n| OptionalGroup::WEIGHT_RANGE.contains(n))
         .parse_next(input)?;
 
     let _ = (multispace0, ';', multispace0).parse_next(input)?;
@@ -105,6 +119,27 @@ fn force_required_line(input: &mut &str) -> ModalResult<ReqfileLine> {
     Ok(ReqfileLine::ForceRequired(base))
 }
 
+// exclude_line = '-' identifier eof
+fn exclude_line(input: &mut &str) -> ModalResult<ReqfileLine> {
+    let _ = ('-', multispace0).parse_next(input)?;
+    let name = identifier.parse_next(input)?;
+    let _ = (multispace0, eof).parse_next(input)?;
+    Ok(ReqfileLine::Exclude(name))
+}
+
+// directive_line = '@' key rest
+fn directive_line(input: &mut &str) -> ModalResult<ReqfileLine> {
+    let _ = '@'.parse_next(input)?;
+    let key = macro_name.parse_next(input)?;
+    let _ = multispace1.parse_next(input)?;
+    let value = rest.parse_next(input)?;
+
+    Ok(ReqfileLine::Directive {
+        key: key.to_lowercase(),
+        value: value.trim().to_string(),
+    })
+}
+
 // range_specifier = number "<=" stat "<=" number eof
 fn range_specifier(input: &mut &str) -> ModalResult<ReqfileLine> {
     let lower = range_bound.parse_next(input)?;
@@ -163,10 +198,52 @@ fn dependency_with_identifier(input: &mut &str) -> ModalResult<BaseReqfileLine>
     Ok(BaseReqfileLine::DependencyWithIdentifier { prereqs, dependent })
 }
 
+// macro_definition = "def" macro_name '=' requirement
+fn macro_definition<'a>(input: &mut &'a str) -> ModalResult<(&'a str, &'a str)> {
+    let _ = ("def", multispace1).parse_next(input)?;
+    let name = macro_name.parse_next(input)?;
+    let _ = (multispace0, '=', not('>'), multispace0).parse_next(input)?;
+    let body = rest.parse_next(input)?;
+    Ok((name, body))
+}
+
+fn macro_name<'a>(input: &mut &'a str) -> ModalResult<&'a str> {
+    take_while(1.., ('A'..='Z', 'a'..='z', '0'..='9', '_')).parse_next(input)
+}
+
+/// Substitutes every `$NAME` in `line` with the body of the macro, as-is. The body is not
+/// parenthesized, so `$BASE, 40 HVY` extends the macro's clauses with one more.
+fn expand_macros(
+    line: &str,
+    macros: &HashMap<String, String>,
+) -> std::result::Result<String, String> {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(at) = rest.find('$') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at + 1..];
+
+        let name = macro_name
+            .parse_next(&mut rest)
+            .map_err(|_| "Expected a macro name after '$'".to_string())?;
+        let body = macros
+            .get(name)
+            .ok_or_else(|| format!("Undefined macro '${name}'"))?;
+        out.push_str(body);
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
 struct ParsedLine {
     rf_line: ReqfileLine,
     line_num: usize,
     timing: Timing,
+    /// The last `# label` comment directly above a requirement, at or before this line and
+    /// within its section
+    label: Option<String>,
 }
 
 struct ReqfileIndex {
@@ -202,6 +279,7 @@ fn build_index(lines: &[ParsedLine]) -> Result<ReqfileIndex> {
                 } else {
                     return Err(DeepError::Reqfile {
                         line: line.line_num,
+                        column: None,
                         message: "Optional annotations '+' or ';' must be used \
                         at the requirement definition, not in a dependency statement, unless \
                         the definition is in the dependency statement itself."
@@ -221,6 +299,7 @@ fn build_index(lines: &[ParsedLine]) -> Result<ReqfileIndex> {
                 {
                     return Err(DeepError::Reqfile {
                         line: line.line_num + 1,
+                        column: None,
                         message: format!("Duplicate identifier: {name}"),
                     });
                 }
@@ -261,6 +340,7 @@ fn validate_no_ambiguous_anonymous(lines: &[ParsedLine]) -> Result<()> {
             if other_anon.is_some() {
                 return Err(DeepError::Reqfile {
                     line: line.line_num,
+                    column: None,
                     message: format!(
                         "You may not have duplicate anonymous requirements if either of them have prerequisites: {}",
                         req.name_or_default()
@@ -273,6 +353,38 @@ fn validate_no_ambiguous_anonymous(lines: &[ParsedLine]) -> Result<()> {
     Ok(())
 }
 
+/// `general` and `post` requirements are solved differently, so the same requirement
+/// can't show up under both sections.
+fn validate_consistent_timing(lines: &[ParsedLine]) -> Result<()> {
+    let mut seen: HashMap<String, (Timing, usize)> = HashMap::new();
+
+    for line in lines {
+        let Some(BaseReqfileLine::Requirement(req)) = line.rf_line.base() else {
+            continue;
+        };
+
+        let key = req.name_or_default();
+        match seen.get(&key) {
+            Some((timing, first_line)) if *timing != line.timing => {
+                return Err(DeepError::Reqfile {
+                    line: line.line_num,
+                    column: None,
+                    message: format!(
+                        "Requirement '{key}' is under {:?}: here but under {timing:?}: on line {first_line}",
+                        line.timing
+                    ),
+                });
+            }
+            Some(_) => {}
+            None => {
+                seen.insert(key, (line.timing, line.line_num));
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn resolve_dependencies(lines: &mut [ParsedLine], index: &ReqfileIndex) -> Result<()> {
     #[allow(
         clippy::cast_possible_truncation,
@@ -290,6 +402,7 @@ fn resolve_dependencies(lines: &mut [ParsedLine], index: &ReqfileIndex) -> Resul
                     if !req.prereqs.is_empty() {
                         return Err(DeepError::Reqfile {
                             line: *line_num as usize,
+                            column: None,
                             message: format!("'{name}' has multiple prerequisite assignments."),
                         });
                     }
@@ -300,6 +413,7 @@ fn resolve_dependencies(lines: &mut [ParsedLine], index: &ReqfileIndex) -> Resul
             None => {
                 return Err(DeepError::Reqfile {
                     line: *line_num as usize,
+                    column: None,
                     message: format!("Dependent: no variable named '{name}'."),
                 });
             }
@@ -329,6 +443,7 @@ fn validate_tree(
     if let Some(cycle) = tree.find_cycle() {
         return Err(DeepError::Reqfile {
             line: 0,
+            column: None,
             message: format!(
                 "Prereqs cannot be dependent on each other. Found cycle: {}",
                 cycle.join(" => ")
@@ -349,6 +464,7 @@ fn validate_tree(
                 if !dependent_line.rf_line.is_explicit_optional() {
                     return Err(DeepError::Reqfile {
                         line: line.line_num,
+                        column: None,
                         message: format!(
                             "'{}' was declared as optional, however one of its \
                                     dependents are required: '{} at line {}'.\n\
@@ -410,11 +526,15 @@ fn apply_force_required(
     str_to_idx: &HashMap<String, usize>,
     optional: &mut [OptionalGroup],
     marked_opt: &mut HashSet<String>,
-) {
+) -> HashSet<String> {
+    let mut forced = HashSet::new();
+
     for line in lines {
         if let ReqfileLine::ForceRequired(base) = &line.rf_line
             && let BaseReqfileLine::Requirement(req) = base
         {
+            forced.insert(req.name_or_default());
+
             for req in tree
                 .all_prereqs(&req.name_or_default())
                 .iter()
@@ -433,6 +553,8 @@ fn apply_force_required(
             }
         }
     }
+
+    forced
 }
 
 fn collect_required_reqs(
@@ -469,6 +591,7 @@ fn build_final_ranges(lines: &[ParsedLine]) -> Result<Vec<StatRange>> {
             if !matches!(line.timing, Timing::Post) {
                 return Err(DeepError::Reqfile {
                     line: line.line_num,
+                    column: None,
                     message: format!(
                         "Range directives are only allowed in the Post stage for now, \
                         but one was found not in Post: '{}'.",
@@ -480,6 +603,7 @@ fn build_final_ranges(lines: &[ParsedLine]) -> Result<Vec<StatRange>> {
             if range.start() > range.end() {
                 return Err(DeepError::Reqfile {
                     line: line.line_num,
+                    column: None,
                     message: format!(
                         "Range directive for '{}' is inverted. The lower bound must not \
                         exceed the upper bound.",
@@ -491,6 +615,7 @@ fn build_final_ranges(lines: &[ParsedLine]) -> Result<Vec<StatRange>> {
             if !seen.insert(*stat) {
                 return Err(DeepError::Reqfile {
                     line: line.line_num,
+                    column: None,
                     message: format!(
                         "'{}' already has a range directive in this stage.",
                         stat.name()
@@ -508,16 +633,115 @@ fn build_final_ranges(lines: &[ParsedLine]) -> Result<Vec<StatRange>> {
     Ok(ranges)
 }
 
-fn validate_and_transform(mut lines: Vec<ParsedLine>) -> Result<Reqfile> {
-    let index = build_index(&lines)?;
-    validate_no_ambiguous_anonymous(&lines)?;
-    resolve_dependencies(&mut lines, &index)?;
+/// The names excluded with '- name'. A reqfile can't exclude one of its own requirements,
+/// exclusions only apply to what it gets merged into.
+fn collect_exclusions(
+    lines: &[ParsedLine],
+    str_to_idx: &HashMap<String, usize>,
+) -> Result<HashSet<String>> {
+    let mut excluded = HashSet::new();
 
-    let tree = build_req_tree(&lines);
-    validate_tree(&lines, &tree, &index.str_to_idx)?;
+    for line in lines {
+        if let ReqfileLine::Exclude(name) = &line.rf_line {
+            if str_to_idx.contains_key(name) {
+                return Err(DeepError::Reqfile {
+                    line: line.line_num + 1,
+                    column: None,
+                    message: format!("'{name}' is both defined and excluded."),
+                });
+            }
+
+            excluded.insert(name.clone());
+        }
+    }
+
+    Ok(excluded)
+}
+
+/// The '@key value' directives. Each key may only be set once, and keys other than
+/// [`Reqfile::DIRECTIVES`] are kept but warned about.
+fn collect_metadata(lines: &[ParsedLine]) -> Result<HashMap<String, String>> {
+    let mut metadata = HashMap::new();
+
+    for line in lines {
+        if let ReqfileLine::Directive { key, value } = &line.rf_line {
+            if !Reqfile::DIRECTIVES.contains(&key.as_str()) {
+                log::warn!(
+                    "Unknown directive '@{key}' on line {}, expected one of: {}",
+                    line.line_num + 1,
+                    Reqfile::DIRECTIVES.join(", ")
+                );
+            }
+
+            if metadata.insert(key.clone(), value.clone()).is_some() {
+                return Err(DeepError::Reqfile {
+                    line: line.line_num + 1,
+                    column: None,
+                    message: format!("Directive '@{key}' is set more than once."),
+                });
+            }
+        }
+    }
+
+    Ok(metadata)
+}
+
+fn collect_labels(lines: &[ParsedLine]) -> HashMap<String, String> {
+    lines
+        .iter()
+        .filter_map(|line| match (line.rf_line.base(), &line.label) {
+            (Some(BaseReqfileLine::Requirement(req)), Some(label)) => {
+                Some((req.name_or_default(), label.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// What [`validate`] gathers on the way, for [`validate_and_transform`] to build from
+struct Validated {
+    index: ReqfileIndex,
+    tree: ReqTree,
+    final_ranges: Vec<StatRange>,
+    excluded: HashSet<String>,
+    metadata: HashMap<String, String>,
+}
+
+/// Runs every semantic check on the parsed lines, resolving dependency statements in place.
+/// Shared by [`parse_reqfile_str`] and [`check_reqfile_str`], so both reject the same input.
+fn validate(lines: &mut [ParsedLine]) -> Result<Validated> {
+    validate_consistent_timing(lines)?;
+    let index = build_index(lines)?;
+    validate_no_ambiguous_anonymous(lines)?;
+    resolve_dependencies(lines, &index)?;
+
+    let tree = build_req_tree(lines);
+    validate_tree(lines, &tree, &index.str_to_idx)?;
+
+    let final_ranges = build_final_ranges(lines)?;
+    let excluded = collect_exclusions(lines, &index.str_to_idx)?;
+    let metadata = collect_metadata(lines)?;
+
+    Ok(Validated {
+        index,
+        tree,
+        final_ranges,
+        excluded,
+        metadata,
+    })
+}
+
+fn validate_and_transform(mut lines: Vec<ParsedLine>) -> Result<Reqfile> {
+    let Validated {
+        index,
+        tree,
+        final_ranges,
+        excluded,
+        metadata,
+    } = validate(&mut lines)?;
 
     let (mut optional, mut marked_opt) = build_optional_groups(&lines, &tree, &index.str_to_idx);
-    apply_force_required(
+    let forced = apply_force_required(
         &lines,
         &tree,
         &index.str_to_idx,
@@ -526,7 +750,7 @@ fn validate_and_transform(mut lines: Vec<ParsedLine>) -> Result<Reqfile> {
     );
 
     let (general, post) = collect_required_reqs(&lines, &marked_opt);
-    let final_ranges = build_final_ranges(&lines)?;
+    let labels = collect_labels(&lines);
 
     Ok(Reqfile {
         general,
@@ -534,45 +758,155 @@ fn validate_and_transform(mut lines: Vec<ParsedLine>) -> Result<Reqfile> {
         final_ranges,
         optional,
         implicit: HashMap::new(),
+        forced,
+        excluded,
+        metadata,
+        labels,
     })
 }
 
-// TODO! this should really be the only entry point to create a Reqfile,
-// since it also validates if the payload will be semantically correct
-pub(crate) fn parse_reqfile_str(content: &str) -> Result<Reqfile> {
+/// `header` on its own, in any case and optionally followed by ':', so `Free:` and `POST` are
+/// headers but a misspelt `FREEE:` or a `free_hand := 40 str` requirement aren't.
+fn is_header(line: &str, header: &str) -> bool {
+    let line = line.trim();
+    let word = line.strip_suffix(':').unwrap_or(line).trim_end();
+    word.eq_ignore_ascii_case(header)
+}
+
+/// The section header a line that failed to parse was probably meant to be, if it's a lone
+/// word (optionally followed by ':') within a couple of typos of one, e.g. `FRE:` or `psot`.
+fn near_miss_header(line: &str) -> Option<&'static str> {
+    let word = line.strip_suffix(':').unwrap_or(line).trim().to_uppercase();
+    if word.is_empty() || !word.chars().all(char::is_alphabetic) {
+        return None;
+    }
+
+    ["FREE", "POST"]
+        .into_iter()
+        .map(|header| (edit_distance(&word, header), header))
+        .filter(|(distance, _)| *distance <= 2)
+        .min()
+        .map(|(_, header)| header)
+}
+
+fn parse_lines(content: &str) -> Result<Vec<ParsedLine>> {
     let mut lines: Vec<ParsedLine> = vec![];
 
     let mut current = Timing::Free;
+    let mut label: Option<String> = None;
+    // the `#` comment on the line before, which only becomes the label if this line turns out
+    // to be a requirement. Any other comment is just a comment
+    let mut pending_label: Option<String> = None;
+    let mut macros: HashMap<String, String> = HashMap::new();
+
+    for (i, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        let comment_above = pending_label.take();
+        if let Some(comment) = line.strip_prefix('#') {
+            let comment = comment.trim_start_matches('#').trim();
+            if !comment.is_empty() {
+                pending_label = Some(comment.to_string());
+            }
+            continue;
+        }
+
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+
+        let err = |message: String| DeepError::Reqfile {
+            line: i + 1,
+            column: None,
+            message,
+        };
 
-    for (i, line) in content.lines().enumerate() {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+        // definitions can expand earlier macros, but never register a line of their own
+        if let Ok((name, body)) = macro_definition.parse_peek(line).map(|(_, def)| def) {
+            let body = expand_macros(body, &macros).map_err(err)?;
+
+            match parse_req(&body) {
+                Ok(req) if req.name.is_some() => {
+                    return Err(err(format!(
+                        "Macro '{name}' can't define a named requirement"
+                    )));
+                }
+                Ok(_) => {}
+                Err(e) => return Err(err(format!("Macro '{name}': {e}"))),
+            }
+
+            if macros.insert(name.to_string(), body).is_some() {
+                return Err(err(format!("Macro '{name}' is already defined")));
+            }
             continue;
         }
 
-        if line.to_uppercase().starts_with("FREE") {
+        let expanded = expand_macros(line, &macros).map_err(err)?;
+        // offsets into an expanded line don't map back onto what was written
+        let column = |offset: usize| {
+            let lead = raw.len() - raw.trim_start().len();
+            (expanded == line).then(|| raw[..lead + offset].chars().count() + 1)
+        };
+        let line = expanded.as_str();
+
+        if is_header(line, "FREE") {
             current = Timing::Free;
+            label = None;
             continue;
         }
 
-        if line.to_uppercase().starts_with("POST") {
+        if is_header(line, "POST") {
             current = Timing::Post;
+            label = None;
             continue;
         }
 
-        let parsed = parse_reqfile_line(line).map_err(|e| DeepError::Reqfile {
-            line: i + 1,
-            message: e,
+        let parsed = parse_reqfile_line(line).map_err(|e| match (near_miss_header(line), e) {
+            (Some(header), _) => err(format!(
+                "Unknown section header '{line}', did you mean '{header}:'?"
+            )),
+            (
+                None,
+                DeepError::Req {
+                    message,
+                    offset,
+                    snippet,
+                },
+            ) => DeepError::Reqfile {
+                line: i + 1,
+                column: column(offset),
+                message: format!("{message} (near '{snippet}')"),
+            },
+            (None, e) => err(e.to_string()),
         })?;
 
+        if comment_above.is_some() && matches!(parsed.base(), Some(BaseReqfileLine::Requirement(_)))
+        {
+            label = comment_above;
+        }
+
         lines.push(ParsedLine {
             rf_line: parsed,
             line_num: i,
             timing: current,
+            label: label.clone(),
         });
     }
 
-    validate_and_transform(lines)
+    Ok(lines)
+}
+
+// TODO! this should really be the only entry point to create a Reqfile,
+// since it also validates if the payload will be semantically correct
+pub(crate) fn parse_reqfile_str(content: &str) -> Result<Reqfile> {
+    validate_and_transform(parse_lines(content)?)
+}
+
+/// Parse and validate a reqfile without building the optional groups or requirement lists.
+pub(crate) fn check_reqfile_str(content: &str) -> Result<()> {
+    let mut lines = parse_lines(content)?;
+    validate(&mut lines)?;
+
+    Ok(())
 }
 
 /// Parse '.req' files into a Reqfile struct
@@ -584,14 +918,25 @@ pub(crate) fn parse_reqfile(path: &Path) -> Result<Reqfile> {
     parse_reqfile_str(&content)
 }
 
-/// Generate a reqfile string from a Reqfile struct.
-pub(crate) fn gen_reqfile(payload: &Reqfile) -> String {
+/// Generate a reqfile string from a Reqfile struct, which parses back into the same
+/// requirements, optional groups (a group's weight goes on its root, shared prereqs are
+/// written once without one), `+` markers, exclusions, ranges and directives.
+pub(crate) fn gen_reqfile<'a>(payload: &'a Reqfile) -> String {
     use std::fmt::Write as _;
 
     let mut output = String::new();
 
     output.push_str("# Auto-generated reqfile\n\n");
 
+    let mut metadata: Vec<(&String, &String)> = payload.metadata.iter().collect();
+    metadata.sort();
+    for (key, value) in &metadata {
+        let _ = writeln!(output, "@{key} {value}");
+    }
+    if !metadata.is_empty() {
+        output.push('\n');
+    }
+
     // remove spaces from names
     //
     // we also give anonymous reqs with prereqs an identifier
@@ -638,13 +983,16 @@ pub(crate) fn gen_reqfile(payload: &Reqfile) -> String {
             if req.name.as_ref().is_none_or(|n| !referenced.contains(n)) {
                 root_weights
                     .entry(req.name_or_default())
-                    .or_insert(group.weight.clamp(1, 20));
+                    .or_insert(group.weight.clamp(
+                        *OptionalGroup::WEIGHT_RANGE.start(),
+                        *OptionalGroup::WEIGHT_RANGE.end(),
+                    ));
             }
         }
     }
 
-    let mut opt_general: Vec<(Requirement, Option<i64>)> = vec![];
-    let mut opt_post: Vec<(Requirement, Option<i64>)> = vec![];
+    let mut opt_general: Vec<(Requirement, Option<i64>, Option<&'a str>)> = vec![];
+    let mut opt_post: Vec<(Requirement, Option<i64>, Option<&'a str>)> = vec![];
     let mut seen: HashSet<String> = HashSet::new();
     let mut opt_prereq_refs: HashSet<String> = HashSet::new();
 
@@ -663,7 +1011,11 @@ pub(crate) fn gen_reqfile(payload: &Reqfile) -> String {
                 continue;
             }
 
-            let line = (name_anon(req), root_weights.get(&key).copied());
+            let line = (
+                name_anon(req),
+                root_weights.get(&key).copied(),
+                payload.labels.get(&key).map(String::as_str),
+            );
             match timing {
                 Timing::Free => opt_general.push(line),
                 Timing::Post => opt_post.push(line),
@@ -671,15 +1023,31 @@ pub(crate) fn gen_reqfile(payload: &Reqfile) -> String {
         }
     }
 
-    let is_forced =
-        |req: &Requirement| req.name.as_ref().is_some_and(|n| opt_prereq_refs.contains(n));
-    let general_forced = general.iter().map(is_forced).collect::<Vec<_>>();
-    let post_forced = post.iter().map(is_forced).collect::<Vec<_>>();
+    // prereqs of optional reqs that ended up required need the marker to parse back the same
+    let is_forced = |(orig, req): (&Requirement, &Requirement)| {
+        payload.is_forced(&orig.name_or_default())
+            || req
+                .name
+                .as_ref()
+                .is_some_and(|n| opt_prereq_refs.contains(n))
+    };
+    let general_forced = payload
+        .general
+        .iter()
+        .zip(&general)
+        .map(is_forced)
+        .collect::<Vec<_>>();
+    let post_forced = payload
+        .post
+        .iter()
+        .zip(&post)
+        .map(is_forced)
+        .collect::<Vec<_>>();
 
     general.map_names(clean_name);
     post.map_names(clean_name);
 
-    for (req, _) in opt_general.iter_mut().chain(opt_post.iter_mut()) {
+    for (req, _, _) in opt_general.iter_mut().chain(opt_post.iter_mut()) {
         req.name = req.name.take().map(|n| clean_name(&n));
         req.prereqs = req
             .prereqs
@@ -691,16 +1059,50 @@ pub(crate) fn gen_reqfile(payload: &Reqfile) -> String {
     output.push_str("# USER REQS\n\n");
     output.push_str("Free:\n");
 
-    for (req, forced) in general.iter().zip(&general_forced) {
-        let _ = writeln!(output, "{}{req}", if *forced { "+ " } else { "" });
+    let label = |req: &Requirement| {
+        payload
+            .labels
+            .get(&req.name_or_default())
+            .map(String::as_str)
+    };
+    let required_line = |((orig, req), forced): ((&Requirement, &Requirement), &bool)| {
+        let marker = if *forced { "+ " } else { "" };
+        (label(orig), format!("{marker}{req}"))
+    };
+    let optional_line =
+        |(req, weight, label): &(Requirement, Option<i64>, Option<&'a str>)| match weight {
+            Some(w) => (*label, format!("{w}; {req}")),
+            None => (*label, req.to_string()),
+        };
+
+    write_labeled(
+        &mut output,
+        payload
+            .general
+            .iter()
+            .zip(&general)
+            .zip(&general_forced)
+            .map(required_line),
+    );
+
+    let mut excluded: Vec<&String> = payload.excluded.iter().collect();
+    excluded.sort();
+    for name in excluded {
+        let _ = writeln!(output, "- {}", clean_name(name));
     }
 
     if !post.is_empty() || !payload.final_ranges.is_empty() {
         output.push_str("\nPost:\n");
 
-        for (req, forced) in post.iter().zip(&post_forced) {
-            let _ = writeln!(output, "{}{req}", if *forced { "+ " } else { "" });
-        }
+        write_labeled(
+            &mut output,
+            payload
+                .post
+                .iter()
+                .zip(&post)
+                .zip(&post_forced)
+                .map(required_line),
+        );
 
         for r in &payload.final_ranges {
             let _ = writeln!(
@@ -718,34 +1120,40 @@ pub(crate) fn gen_reqfile(payload: &Reqfile) -> String {
 
         if !opt_general.is_empty() {
             output.push_str("\nFree:\n");
-
-            for (req, weight) in &opt_general {
-                match weight {
-                    Some(w) => {
-                        let _ = writeln!(output, "{w}; {req}");
-                    }
-                    None => {
-                        let _ = writeln!(output, "{req}");
-                    }
-                }
-            }
+            write_labeled(&mut output, opt_general.iter().map(optional_line));
         }
 
         if !opt_post.is_empty() {
             output.push_str("\nPost:\n");
-
-            for (req, weight) in &opt_post {
-                match weight {
-                    Some(w) => {
-                        let _ = writeln!(output, "{w}; {req}");
-                    }
-                    None => {
-                        let _ = writeln!(output, "{req}");
-                    }
-                }
-            }
+            write_labeled(&mut output, opt_post.iter().map(optional_line));
         }
     }
 
     output
 }
+
+/// Writes one section's lines grouped by label, each labeled group under a `# label` comment
+/// in the order the labels first appear. Unlabeled lines go first, as anything after a label
+/// would parse back under it.
+fn write_labeled<'a>(output: &mut String, lines: impl Iterator<Item = (Option<&'a str>, String)>) {
+    use std::fmt::Write as _;
+
+    let mut groups: Vec<(Option<&str>, Vec<String>)> = vec![];
+    for (label, line) in lines {
+        match groups.iter_mut().find(|(l, _)| *l == label) {
+            Some((_, group)) => group.push(line),
+            None => groups.push((label, vec![line])),
+        }
+    }
+    groups.sort_by_key(|(label, _)| label.is_some());
+
+    for (label, group) in groups {
+        if let Some(label) = label {
+            let _ = write!(output, "\n# {label}\n");
+        }
+        for line in group {
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+}
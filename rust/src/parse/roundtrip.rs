@@ -0,0 +1,304 @@
+//! Property tests: random valid reqfiles must survive a `gen_reqfile` -> `parse_reqfile_str`
+//! round trip unchanged.
+
+use std::{collections::HashSet, fmt::Write as _};
+
+use proptest::{prelude::*, sample::subsequence};
+
+use super::reqfile::{gen_reqfile, parse_reqfile_str};
+use crate::{
+    Stat,
+    model::{
+        req::Requirement,
+        reqfile::Reqfile,
+        stat::{ATTUNEMENT, CORE, WEAPON},
+    },
+};
+
+fn stats() -> Vec<Stat> {
+    CORE.iter()
+        .chain(WEAPON)
+        .chain(ATTUNEMENT)
+        .copied()
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+struct AtomPlan {
+    stats: Vec<Stat>,
+    value: i64,
+    strict: bool,
+}
+
+#[derive(Clone, Debug)]
+enum ClausePlan {
+    And(AtomPlan),
+    Or(Vec<AtomPlan>),
+}
+
+/// One reqfile line, described loosely enough that shrinking stays meaningful.
+/// `into_source` resolves the plan into something the parser accepts
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug)]
+struct LinePlan {
+    named: bool,
+    prereq_mask: u8,
+    inline_prereqs: bool,
+    weight: Option<i64>,
+    forced: bool,
+    post: bool,
+    clauses: Vec<ClausePlan>,
+}
+
+#[derive(Clone, Debug)]
+struct ReqfilePlan {
+    lines: Vec<LinePlan>,
+    ranges: Vec<(Stat, u32, u32)>,
+}
+
+fn atom() -> impl Strategy<Value = AtomPlan> {
+    (subsequence(stats(), 1..=2), 1..=100i64, any::<bool>()).prop_map(|(stats, value, strict)| {
+        AtomPlan {
+            stats,
+            value,
+            strict,
+        }
+    })
+}
+
+fn clause() -> impl Strategy<Value = ClausePlan> {
+    prop_oneof![
+        3 => atom().prop_map(ClausePlan::And),
+        1 => prop::collection::vec(atom(), 2..=3).prop_map(ClausePlan::Or),
+    ]
+}
+
+fn line() -> impl Strategy<Value = LinePlan> {
+    (
+        any::<bool>(),
+        any::<u8>(),
+        any::<bool>(),
+        prop::option::of(1..=20i64),
+        any::<bool>(),
+        any::<bool>(),
+        prop::collection::vec(clause(), 0..=3),
+    )
+        .prop_map(
+            |(named, prereq_mask, inline_prereqs, weight, forced, post, clauses)| LinePlan {
+                named,
+                prereq_mask,
+                inline_prereqs,
+                weight,
+                forced,
+                post,
+                clauses,
+            },
+        )
+}
+
+fn reqfile() -> impl Strategy<Value = ReqfilePlan> {
+    (
+        prop::collection::vec(line(), 1..=8),
+        subsequence(stats(), 0..=2),
+        prop::collection::vec((0..=50u32, 0..=50u32), 2),
+    )
+        .prop_map(|(lines, range_stats, bounds)| ReqfilePlan {
+            lines,
+            ranges: range_stats
+                .into_iter()
+                .zip(bounds)
+                .map(|(s, (a, b))| (s, a, b))
+                .collect(),
+        })
+}
+
+fn atom_source(atom: &AtomPlan) -> String {
+    let r = if atom.strict { "s" } else { "r" };
+
+    if let [stat] = atom.stats.as_slice() {
+        format!("{}{r} {}", atom.value, stat.short_name())
+    } else {
+        let sum = atom
+            .stats
+            .iter()
+            .map(Stat::short_name)
+            .collect::<Vec<_>>()
+            .join(" + ");
+        format!("{sum} = {}{r}", atom.value)
+    }
+}
+
+fn body_source(clauses: &[ClausePlan]) -> String {
+    let clauses = clauses
+        .iter()
+        .map(|clause| match clause {
+            ClausePlan::And(atom) => atom_source(atom),
+            ClausePlan::Or(atoms) => {
+                // identical atoms collapse into a single-atom OR, which prints as an AND
+                let mut seen = HashSet::new();
+                atoms
+                    .iter()
+                    .map(atom_source)
+                    .filter(|a| seen.insert(a.clone()))
+                    .collect::<Vec<_>>()
+                    .join(" OR ")
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if clauses.is_empty() {
+        "()".to_string()
+    } else {
+        clauses.join(", ")
+    }
+}
+
+impl ReqfilePlan {
+    /// Resolve the plan into reqfile source. Prereqs only point backwards (no cycles) and
+    /// only at named reqs, a line is only optional when all of its dependents are, and
+    /// anonymous reqs never share a body (they would share a key).
+    fn into_source(self) -> String {
+        let n = self.lines.len();
+        let mut bodies: HashSet<String> = HashSet::new();
+
+        let names: Vec<Option<String>> = self
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let body = body_source(&line.clauses);
+                (line.named || !bodies.insert(body)).then(|| format!("r{i}"))
+            })
+            .collect();
+
+        let prereqs: Vec<Vec<usize>> = self
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                if names[i].is_none() {
+                    return vec![];
+                }
+                (0..i)
+                    .filter(|&j| j < 8 && line.prereq_mask & (1 << j) != 0 && names[j].is_some())
+                    .collect()
+            })
+            .collect();
+
+        let mut optional = vec![false; n];
+        for i in (0..n).rev() {
+            let dependents_optional = (i + 1..n).all(|j| !prereqs[j].contains(&i) || optional[j]);
+            optional[i] = self.lines[i].weight.is_some() && dependents_optional;
+        }
+
+        let mut free = String::new();
+        let mut post = String::new();
+
+        for (i, line) in self.lines.iter().enumerate() {
+            let out = if line.post { &mut post } else { &mut free };
+
+            let prefix = match (optional[i], line.forced) {
+                (true, _) => format!("{}; ", line.weight.unwrap_or(1)),
+                (false, true) => "+ ".to_string(),
+                (false, false) => String::new(),
+            };
+
+            let deps = prereqs[i]
+                .iter()
+                .filter_map(|&j| names[j].clone())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let body = body_source(&line.clauses);
+
+            match &names[i] {
+                Some(name) if deps.is_empty() => {
+                    let _ = writeln!(out, "{prefix}{name} := {body}");
+                }
+                Some(name) if line.inline_prereqs => {
+                    let _ = writeln!(out, "{prefix}{deps} => {name} := {body}");
+                }
+                Some(name) => {
+                    let _ = writeln!(out, "{deps} => {name}");
+                    let _ = writeln!(out, "{prefix}{name} := {body}");
+                }
+                None => {
+                    let _ = writeln!(out, "{prefix}{body}");
+                }
+            }
+        }
+
+        for (stat, a, b) in self.ranges {
+            let _ = writeln!(
+                post,
+                "{} <= {} <= {}",
+                a.min(b),
+                stat.short_name(),
+                a.max(b)
+            );
+        }
+
+        format!("Free:\n{free}\nPost:\n{post}")
+    }
+}
+
+/// Order-independent view of a reqfile, so equal payloads compare equal regardless of
+/// line or group ordering.
+#[derive(Debug, PartialEq, Eq)]
+struct Shape {
+    general: Vec<Requirement>,
+    post: Vec<Requirement>,
+    final_ranges: Vec<(Stat, u32, u32)>,
+    optional: Vec<(i64, Vec<Requirement>, Vec<Requirement>)>,
+}
+
+fn sorted(reqs: impl IntoIterator<Item = Requirement>) -> Vec<Requirement> {
+    let mut reqs: Vec<_> = reqs.into_iter().collect();
+    reqs.sort();
+    reqs
+}
+
+fn shape(reqfile: &Reqfile) -> Shape {
+    let mut final_ranges: Vec<_> = reqfile
+        .final_ranges
+        .iter()
+        .map(|r| (r.stat, *r.range.start(), *r.range.end()))
+        .collect();
+    final_ranges.sort();
+
+    let mut optional: Vec<_> = reqfile
+        .optional
+        .iter()
+        .map(|g| {
+            (
+                g.weight,
+                sorted(g.general.iter().cloned()),
+                sorted(g.post.iter().cloned()),
+            )
+        })
+        .collect();
+    optional.sort();
+
+    Shape {
+        general: sorted(reqfile.general.iter().cloned()),
+        post: sorted(reqfile.post.iter().cloned()),
+        final_ranges,
+        optional,
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn gen_then_parse_round_trips(plan in reqfile()) {
+        let source = plan.into_source();
+        let parsed = parse_reqfile_str(&source)
+            .map_err(|e| TestCaseError::fail(format!("generated source rejected: {e}\n{source}")))?;
+
+        let generated = gen_reqfile(&parsed);
+        let reparsed = parse_reqfile_str(&generated)
+            .map_err(|e| TestCaseError::fail(format!("gen_reqfile output rejected: {e}\n{generated}")))?;
+
+        prop_assert_eq!(shape(&parsed), shape(&reparsed), "\n{}\n---\n{}", source, generated);
+    }
+}
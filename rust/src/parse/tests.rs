@@ -2,7 +2,9 @@ use std::collections::{BTreeSet, HashSet};
 
 use super::reqfile::{gen_reqfile, parse_reqfile_str};
 use crate::Stat;
-use crate::model::req::PrereqGroup;
+use crate::error::DeepError;
+use crate::model::req::{PrereqGroup, Requirement};
+use crate::model::reqfile::Reqfile;
 
 #[test]
 fn reqfile_prereqs() {
@@ -154,6 +156,13 @@ fn reqfile_gen_with_optional() {
         assert_eq!(group.general, new_group.general);
         assert_eq!(group.post, new_group.post);
     }
+
+    assert_eq!(payload.forced, new_payload.forced, "{gen_content}");
+    assert_eq!(
+        payload.content_id(),
+        new_payload.content_id(),
+        "{gen_content}"
+    );
 }
 
 #[test]
@@ -184,6 +193,18 @@ fn reqfile_gen_optional_shared_prereq() {
             .expect(&gen_content);
         assert_eq!(group.general, new_group.general);
     }
+
+    // the shared prereq is written once, without a weight of its own
+    let shared_lines: Vec<&str> = gen_content
+        .lines()
+        .filter(|l| l.contains("shared :="))
+        .collect();
+    assert_eq!(shared_lines, ["shared := 10s STR"], "{gen_content}");
+    assert_eq!(
+        payload.content_id(),
+        new_payload.content_id(),
+        "{gen_content}"
+    );
 }
 
 #[test]
@@ -658,6 +679,55 @@ fn optional_shared_prereq() {
     assert!(names_b.contains("opt_b"));
 }
 
+#[test]
+fn coalesce_optional_merges_equal_groups() {
+    let content = r"
+        Free:
+        root := 5 cha
+        shared := 10 str
+
+        1; opt_a := 20 int
+        2; opt_b := 30 ftd
+
+        root => shared
+        shared => opt_a
+        shared => opt_b
+        ";
+
+    let payload = parse_reqfile_str(content).unwrap();
+
+    // the groups overlap but neither holds the other, so both stay
+    let mut coalesced = payload.clone();
+    coalesced.coalesce_optional();
+    assert_eq!(coalesced.optional.len(), 2);
+
+    // merging a reqfile into itself doubles every group
+    let mut doubled = payload.clone() + payload;
+    assert_eq!(doubled.optional.len(), 4);
+    doubled.coalesce_optional();
+    assert_eq!(doubled.optional.len(), 2);
+
+    let mut weights: Vec<i64> = doubled.optional.iter().map(|g| g.weight).collect();
+    weights.sort_unstable();
+    assert_eq!(weights, [2, 4]);
+
+    // opt_b needing opt_a makes opt_a's group a subset of opt_b's, which is kept apart
+    let nested = r"
+        Free:
+        shared := 10 str
+
+        1; opt_a := 20 int
+        2; opt_b := 30 ftd
+
+        shared => opt_a
+        opt_a => opt_b
+        ";
+
+    let mut payload = parse_reqfile_str(nested).unwrap();
+    payload.coalesce_optional();
+    assert_eq!(payload.optional.len(), 2);
+}
+
 // === Error Case Tests ===
 
 #[test]
@@ -829,3 +899,246 @@ fn range_specifier_coexists_with_reqs() {
     assert_eq!(payload.final_ranges.len(), 1);
     assert_eq!(payload.final_ranges[0].stat, Stat::Intelligence);
 }
+
+// === Validation-only parse ===
+
+#[test]
+fn check_accepts_valid_reqfile() {
+    let content = r"
+        Free:
+        base := 25 STR
+        1; opt := 40 INT
+        base => opt
+
+        Post:
+        75r hvy
+        5 <= INT <= 20
+        ";
+
+    assert!(Reqfile::check(content).is_ok());
+}
+
+#[test]
+fn check_rejects_cycle() {
+    let content = r"
+        Free:
+        a := 10 str
+        b := 20 int
+
+        a => b
+        b => a
+        ";
+
+    let err = Reqfile::check(content).unwrap_err().to_string();
+    assert!(err.contains("cycle"), "unexpected error: {err}");
+
+    // same verdict as a full parse
+    assert!(parse_reqfile_str(content).is_err());
+}
+
+#[test]
+fn check_rejects_self_exclusion() {
+    let content = "Free:\na := 10 str\n- a";
+
+    let err = Reqfile::check(content).unwrap_err().to_string();
+    assert!(
+        err.contains("both defined and excluded"),
+        "unexpected error: {err}"
+    );
+    assert!(parse_reqfile_str(content).is_err());
+}
+
+#[test]
+fn check_rejects_duplicate_directive() {
+    let content = "@race Khan\n@race Celtor\nFree:\na := 10 str";
+
+    let err = Reqfile::check(content).unwrap_err().to_string();
+    assert!(err.contains("more than once"), "unexpected error: {err}");
+    assert!(parse_reqfile_str(content).is_err());
+}
+
+#[test]
+fn same_requirement_across_timings_rejected() {
+    let content = r"
+        Free:
+        flame := 40 flm
+
+        Post:
+        flame := 40 flm
+        ";
+
+    let err = parse_reqfile_str(content).unwrap_err().to_string();
+    assert!(
+        err.contains("'flame'") && err.contains("Free"),
+        "unexpected error: {err}"
+    );
+
+    let content = r"
+        Free:
+        40 flm
+
+        Post:
+        40 flm
+        ";
+
+    assert!(parse_reqfile_str(content).is_err());
+}
+
+#[test]
+fn macros_expand_into_requirements() {
+    let content = r"
+        def BASE = 25 str, 25 ftd
+
+        Free:
+        $BASE, 40 hvy
+        heavy := $BASE, 60 hvy
+        ";
+
+    let rf = parse_reqfile_str(content).unwrap();
+    assert_eq!(rf.general.len(), 2);
+
+    let expected: Requirement = "25 str, 25 ftd, 40 hvy".parse().unwrap();
+    assert!(
+        rf.general
+            .iter()
+            .any(|r| r.name.is_none() && r.clauses == expected.clauses)
+    );
+
+    let heavy = rf
+        .general
+        .iter()
+        .find(|r| r.name.as_deref() == Some("heavy"))
+        .unwrap();
+    let expected: Requirement = "25 str, 25 ftd, 60 hvy".parse().unwrap();
+    assert_eq!(heavy.clauses, expected.clauses);
+}
+
+#[test]
+fn undefined_macro_reports_line() {
+    let content = "Free:\n40 str\n$BASE, 40 hvy\ndef BASE = 25 str\n";
+
+    let err = parse_reqfile_str(content).unwrap_err();
+    assert!(
+        matches!(&err, DeepError::Reqfile { line: 3, message, .. } if message.contains("$BASE")),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn forced_requirements_are_reported() {
+    let content = r"
+        Free:
+        p1 := 10 str
+        + p2 := 20 int
+        + lone := 30 ftd
+
+        1; has_prereqs := 42 hvy
+
+        p1, p2 => has_prereqs
+        ";
+
+    let payload = parse_reqfile_str(content).unwrap();
+    assert!(payload.is_forced("p2"));
+    assert!(payload.is_forced("lone"));
+    assert!(!payload.is_forced("p1"));
+    assert!(!payload.is_forced("has_prereqs"));
+
+    // the markers survive generation, even where nothing optional depends on the req
+    let regenerated = parse_reqfile_str(&gen_reqfile(&payload)).unwrap();
+    assert_eq!(regenerated.forced, payload.forced);
+}
+
+#[test]
+fn accented_identifiers() {
+    let content = r"
+        Free:
+        épée := 40 str
+        épée => coup_de_grâce := 60 str
+        ";
+
+    let parsed = parse_reqfile_str(content);
+
+    if cfg!(feature = "unicode-identifiers") {
+        let payload = parsed.unwrap();
+        let dependent = payload
+            .general
+            .iter()
+            .find(|r| r.name.as_deref() == Some("coup_de_grâce"))
+            .unwrap();
+        assert_eq!(
+            dependent.prereqs,
+            BTreeSet::from([PrereqGroup::single("épée")])
+        );
+    } else {
+        assert!(parsed.is_err());
+    }
+}
+
+#[test]
+fn near_miss_headers_suggest_section() {
+    for (header, suggestion) in [("FRE:", "FREE:"), ("freee:", "FREE:"), ("POS", "POST:")] {
+        let content = format!("{header}\n40 str\n");
+
+        let err = parse_reqfile_str(&content).unwrap_err();
+        assert!(
+            matches!(&err, DeepError::Reqfile { line: 1, message, .. } if message.contains(suggestion)),
+            "unexpected error for {header}: {err}"
+        );
+    }
+
+    // anything further off is left to the regular parse error
+    let err = parse_reqfile_str("Frozen:\n40 str\n").unwrap_err();
+    assert!(!err.to_string().contains("did you mean"), "{err}");
+}
+
+#[test]
+fn header_prefixed_names_are_requirements() {
+    let rf = parse_reqfile_str(
+        "free:\nfree_hand := 40 str\nPost\npost_x := 30 agl\nFREE :\nfreeform := 20 cha\n",
+    )
+    .unwrap();
+
+    let names = |reqs: &Vec<Requirement>| -> Vec<String> {
+        reqs.iter().filter_map(|r| r.name.clone()).collect()
+    };
+    assert_eq!(names(&rf.general), ["free_hand", "freeform"]);
+    assert_eq!(names(&rf.post), ["post_x"]);
+}
+
+#[test]
+fn parse_errors_carry_a_column() {
+    let content = "Free:\n40 str\n    heavy := 40 str, 30 fdt\n";
+
+    let err = parse_reqfile_str(content).unwrap_err();
+    let DeepError::Reqfile {
+        line,
+        column,
+        message,
+    } = &err
+    else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(*line, 3);
+    // the failure is at the comma before the bad clause
+    assert_eq!(*column, Some(20));
+    assert!(message.contains("30 fdt"), "{message}");
+    assert!(
+        err.to_string().starts_with("Parse on line 3, column 20:"),
+        "{err}"
+    );
+
+    // through a macro, the offset no longer lines up with the written line
+    let content = "def BASE = 25 str\nFree:\n$BASE, 30 fdt\n";
+    let err = parse_reqfile_str(content).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            DeepError::Reqfile {
+                line: 3,
+                column: None,
+                ..
+            }
+        ),
+        "unexpected error: {err}"
+    );
+}
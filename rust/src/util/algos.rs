@@ -10,16 +10,30 @@ use crate::{
     error::{DeepError, Result},
     model::reqfile::Reqfile,
     model::stat::StatRange,
-    req::{Atom, Clause, ClauseType, PrereqGroup, Reducability, Requirement},
-    util::statmap::StatMap,
+    req::{PrereqGroup, Reducability, Requirement},
+    util::{name_to_identifier, solve, statmap::StatMap},
 };
 
 use crate::constants::KHAN_REQ_REDUCTION;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{BTreeSet, HashMap, HashSet, VecDeque},
     ops::RangeInclusive,
 };
 
+/// The stats the Shrine of Order leaves a character with, given their pre-shrine stats `pre`
+/// (innates included) and their race's innates `racial`. Mirrors the algorithm the deepwoken
+/// builder uses.
+///
+/// Every invested stat is set to the average of the invested points, except that no
+/// non-attunement stat may drop more than [`SHRINE_ORDER_MAX_LOSS`] below where it was; the
+/// points those stats keep are taken evenly from the rest. Values are floored, and points
+/// left over from flooring are handed back a whole round at a time.
+///
+/// The result is a fixed point for builds that didn't bottleneck: shrining it again changes
+/// nothing.
+///
+/// [`SHRINE_ORDER_MAX_LOSS`]: crate::constants::SHRINE_ORDER_MAX_LOSS
 #[must_use]
 #[allow(
     clippy::cast_precision_loss,
@@ -166,32 +180,23 @@ fn namespace_of(id: &str) -> &str {
     id.split_once(':').map_or(id, |(ns, _)| ns)
 }
 
-fn empty_named(name: &str) -> Requirement {
-    let mut req = Requirement::new();
-    req.name = Some(name.to_string());
-    req
+/// Whether `race` lowers equipment and weapon requirements (Khan's Versatile)
+pub(crate) fn is_khan(race: &Aspect) -> bool {
+    race.name == "Khan"
 }
 
-fn strictify(req: &Requirement) -> Requirement {
-    let mut clauses: BTreeSet<Clause> = BTreeSet::new();
-
-    for clause in &req.clauses {
-        clauses.insert(Clause {
-            clause_type: clause.clause_type.clone(),
-            atoms: clause
-                .atoms
-                .iter()
-                .cloned()
-                .map(|a| a.reducability(Reducability::Strict))
-                .collect(),
-        });
+/// Applies Khan's reduction to `req` if it's a weapon or equipment requirement, going by the
+/// namespace of `id`. Power gates are left alone.
+pub(crate) fn khan_reduce(id: &str, req: &mut Requirement) {
+    if [Weapon::NAMESPACE, Equipment::NAMESPACE].contains(&namespace_of(id)) {
+        req.add_to_stat_atoms(-KHAN_REQ_REDUCTION);
     }
+}
 
-    Requirement {
-        name: req.name.clone(),
-        prereqs: req.prereqs.clone(),
-        clauses,
-    }
+fn empty_named(name: &str) -> Requirement {
+    let mut req = Requirement::new();
+    req.name = Some(name.to_string());
+    req
 }
 
 enum Emit {
@@ -200,7 +205,50 @@ enum Emit {
     Post(Requirement),
 }
 
+/// Where a build's points go, as computed by [`BuildConfig::plan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlanCost {
+    /// Points the race hands out for free.
+    pub racial: i64,
+    /// Points invested before the shrine, innates excluded.
+    pub pre_shrine: i64,
+    /// Points lost to rounding when the shrine evens stats out.
+    pub shrine_loss: i64,
+    /// Points invested after the shrine to meet the post requirements.
+    pub post_shrine: i64,
+}
+
+/// A build's stats at each stage: the pre-shrine stats that meet the `Free:` requirements,
+/// those same stats after the Shrine of Order, and the final stats once the `Post:`
+/// requirements are met on top. Every map includes the racial innates.
+#[derive(Debug, Clone)]
+pub struct BuildPlan {
+    pub reqfile: Reqfile,
+    pub racial: StatMap,
+    pub pre_shrine: StatMap,
+    pub shrine_order: StatMap,
+    pub post_shrine: StatMap,
+    pub cost: PlanCost,
+}
+
 /// The configuration for a build that affect requirement generation.
+///
+/// Start from [`BuildConfig::builder`] rather than filling in the fields:
+///
+/// ```
+/// use deepwoken::util::algos::BuildConfig;
+///
+/// let config = BuildConfig::builder()
+///     .talent("Aerogliding")
+///     .weapon("Adretian Axe")
+///     .race("Khan")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(config.reqs, ["talent:aerogliding", "weapon:adretian_axe"]);
+/// ```
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BuildConfig {
     /// Controls whether the requirement generation will output weapon requirements as
     /// strict or reducible.
@@ -212,6 +260,11 @@ pub struct BuildConfig {
     /// Puts weapon requirements in the Free: block instead of constraining it to Post.
     pub allow_weapons_preshrine: bool,
 
+    /// Lets vaulted talents and mantras be picked, which can't be obtained in game anymore.
+    ///
+    /// Default: false (picking one is an error)
+    pub allow_vaulted: bool,
+
     /// Qualified ids (`ns:name`) of everything the build must obtain.
     pub reqs: Vec<String>,
     /// Qualified ids (`ns:name`) of reqs that are given as facts (origin, race).
@@ -227,11 +280,162 @@ pub struct BuildConfig {
 
     pub final_ranges: HashMap<Stat, RangeInclusive<u32>>,
 
-    /// Use optional reqfiles
+    /// Use optional reqfiles. Serialized as reqfile text.
     pub use_presets: Vec<Reqfile>,
 }
 
+/// Chainable construction of a [`BuildConfig`], see [`BuildConfig::builder`].
+///
+/// Names are turned into ids with [`name_to_identifier`], so in-game names and map keys both
+/// work.
+#[derive(Default)]
+pub struct BuildConfigBuilder {
+    config: BuildConfig,
+}
+
+impl BuildConfigBuilder {
+    fn req(mut self, namespace: &str, name: &str) -> Self {
+        self.config
+            .reqs
+            .push(format!("{namespace}:{}", name_to_identifier(name)));
+        self
+    }
+
+    #[must_use]
+    pub fn talent(self, name: &str) -> Self {
+        self.req(Talent::NAMESPACE, name)
+    }
+
+    #[must_use]
+    pub fn mantra(self, name: &str) -> Self {
+        self.req(Mantra::NAMESPACE, name)
+    }
+
+    #[must_use]
+    pub fn weapon(self, name: &str) -> Self {
+        self.req(Weapon::NAMESPACE, name)
+    }
+
+    #[must_use]
+    pub fn outfit(self, name: &str) -> Self {
+        self.req(Outfit::NAMESPACE, name)
+    }
+
+    #[must_use]
+    pub fn race(mut self, name: &str) -> Self {
+        self.config.race = Some(name_to_identifier(name));
+        self
+    }
+
+    /// See [`BuildConfig::disable_som_weapons`]
+    #[must_use]
+    pub fn disable_som_weapons(mut self, disable: bool) -> Self {
+        self.config.disable_som_weapons = disable;
+        self
+    }
+
+    /// See [`BuildConfig::allow_weapons_preshrine`]
+    #[must_use]
+    pub fn allow_weapons_preshrine(mut self, allow: bool) -> Self {
+        self.config.allow_weapons_preshrine = allow;
+        self
+    }
+
+    /// See [`BuildConfig::allow_vaulted`]
+    #[must_use]
+    pub fn allow_vaulted(mut self, allow: bool) -> Self {
+        self.config.allow_vaulted = allow;
+        self
+    }
+
+    /// Merges `preset` into the generated reqfile, see [`BuildConfig::use_presets`]
+    #[must_use]
+    pub fn preset(mut self, preset: Reqfile) -> Self {
+        self.config.use_presets.push(preset);
+        self
+    }
+
+    /// # Errors
+    /// [`DeepError::ReqfileBuild`] if the config has nothing to build: no talent, mantra,
+    /// weapon or outfit, and no preset.
+    pub fn build(self) -> Result<BuildConfig> {
+        if self.config.reqs.is_empty() && self.config.use_presets.is_empty() {
+            return Err(DeepError::ReqfileBuild(
+                "Empty build config: add a talent, mantra, weapon, outfit or preset".into(),
+            ));
+        }
+
+        Ok(self.config)
+    }
+}
+
 impl BuildConfig {
+    #[must_use]
+    pub fn builder() -> BuildConfigBuilder {
+        BuildConfigBuilder::default()
+    }
+
+    /// # Errors
+    /// [`DeepError::SerdeError`] if `json` isn't a serialized config, or a preset in it
+    /// isn't a valid reqfile.
+    pub fn from_json(json: &str) -> Result<BuildConfig> {
+        serde_json::from_str(json).map_err(DeepError::from)
+    }
+
+    /// # Errors
+    /// [`DeepError::SerdeError`] if serialization fails.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(DeepError::from)
+    }
+
+    /// Errors on picks that can't be had: a vaulted talent or mantra (unless
+    /// [`BuildConfig::allow_vaulted`]), or two talents where either lists the other as
+    /// exclusive. Ids not in `data` are left for [`BuildConfig::to_reqfile`] to report.
+    fn check_picks(&self, data: &DeepData) -> Result<()> {
+        let mut talents: Vec<(&str, &Talent)> = vec![];
+
+        for id in &self.reqs {
+            let Some((namespace, key)) = id.split_once(':') else {
+                continue;
+            };
+
+            let vaulted = match namespace {
+                Talent::NAMESPACE => data.get_talent(key).is_some_and(|talent| {
+                    talents.push((id, talent));
+                    talent.vaulted
+                }),
+                Mantra::NAMESPACE => data.get_mantra(key).is_some_and(|mantra| mantra.vaulted),
+                _ => false,
+            };
+
+            if vaulted && !self.allow_vaulted {
+                return Err(DeepError::ReqfileBuild(format!(
+                    "'{id}' is vaulted and can't be obtained (see allow_vaulted)"
+                )));
+            }
+        }
+
+        let excludes = |talent: &Talent, other: &Talent| {
+            let other = name_to_identifier(&other.name);
+            talent
+                .exclusive
+                .iter()
+                .any(|name| name_to_identifier(name) == other)
+        };
+
+        for (i, (id, talent)) in talents.iter().enumerate() {
+            for (other_id, other) in &talents[i + 1..] {
+                if excludes(talent, other) || excludes(other, talent) {
+                    return Err(DeepError::ReqfileBuild(format!(
+                        "'{id}' and '{other_id}' are mutually exclusive"
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn build_req(&self, data: &DeepData, id: &str) -> Result<Emit> {
         let (namespace, key) = id
             .split_once(':')
@@ -271,13 +475,13 @@ impl BuildConfig {
                 )))?;
 
                 let mut req = if self.disable_som_weapons {
-                    strictify(&weapon.requirement(key))
+                    weapon.requirement(key).with_reducability(Reducability::Strict)
                 } else {
                     weapon.requirement(key)
                 };
 
                 if self.is_khan(data)? {
-                    req.add_to_stat_atoms(-KHAN_REQ_REDUCTION);
+                    khan_reduce(id, &mut req);
                 }
 
                 if self.allow_weapons_preshrine {
@@ -303,7 +507,7 @@ impl BuildConfig {
                 let mut req = equipment.requirement(key);
 
                 if self.is_khan(data)? {
-                    req.add_to_stat_atoms(-KHAN_REQ_REDUCTION);
+                    khan_reduce(id, &mut req);
                 }
 
                 Emit::General(req)
@@ -388,20 +592,89 @@ impl BuildConfig {
         Ok(())
     }
 
-    /// Whether the build's race lowers equipment and weapon requirements (Khan's Versatile).
-    fn is_khan(&self, data: &DeepData) -> Result<bool> {
-        let Some(race) = &self.race else {
-            return Ok(false);
+    /// Remaps ids that no longer exist in `data` (e.g. items renamed by a game update) to
+    /// their closest match, see [`DeepData::resolve_id`]. `race` is remapped the same way.
+    ///
+    /// Returns the ids that couldn't be matched, which are left as they were rather than
+    /// failing, so the rest of a saved build still loads.
+    pub fn migrate(&mut self, data: &DeepData) -> Vec<String> {
+        let mut unresolved: Vec<String> = vec![];
+        let mut migrate_id = |id: &mut String| match data.resolve_id(id) {
+            Some(resolved) => {
+                if resolved != *id {
+                    log::info!("migrated '{id}' to '{resolved}'");
+                    *id = resolved;
+                }
+            }
+            None => {
+                if !unresolved.contains(id) {
+                    unresolved.push(id.clone());
+                }
+            }
         };
 
-        let race = data
-            .get_aspect(race)
-            .ok_or(DeepError::ReqfileBuild(format!("Race not found: {race}")))?;
+        for id in self
+            .reqs
+            .iter_mut()
+            .chain(&mut self.given)
+            .chain(&mut self.post)
+            .chain(&mut self.granted)
+        {
+            migrate_id(id);
+        }
 
-        Ok(race.name == "Khan")
+        if let Some(race) = &mut self.race {
+            let mut id = format!("{}:{race}", Aspect::NAMESPACE);
+            migrate_id(&mut id);
+            if let Some((_, key)) = id.split_once(':') {
+                *race = key.to_string();
+            }
+        }
+
+        unresolved
+    }
+
+    /// The build's race: `race`, or failing that the first `@race` directive among the
+    /// presets.
+    fn race<'a>(&self, data: &'a DeepData) -> Result<Option<&'a Aspect>> {
+        if let Some(race) = &self.race {
+            return data
+                .get_aspect(race)
+                .map(Some)
+                .ok_or(DeepError::ReqfileBuild(format!("Race not found: {race}")));
+        }
+
+        for preset in &self.use_presets {
+            if let Some(race) = preset.race(data)? {
+                return Ok(Some(race));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Whether the build's [race](BuildConfig::race) is Khan, see [`is_khan`]
+    fn is_khan(&self, data: &DeepData) -> Result<bool> {
+        Ok(self.race(data)?.is_some_and(is_khan))
+    }
+
+    /// Generates the reqfile, solves its `Free:` requirements on top of the race's innates,
+    /// runs the shrine order, then solves the `Post:` requirements on top of the result.
+    ///
+    /// Optional requirements are left out, and the solve is greedy (see
+    /// [`solve::min_statmap`]), so the plan is a cheap build rather than the cheapest one.
+    /// Without a `race`, a `@race` directive from the presets is used instead.
+    pub fn plan(&self, data: &DeepData) -> Result<BuildPlan> {
+        let reqfile = self.to_reqfile(data)?;
+        let aspect = self.race(data)?;
+
+        Ok(plan_reqfile(reqfile, aspect))
     }
 
     /// Generates a reqfile from the given data.
+    ///
+    /// Errors if an id isn't in `data`, conflicts with another (two origins, two talents that
+    /// exclude each other), or is vaulted without [`BuildConfig::allow_vaulted`].
     pub fn to_reqfile(&self, data: &DeepData) -> Result<Reqfile> {
         let mut ret = Reqfile {
             general: vec![],
@@ -416,6 +689,10 @@ impl BuildConfig {
                 .collect(),
             optional: vec![],
             implicit: HashMap::new(),
+            forced: HashSet::new(),
+            excluded: HashSet::new(),
+            metadata: HashMap::new(),
+            labels: HashMap::new(),
         };
 
         ret.resolve_implicit(data);
@@ -438,6 +715,8 @@ impl BuildConfig {
             }
         }
 
+        self.check_picks(data)?;
+
         let mut emitted: HashSet<String> = HashSet::new();
 
         let graph = data.prereq_graph();
@@ -525,18 +804,7 @@ impl BuildConfig {
         Self::rewrite_edges(&mut ret.post, &known)?;
 
         if let Some(mantra_levels) = &self.required_mantra_levels {
-            let mut clause = Clause::new(ClauseType::And);
-            for (stat, lvl) in &mantra_levels.0 {
-                let lvl = (*lvl).max(1);
-
-                if lvl == 1 {
-                    clause.add_atom(Atom::reducible().stat(*stat).value(1));
-                } else {
-                    clause.add_atom(Atom::reducible().stat(*stat).value((lvl - 1) * 20));
-                }
-            }
-
-            let mut req = Requirement::from(clause);
+            let mut req = Requirement::from_mantra_levels(mantra_levels);
 
             req.name = Some("mantra_levels".into());
 
@@ -552,6 +820,42 @@ impl BuildConfig {
     }
 }
 
+/// The planning half of [`BuildConfig::plan`], for an already generated reqfile.
+pub(crate) fn plan_reqfile(reqfile: Reqfile, aspect: Option<&Aspect>) -> BuildPlan {
+    let racial = aspect.map(Aspect::innate_statmap).unwrap_or_default();
+    let invested =
+        |stats: &StatMap| aspect.map_or_else(|| stats.clone(), |a| stats.without_innate(a));
+
+    // innates are there from the start, so only ever build on top of them
+    let floor = StatMap(
+        racial
+            .iter()
+            .filter(|(stat, value)| **stat != Stat::Total && **value > 0)
+            .map(|(stat, value)| (*stat, *value))
+            .collect(),
+    );
+
+    let pre_shrine = solve::min_statmap_from(&floor, &reqfile.general);
+    let shrine_order = shrine_order_dwb(&pre_shrine, &racial);
+    let post_shrine = solve::min_statmap_from(&shrine_order, &reqfile.post);
+
+    let cost = PlanCost {
+        racial: floor.cost(),
+        pre_shrine: invested(&pre_shrine).cost(),
+        shrine_loss: pre_shrine.cost() - shrine_order.cost(),
+        post_shrine: post_shrine.cost() - shrine_order.cost(),
+    };
+
+    BuildPlan {
+        reqfile,
+        racial,
+        pre_shrine,
+        shrine_order,
+        post_shrine,
+        cost,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -563,10 +867,17 @@ mod tests {
         DeepData::from_json(&json).expect("parse bundle")
     }
 
+    fn bundled_data() -> DeepData {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/all.json");
+        let json = std::fs::read_to_string(path).expect("read bundled all.json");
+        DeepData::from_json(&json).expect("parse bundled all.json")
+    }
+
     fn config(reqs: &[&str], given: &[&str], race: Option<&str>) -> BuildConfig {
         BuildConfig {
             disable_som_weapons: false,
             allow_weapons_preshrine: false,
+            allow_vaulted: false,
             reqs: reqs.iter().map(ToString::to_string).collect(),
             given: given.iter().map(ToString::to_string).collect(),
             post: vec![],
@@ -656,6 +967,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn vaulted_and_exclusive_picks_error() {
+        let talent = |key: &str, vaulted: bool, exclusive: &str| {
+            format!(
+                r#""{key}":{{"name":"{key}","desc":"","rarity":"Common","category":"",
+                    "reqs":"10s STR","count_towards_talent_total":true,"vaulted":{vaulted},
+                    "voi":false,"exclusive":[{exclusive}]}}"#
+            )
+        };
+        let data = DeepData::from_json(&format!(
+            r#"{{"talents":{{{},{},{},{}}},
+                "mantras":{{"old_blast":{{"name":"Old Blast","desc":"","stars":1,
+                    "category":"","type":"Normal","attributes":[],"reqs":"10s FLM",
+                    "vaulted":true,"voi":false}}}}}}"#,
+            talent("rage", false, r#""Reflexes""#),
+            talent("reflexes", false, ""),
+            talent("calm", false, ""),
+            talent("spike_traps", true, ""),
+        ))
+        .unwrap();
+
+        let build = |reqs: &[&str], allow_vaulted: bool| {
+            let mut config = config(reqs, &[], None);
+            config.allow_vaulted = allow_vaulted;
+            config.to_reqfile(&data)
+        };
+
+        assert!(build(&["talent:rage", "talent:calm"], false).is_ok());
+
+        // only one side lists the other, either order is caught
+        for reqs in [
+            ["talent:rage", "talent:reflexes"],
+            ["talent:reflexes", "talent:rage"],
+        ] {
+            let msg = build(&reqs, true).unwrap_err().to_string();
+            assert!(
+                msg.contains("mutually exclusive"),
+                "unexpected error: {msg}"
+            );
+        }
+
+        for id in ["talent:spike_traps", "mantra:old_blast"] {
+            let msg = build(&[id], false).unwrap_err().to_string();
+            assert!(msg.contains("vaulted"), "unexpected error: {msg}");
+            assert!(build(&[id], true).is_ok());
+        }
+    }
+
     #[test]
     fn oath_timing_split() {
         let data = load_data();
@@ -847,4 +1206,207 @@ mod tests {
                 .any(|r| r.name.as_deref() == Some("origin:castaway") && r.is_empty())
         );
     }
+
+    #[test]
+    fn preset_race_gets_the_khan_reduction() {
+        let data = bundled_data();
+        let mut config = config(&["weapon:adretian_axe"], &[], None);
+        config.use_presets = vec![Reqfile::parse_str("@race Khan").unwrap()];
+
+        let plan = config.plan(&data).unwrap();
+        let axe = plan
+            .reqfile
+            .req_iter()
+            .find(|r| r.name.as_deref() == Some("weapon:adretian_axe"))
+            .unwrap();
+        assert_eq!(single_atom_value(axe), 35 - KHAN_REQ_REDUCTION);
+        assert_eq!(plan.cost.racial, 5);
+    }
+
+    #[test]
+    fn fixture_pulls_in_prereqs() {
+        let data = DeepData::test_fixture();
+        let rf = config(&["talent:a_world_without_song"], &[], None)
+            .to_reqfile(&data)
+            .unwrap();
+
+        let song = rf
+            .req_iter()
+            .find(|r| r.name.as_deref() == Some("talent:a_world_without_song"))
+            .unwrap();
+        assert_eq!(song.prereqs.len(), 1);
+
+        let blade = rf
+            .req_iter()
+            .find(|r| r.name.as_deref() == Some("talent:silencers_blade"))
+            .expect("prereq emitted");
+        assert!(blade.used_stats().contains(&Stat::Galebreathe));
+        assert!(rf.post.is_empty());
+    }
+
+    #[test]
+    fn fixture_weapon_is_post() {
+        let data = DeepData::test_fixture();
+        let rf = config(&["weapon:acherons_warspear"], &[], None)
+            .to_reqfile(&data)
+            .unwrap();
+
+        assert!(rf.general.is_empty());
+        assert_eq!(rf.post.len(), 1);
+        assert_eq!(
+            rf.post[0].to_string(),
+            "weapon:acherons_warspear := 40r MED"
+        );
+    }
+
+    #[test]
+    fn builder_matches_fields() {
+        let data = bundled_data();
+        let built = BuildConfig::builder()
+            .talent("Aerogliding")
+            .talent("action_surge")
+            .weapon("Adretian Axe")
+            .race("Khan")
+            .build()
+            .unwrap();
+
+        let filled = config(
+            &[
+                "talent:aerogliding",
+                "talent:action_surge",
+                "weapon:adretian_axe",
+            ],
+            &[],
+            Some("khan"),
+        );
+        assert_eq!(built.reqs, filled.reqs);
+        assert_eq!(
+            built.to_reqfile(&data).unwrap().generate(),
+            filled.to_reqfile(&data).unwrap().generate()
+        );
+
+        let empty = BuildConfig::builder().race("Khan").build();
+        assert!(matches!(empty, Err(DeepError::ReqfileBuild(_))));
+    }
+
+    #[test]
+    fn config_json_round_trip() {
+        let data = bundled_data();
+        let mut config = BuildConfig::builder()
+            .talent("Aerogliding")
+            .weapon("Adretian Axe")
+            .race("Khan")
+            .disable_som_weapons(true)
+            .preset(Reqfile::parse_str("Free:\nbase := 25 STR\n5; extra := 40 FTD").unwrap())
+            .build()
+            .unwrap();
+        config.post.push("talent:aerogliding".into());
+        config.required_mantra_levels = Some(StatMap::from(HashMap::from([(Stat::Flamecharm, 3)])));
+        config.final_ranges.insert(Stat::Agility, 10..=40);
+
+        let json = config.to_json().unwrap();
+        let back = BuildConfig::from_json(&json).unwrap();
+
+        assert_eq!(back.to_json().unwrap(), json);
+        assert_eq!(
+            back.to_reqfile(&data).unwrap().generate(),
+            config.to_reqfile(&data).unwrap().generate()
+        );
+
+        // every field is optional
+        let empty = BuildConfig::from_json("{}").unwrap();
+        assert!(empty.reqs.is_empty() && empty.race.is_none());
+    }
+
+    #[test]
+    fn renamed_ids_migrate() {
+        let data = bundled_data();
+        let mut config = config(
+            &[
+                "talent:aeroglidng",
+                "weapon:adretian_axe",
+                "talent:no_such_talent_at_all",
+            ],
+            &[],
+            Some("khann"),
+        );
+        config.post.push("talent:aeroglidng".into());
+
+        let unresolved = config.migrate(&data);
+        assert_eq!(unresolved, ["talent:no_such_talent_at_all"]);
+        assert_eq!(config.reqs[0], "talent:aerogliding");
+        assert_eq!(config.reqs[1], "weapon:adretian_axe");
+        assert_eq!(config.post, ["talent:aerogliding"]);
+        assert_eq!(config.race.as_deref(), Some("khan"));
+    }
+
+    #[test]
+    fn shrine_result_is_a_fixed_point() {
+        let pre = StatMap::from(HashMap::from([
+            (Stat::Strength, 40),
+            (Stat::Agility, 30),
+            (Stat::Fortitude, 35),
+            (Stat::Flamecharm, 20),
+        ]));
+        let racial = StatMap::new();
+
+        let post = shrine_order_dwb(&pre, &racial);
+        assert!(post.values().all(|v| (30..=32).contains(v)));
+        assert!(post.cost() <= pre.cost());
+        assert_eq!(shrine_order_dwb(&post, &racial), post);
+    }
+
+    #[test]
+    fn plan_solves_then_shrines() {
+        let data = bundled_data();
+        let plan = config(
+            &[
+                "talent:aerogliding",
+                "talent:action_surge",
+                "weapon:adretian_axe",
+            ],
+            &[],
+            Some("khan"),
+        )
+        .plan(&data)
+        .unwrap();
+
+        // innates are kept, and the free reqs are met before the shrine
+        assert_eq!(plan.racial.get(&Stat::Strength), 3);
+        assert_eq!(plan.pre_shrine.get(&Stat::Strength), 3);
+        assert!(
+            plan.reqfile
+                .general
+                .iter()
+                .all(|r| r.satisfied_by(&plan.pre_shrine))
+        );
+        assert_eq!(plan.pre_shrine.get(&Stat::Agility), 70);
+
+        assert_eq!(
+            plan.shrine_order,
+            plan.pre_shrine.shrine_order(&plan.racial)
+        );
+        assert!(plan.shrine_order.get(&Stat::Agility) < 70);
+
+        // the weapon is post-only, and khan knocks its req down
+        assert!(
+            plan.reqfile
+                .post
+                .iter()
+                .all(|r| r.satisfied_by(&plan.post_shrine))
+        );
+        assert!(
+            plan.shrine_order
+                .iter()
+                .all(|(stat, value)| plan.post_shrine.get(stat) >= *value)
+        );
+        assert!(plan.post_shrine.get(&Stat::HeavyWeapon) < 35);
+
+        assert_eq!(plan.cost.racial, 5);
+        assert_eq!(plan.cost.pre_shrine, plan.pre_shrine.cost() - 5);
+        assert_eq!(
+            plan.post_shrine.cost(),
+            plan.pre_shrine.cost() - plan.cost.shrine_loss + plan.cost.post_shrine
+        );
+    }
 }
@@ -1,5 +1,13 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+use log::warn;
 use reqwest::header::{ACCEPT, USER_AGENT};
-use serde::Deserialize;
+#[cfg(not(target_arch = "wasm32"))]
+use serde::Serialize;
+use serde::{Deserialize, de::DeserializeOwned};
 
 use crate::{
     data::DeepData,
@@ -16,66 +24,251 @@ pub struct GithubRelease {
 #[derive(Debug, Deserialize)]
 pub struct GithubAsset {
     pub name: String,
+    /// Size of the asset in bytes
+    pub size: u64,
     pub browser_download_url: String,
 }
 
+impl GithubRelease {
+    #[must_use]
+    pub fn asset_names(&self) -> Vec<&str> {
+        self.assets.iter().map(|a| a.name.as_str()).collect()
+    }
+
+    fn missing_asset(&self, wanted: &str) -> DeepError {
+        DeepError::NotFound(format!(
+            "Failed to find '{wanted}' in release {}, found files [{}] instead.",
+            self.tag_name,
+            self.assets
+                .iter()
+                .map(|a| format!("{} ({} bytes)", a.name, a.size))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ))
+    }
+}
+
+async fn fetch_json<T: DeserializeOwned>(url: &str) -> Result<T> {
+    let client = reqwest::Client::new();
+
+    let value = client
+        .get(url)
+        .header(USER_AGENT, "my-app/0.1")
+        .header(ACCEPT, "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<T>()
+        .await?;
+
+    Ok(value)
+}
+
+/// How to download a bundle from a host of your own, see [`DeepData::from_url`]
+#[derive(Clone, Debug, Default)]
+pub struct FetchOptions {
+    /// Gives up on the download after this long. No limit by default.
+    pub timeout: Option<Duration>,
+    /// Extra headers sent with the request, e.g. an `Authorization` for a private bucket
+    pub headers: Vec<(String, String)>,
+}
+
+async fn fetch_text(url: &str) -> Result<String> {
+    fetch_text_with(url, &FetchOptions::default()).await
+}
+
+async fn fetch_text_with(url: &str, opts: &FetchOptions) -> Result<String> {
+    let client = reqwest::Client::new();
+
+    let mut request = client.get(url).header(USER_AGENT, "my-app/0.1");
+    if let Some(timeout) = opts.timeout {
+        request = request.timeout(timeout);
+    }
+    for (name, value) in &opts.headers {
+        request = request.header(name, value);
+    }
+
+    let content = request.send().await?.error_for_status()?.text().await?;
+
+    Ok(content)
+}
+
+/// A downloaded payload that didn't parse is a bad release, not a parse error on our side.
+#[allow(clippy::needless_pass_by_value, reason = "used as a map_err callback")]
+fn decode_error(e: DeepError) -> DeepError {
+    DeepError::DecodeData(e.to_string())
+}
+
+/// The bundle [`DeepData::fetch_cached`] keeps, next to its [`CacheMeta`] sidecar
+#[cfg(not(target_arch = "wasm32"))]
+const CACHE_FILE: &str = "all.json";
+#[cfg(not(target_arch = "wasm32"))]
+const CACHE_META_FILE: &str = "all.meta.json";
+
+/// What release a cached bundle came from, and when it was last known to be the latest
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheMeta {
+    tag_name: String,
+    /// Unix timestamp, in seconds
+    fetched_at: u64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// The cached bundle in `dir`, if there's a complete and readable one
+#[cfg(not(target_arch = "wasm32"))]
+fn read_cache(dir: &Path) -> Option<(CacheMeta, DeepData)> {
+    let meta = std::fs::read_to_string(dir.join(CACHE_META_FILE)).ok()?;
+    let meta: CacheMeta = serde_json::from_str(&meta).ok()?;
+    let data = std::fs::read_to_string(dir.join(CACHE_FILE)).ok()?;
+    let mut data = DeepData::from_json(&data).ok()?;
+    data.set_version(Some(meta.tag_name.clone()));
+
+    Some((meta, data))
+}
+
+/// A new bundle first removes the old sidecar, and the sidecar is written last, so a cache
+/// interrupted mid-write reads as missing rather than as the new bundle under the old tag
+#[cfg(not(target_arch = "wasm32"))]
+fn write_cache(dir: &Path, meta: &CacheMeta, content: Option<&str>) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    if let Some(content) = content {
+        match std::fs::remove_file(dir.join(CACHE_META_FILE)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        std::fs::write(dir.join(CACHE_FILE), content)?;
+    }
+    std::fs::write(dir.join(CACHE_META_FILE), serde_json::to_string(meta)?)?;
+
+    Ok(())
+}
+
+fn release_url(owner: &str, repo: &str) -> String {
+    format!("https://api.github.com/repos/{owner}/{repo}/releases/latest")
+}
+
+async fn fetch_asset(release: &GithubRelease, name: &str) -> Result<String> {
+    let asset = release.assets.iter().find(|asset| asset.name == name);
+
+    if let Some(asset) = asset {
+        fetch_text(&asset.browser_download_url).await
+    } else {
+        Err(release.missing_asset(name))
+    }
+}
+
 impl DeepData {
+    const OWNER: &str = "pocamind";
+    const REPO: &str = "data";
+
     /// Fetch the latest release from pocamind/data
     pub async fn latest_release() -> Result<GithubRelease> {
-        const OWNER: &str = "pocamind";
-        const REPO: &str = "data";
-
-        Self::latest_release_from(OWNER, REPO).await
+        Self::latest_release_from(Self::OWNER, Self::REPO).await
     }
 
     /// Fetch the latest release from a fork
     pub async fn latest_release_from(owner: &str, repo: &str) -> Result<GithubRelease> {
-        let url = format!("https://api.github.com/repos/{owner}/{repo}/releases/latest");
+        fetch_json(&release_url(owner, repo)).await
+    }
+
+    /// The release's `all.json`, with its tag as the [version](DeepData::version)
+    pub async fn from_release(release: &GithubRelease) -> Result<DeepData> {
+        let content = fetch_asset(release, "all.json").await?;
+
+        let mut data = DeepData::from_json(&content).map_err(decode_error)?;
+        data.set_version(Some(release.tag_name.clone()));
+        Ok(data)
+    }
+
+    /// Fetch the latest data from pocamind/data, along with the tag of the release it came
+    /// from, e.g. to show which data version is loaded. The tag is also the data's
+    /// [version](DeepData::version).
+    pub async fn fetch_latest_versioned() -> Result<(DeepData, String)> {
+        Self::fetch_versioned_from(&release_url(Self::OWNER, Self::REPO)).await
+    }
 
-        let client = reqwest::Client::new();
+    async fn fetch_versioned_from(release_url: &str) -> Result<(DeepData, String)> {
+        let release: GithubRelease = fetch_json(release_url).await?;
+        let data = Self::from_release(&release).await?;
 
-        let release = client
-            .get(url)
-            .header(USER_AGENT, "my-app/0.1")
-            .header(ACCEPT, "application/vnd.github+json")
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<GithubRelease>()
-            .await?;
+        Ok((data, release.tag_name))
+    }
 
-        Ok(release)
+    /// The latest data, kept in `cache_dir` between runs.
+    ///
+    /// A bundle fetched less than `max_age` ago is used as-is, without going online. Past that,
+    /// the latest release is checked and only downloaded if its tag differs from the cached one.
+    /// If the check fails (offline, rate limited) the stale bundle is used anyway, with a
+    /// warning, so errors only surface when there's nothing cached.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn fetch_cached(cache_dir: &Path, max_age: Duration) -> Result<DeepData> {
+        Self::fetch_cached_from(cache_dir, max_age, &release_url(Self::OWNER, Self::REPO)).await
     }
 
-    pub async fn from_release(release: &GithubRelease) -> Result<DeepData> {
-        let asset = release.assets.iter().find(|asset| asset.name == "all.json");
-
-        if let Some(asset) = asset {
-            let client = reqwest::Client::new();
-
-            let asset_url = &asset.browser_download_url;
-
-            let content = client
-                .get(asset_url)
-                .header(USER_AGENT, "my-app/0.1")
-                .send()
-                .await?
-                .error_for_status()?
-                .text()
-                .await?;
-
-            DeepData::from_json(&content)
-        } else {
-            Err(DeepError::FetchError(format!(
-                "Failed to find 'all.json', found files [{}] instead.",
-                release
-                    .assets
-                    .iter()
-                    .map(|a| a.name.clone())
-                    .collect::<Vec<String>>()
-                    .join(", ")
-            )))
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn fetch_cached_from(
+        cache_dir: &Path,
+        max_age: Duration,
+        release_url: &str,
+    ) -> Result<DeepData> {
+        let cached = read_cache(cache_dir);
+
+        match cached {
+            Some((meta, data))
+                if now_secs().saturating_sub(meta.fetched_at) < max_age.as_secs() =>
+            {
+                return Ok(data);
+            }
+            _ => {}
         }
+
+        let release = match fetch_json::<GithubRelease>(release_url).await {
+            Ok(release) => release,
+            Err(e) => {
+                let Some((meta, data)) = cached else {
+                    return Err(e);
+                };
+                warn!(
+                    "Couldn't check for new data ({e}), using the cached {}",
+                    meta.tag_name
+                );
+                return Ok(data);
+            }
+        };
+
+        let meta = CacheMeta {
+            tag_name: release.tag_name.clone(),
+            fetched_at: now_secs(),
+        };
+
+        if let Some((cached_meta, data)) = cached
+            && cached_meta.tag_name == release.tag_name
+        {
+            write_cache(cache_dir, &meta, None)?;
+            return Ok(data);
+        }
+
+        let content = fetch_asset(&release, CACHE_FILE).await?;
+        let mut data = DeepData::from_json(&content).map_err(decode_error)?;
+        data.set_version(Some(release.tag_name));
+        write_cache(cache_dir, &meta, Some(&content))?;
+
+        Ok(data)
+    }
+
+    /// Download and parse an `all.json` hosted anywhere (S3, a CDN), skipping the GitHub
+    /// release lookup entirely
+    pub async fn from_url(url: &str, opts: FetchOptions) -> Result<DeepData> {
+        let content = fetch_text_with(url, &opts).await?;
+
+        DeepData::from_json(&content).map_err(decode_error)
     }
 }
 
@@ -88,60 +281,291 @@ impl DeepWiki {
     }
 
     pub async fn latest_release_from(owner: &str, repo: &str) -> Result<GithubRelease> {
-        let url = format!("https://api.github.com/repos/{owner}/{repo}/releases/latest");
-
-        let client = reqwest::Client::new();
-
-        let release = client
-            .get(url)
-            .header(USER_AGENT, "my-app/0.1")
-            .header(ACCEPT, "application/vnd.github+json")
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<GithubRelease>()
-            .await?;
-
-        Ok(release)
+        fetch_json(&release_url(owner, repo)).await
     }
 
     pub async fn from_release(release: &GithubRelease) -> Result<DeepWiki> {
-        let asset = release.assets.iter().find(|asset| asset.name == "wiki.json");
-
-        if let Some(asset) = asset {
-            let client = reqwest::Client::new();
-
-            let asset_url = &asset.browser_download_url;
-
-            let content = client
-                .get(asset_url)
-                .header(USER_AGENT, "my-app/0.1")
-                .send()
-                .await?
-                .error_for_status()?
-                .text()
-                .await?;
-
-            DeepWiki::from_json(&content)
-        } else {
-            Err(DeepError::FetchError(format!(
-                "Failed to find 'wiki.json', found files [{}] instead.",
-                release
-                    .assets
-                    .iter()
-                    .map(|a| a.name.clone())
-                    .collect::<Vec<String>>()
-                    .join(", ")
-            )))
-        }
+        let content = fetch_asset(release, "wiki.json").await?;
+
+        DeepWiki::from_json(&content).map_err(decode_error)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::data::DeepData;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        time::Duration,
+    };
+
+    use super::{
+        CACHE_FILE, CACHE_META_FILE, CacheMeta, FetchOptions, GithubRelease, decode_error,
+        fetch_json, fetch_text, now_secs, read_cache, write_cache,
+    };
+    use crate::{data::DeepData, error::DeepError};
+
+    /// Serves one canned HTTP response on a local port, returning its url.
+    fn serve_once(status: &str, body: impl Into<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let status = status.to_string();
+        let body = body.into();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn missing_release_is_not_found() {
+        let url = serve_once("404 Not Found", r#"{"message": "Not Found"}"#);
+
+        let err = fetch_json::<GithubRelease>(&url).await.unwrap_err();
+        assert!(
+            matches!(err, DeepError::NotFound(_)),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn malformed_body_is_decode_data() {
+        let url = serve_once("200 OK", r#"{"tag_name": "v1", "assets": ["#);
+        let err = fetch_json::<GithubRelease>(&url).await.unwrap_err();
+        assert!(
+            matches!(err, DeepError::DecodeData(_)),
+            "unexpected error: {err}"
+        );
+
+        let url = serve_once("200 OK", r#"{"talents": 5}"#);
+        let content = fetch_text(&url).await.unwrap();
+        let err = DeepData::from_json(&content)
+            .map_err(decode_error)
+            .unwrap_err();
+        assert!(
+            matches!(err, DeepError::DecodeData(_)),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_connection_is_network() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+
+        let err = fetch_text(&url).await.unwrap_err();
+        assert!(
+            matches!(err, DeepError::Network(_)),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn data_from_custom_host() {
+        let url = serve_once("200 OK", FIXTURE);
+        let opts = FetchOptions {
+            timeout: Some(std::time::Duration::from_secs(5)),
+            headers: vec![("Authorization".to_string(), "Bearer token".to_string())],
+        };
+
+        let data = DeepData::from_url(&url, opts).await.unwrap();
+        assert!(data.get_talent("flamecharmer").is_some());
+        assert_eq!(
+            data.talents().count(),
+            DeepData::from_json(FIXTURE).unwrap().talents().count()
+        );
+
+        let url = serve_once("404 Not Found", "");
+        let err = DeepData::from_url(&url, FetchOptions::default())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, DeepError::NotFound(_)),
+            "unexpected error: {err}"
+        );
+
+        let url = serve_once("200 OK", r#"{"talents": 5}"#);
+        let err = DeepData::from_url(&url, FetchOptions::default())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, DeepError::DecodeData(_)),
+            "unexpected error: {err}"
+        );
+    }
+
+    const FIXTURE: &str = include_str!("../../assets/fixture.json");
+
+    /// An empty directory under the system temp dir, unique to the test
+    fn cache_dir(test: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("deepwoken-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// A url nothing listens on
+    fn offline_url() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}/", listener.local_addr().unwrap())
+    }
+
+    fn release_json(tag: &str, asset_url: &str) -> String {
+        format!(
+            r#"{{"tag_name": "{tag}", "assets": [{{"name": "all.json", "size": 1, "browser_download_url": "{asset_url}"}}]}}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn versioned_fetch_returns_the_tag() {
+        let asset = serve_once("200 OK", FIXTURE);
+        let release = serve_once("200 OK", release_json("v1.2.3", &asset));
+
+        let (data, tag) = DeepData::fetch_versioned_from(&release).await.unwrap();
+        assert_eq!(tag, "v1.2.3");
+        assert_eq!(data.version(), Some("v1.2.3"));
+        assert_eq!(data.raw_json(), FIXTURE);
+        assert!(data.get_talent("flamecharmer").is_some());
+
+        // derived bundles keep it, parsed ones have none
+        let subset = data.subset(["talent:flamecharmer"]).unwrap();
+        assert_eq!(subset.version(), Some("v1.2.3"));
+        assert_eq!(DeepData::from_json(FIXTURE).unwrap().version(), None);
+    }
+
+    #[tokio::test]
+    async fn cache_downloads_new_releases_only() {
+        let dir = cache_dir("cache-downloads");
+        let hour = Duration::from_hours(1);
+
+        // nothing cached and offline
+        let err = DeepData::fetch_cached_from(&dir, hour, &offline_url())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, DeepError::Network(_)),
+            "unexpected error: {err}"
+        );
+
+        let asset = serve_once("200 OK", FIXTURE);
+        let release = serve_once("200 OK", release_json("v1", &asset));
+        let data = DeepData::fetch_cached_from(&dir, hour, &release)
+            .await
+            .unwrap();
+        assert!(data.get_talent("flamecharmer").is_some());
+        assert_eq!(
+            std::fs::read_to_string(dir.join(CACHE_FILE)).unwrap(),
+            FIXTURE
+        );
+
+        // fresh, so it doesn't even check
+        let data = DeepData::fetch_cached_from(&dir, hour, &offline_url())
+            .await
+            .unwrap();
+        assert!(data.get_talent("flamecharmer").is_some());
+
+        // stale but the same tag, so the (unreachable) asset isn't downloaded again
+        let release = serve_once("200 OK", release_json("v1", &offline_url()));
+        let data = DeepData::fetch_cached_from(&dir, Duration::ZERO, &release)
+            .await
+            .unwrap();
+        assert!(data.get_talent("flamecharmer").is_some());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn stale_cache_is_used_offline() {
+        let dir = cache_dir("cache-offline");
+        let meta = CacheMeta {
+            tag_name: "v1".to_string(),
+            fetched_at: 0,
+        };
+        write_cache(&dir, &meta, Some(FIXTURE)).unwrap();
+
+        let data = DeepData::fetch_cached_from(&dir, Duration::from_mins(1), &offline_url())
+            .await
+            .unwrap();
+        assert!(data.get_talent("flamecharmer").is_some());
+        assert_eq!(data.version(), Some("v1"));
+
+        // a newer tag replaces the bundle and bumps the sidecar
+        let asset = serve_once("200 OK", FIXTURE);
+        let release = serve_once("200 OK", release_json("v2", &asset));
+        let data = DeepData::fetch_cached_from(&dir, Duration::from_mins(1), &release)
+            .await
+            .unwrap();
+        assert_eq!(data.version(), Some("v2"));
+
+        let meta: CacheMeta =
+            serde_json::from_str(&std::fs::read_to_string(dir.join(CACHE_META_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(meta.tag_name, "v2");
+        assert!(meta.fetched_at + 60 > now_secs());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn interrupted_write_reads_as_missing() {
+        let dir = cache_dir("cache-interrupted");
+        let meta = CacheMeta {
+            tag_name: "v1".to_string(),
+            fetched_at: 0,
+        };
+        write_cache(&dir, &meta, Some(FIXTURE)).unwrap();
+        assert!(read_cache(&dir).is_some());
+
+        // the new bundle fails to land after the old sidecar is gone
+        std::fs::remove_file(dir.join(CACHE_FILE)).unwrap();
+        std::fs::create_dir(dir.join(CACHE_FILE)).unwrap();
+        let meta = CacheMeta {
+            tag_name: "v2".to_string(),
+            fetched_at: 0,
+        };
+        assert!(write_cache(&dir, &meta, Some(FIXTURE)).is_err());
+        assert!(!dir.join(CACHE_META_FILE).exists());
+        assert!(read_cache(&dir).is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn release_lists_assets() {
+        let release: GithubRelease = serde_json::from_str(
+            r#"{
+                "tag_name": "v1.2.0",
+                "assets": [
+                    {"name": "all.json", "size": 5242880, "browser_download_url": "https://example.com/all.json"},
+                    {"name": "all.min.json", "size": 2097152, "browser_download_url": "https://example.com/all.min.json"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(release.asset_names(), ["all.json", "all.min.json"]);
+        assert_eq!(release.assets[0].size, 5_242_880);
+
+        let err = release.missing_asset("wiki.json");
+        assert!(matches!(err, DeepError::NotFound(_)));
+        let err = err.to_string();
+        assert!(
+            err.contains("all.min.json (2097152 bytes)"),
+            "unexpected error: {err}"
+        );
+    }
 
     #[tokio::test]
+    #[ignore = "hits the live GitHub API"]
     pub async fn fetch_data() {
         let release = DeepData::latest_release().await.unwrap();
 
@@ -0,0 +1,29 @@
+use crate::{Stat, util::statmap::StatMap};
+
+/// Tiny deterministic RNG for randomized tests, so they don't need a rng crate.
+pub(crate) struct Lcg(u64);
+
+impl Lcg {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Uniform-ish value in `0..bound`
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1);
+        (self.0 >> 33) % bound
+    }
+
+    /// A map giving each of `stats` a random value in `0..100`
+    #[allow(clippy::cast_possible_wrap, reason = "values are below 100")]
+    pub(crate) fn statmap(&mut self, stats: impl IntoIterator<Item = Stat>) -> StatMap {
+        let mut map = StatMap::new();
+        for stat in stats {
+            map.insert(stat, self.below(100) as i64);
+        }
+        map
+    }
+}
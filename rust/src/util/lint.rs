@@ -0,0 +1,278 @@
+//! Soft checks on reqfiles that parse fine, but probably don't do what the author meant.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::model::{
+    data::{DeepData, Weapon},
+    opt::OptionalGroup,
+    req::{PrereqGroup, Requirement},
+    reqfile::{Reqfile, ResolvedItem},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReqfileLint {
+    /// Every optional group shares one weight, so the solver just takes as many as fit.
+    UniformWeights { weight: i64 },
+    /// The group (by index into `Reqfile::optional`) sits at the edge of the weight range,
+    /// leaving no room to rank another group past it.
+    ExtremeWeight { group: usize, weight: i64 },
+    /// The requirement is marked `+`, but neither it nor its prereqs are something an
+    /// optional requirement depends on, so it was required regardless.
+    RedundantForce { name: String },
+    /// The requirement is named after a weapon but sits in `Free:`, while weapons are
+    /// normally obtained post-shrine.
+    WeaponPreshrine { name: String, weapon: String },
+}
+
+impl fmt::Display for ReqfileLint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReqfileLint::UniformWeights { weight } => write!(
+                f,
+                "Every optional requirement has weight {weight}, so weights don't prioritize \
+                anything. Consider giving the ones you care most about a higher weight."
+            ),
+            ReqfileLint::ExtremeWeight { group, weight } => write!(
+                f,
+                "Optional group {group} has weight {weight}, the edge of the allowed {}-{} range.",
+                OptionalGroup::WEIGHT_RANGE.start(),
+                OptionalGroup::WEIGHT_RANGE.end()
+            ),
+            ReqfileLint::RedundantForce { name } => write!(
+                f,
+                "'{name}' is marked '+', but no optional requirement depends on it, \
+                so the marker does nothing."
+            ),
+            ReqfileLint::WeaponPreshrine { name, weapon } => write!(
+                f,
+                "'{name}' is the weapon {weapon}, which is usually obtained post-shrine. \
+                Consider moving it to Post:."
+            ),
+        }
+    }
+}
+
+pub(crate) fn lint_reqfile(reqfile: &Reqfile) -> Vec<ReqfileLint> {
+    let mut lints = vec![];
+
+    lint_weights(reqfile, &mut lints);
+    lint_forced(reqfile, &mut lints);
+
+    lints
+}
+
+/// [`lint_reqfile`], plus the lints that need to know what requirements refer to.
+pub(crate) fn lint_reqfile_with_data(
+    reqfile: &Reqfile,
+    data: &DeepData,
+    allow_weapons_preshrine: bool,
+) -> Vec<ReqfileLint> {
+    let mut lints = lint_reqfile(reqfile);
+
+    if !allow_weapons_preshrine {
+        lint_weapon_timing(reqfile, data, &mut lints);
+    }
+
+    lints
+}
+
+fn lint_weights(reqfile: &Reqfile, lints: &mut Vec<ReqfileLint>) {
+    let Some(first) = reqfile.optional.first() else {
+        return;
+    };
+
+    if reqfile.optional.len() > 1 && reqfile.optional.iter().all(|g| g.weight == first.weight) {
+        lints.push(ReqfileLint::UniformWeights {
+            weight: first.weight,
+        });
+        return;
+    }
+
+    let range = OptionalGroup::WEIGHT_RANGE;
+    for (group, opt) in reqfile.optional.iter().enumerate() {
+        if opt.weight == *range.start() || opt.weight == *range.end() {
+            lints.push(ReqfileLint::ExtremeWeight {
+                group,
+                weight: opt.weight,
+            });
+        }
+    }
+}
+
+fn lint_forced(reqfile: &Reqfile, lints: &mut Vec<ReqfileLint>) {
+    let by_name: HashMap<String, &Requirement> = reqfile
+        .req_iter()
+        .chain(reqfile.optional_req_iter())
+        .map(|r| (r.name_or_default(), r))
+        .collect();
+
+    // everything optional requirements depend on, which a '+' would pull out of the groups
+    let depended_on = closure(
+        &by_name,
+        reqfile
+            .optional_req_iter()
+            .flat_map(|r| r.prereqs.iter().flat_map(PrereqGroup::alternatives))
+            .collect(),
+    );
+
+    let mut forced: Vec<&String> = reqfile.forced.iter().collect();
+    forced.sort();
+
+    for name in forced {
+        if closure(&by_name, vec![name]).is_disjoint(&depended_on) {
+            lints.push(ReqfileLint::RedundantForce { name: name.clone() });
+        }
+    }
+}
+
+fn lint_weapon_timing(reqfile: &Reqfile, data: &DeepData, lints: &mut Vec<ReqfileLint>) {
+    let free: HashSet<String> = reqfile
+        .general
+        .iter()
+        .map(Requirement::name_or_default)
+        .collect();
+
+    for item in reqfile.resolve_items(data) {
+        if let ResolvedItem::Item {
+            requirement,
+            namespace: Weapon::NAMESPACE,
+            name,
+            ..
+        } = item
+            && free.contains(&requirement)
+        {
+            lints.push(ReqfileLint::WeaponPreshrine {
+                name: requirement,
+                weapon: name,
+            });
+        }
+    }
+}
+
+/// `roots` and everything they transitively depend on
+fn closure<'a>(
+    by_name: &HashMap<String, &'a Requirement>,
+    roots: Vec<&'a String>,
+) -> HashSet<&'a String> {
+    let mut reached: HashSet<&String> = HashSet::new();
+    let mut stack = roots;
+
+    while let Some(name) = stack.pop() {
+        if reached.insert(name)
+            && let Some(req) = by_name.get(name)
+        {
+            stack.extend(req.prereqs.iter().flat_map(PrereqGroup::alternatives));
+        }
+    }
+
+    reached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_weights_warned() {
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            3; a := 20 int
+            3; b := 30 ftd
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(rf.lint(), vec![ReqfileLint::UniformWeights { weight: 3 }]);
+    }
+
+    #[test]
+    fn extreme_weight_warned() {
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            20; a := 20 int
+            4; b := 30 ftd
+            ",
+        )
+        .unwrap();
+
+        let lints = rf.lint();
+        assert_eq!(lints.len(), 1);
+        assert!(matches!(
+            lints[0],
+            ReqfileLint::ExtremeWeight { weight: 20, .. }
+        ));
+    }
+
+    #[test]
+    fn redundant_force_warned() {
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            + base := 20 str
+            + plate := 40 ftd
+            + heavy := 30 hvy
+            5; plate => helm := 50 ftd
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            rf.lint(),
+            vec![
+                ReqfileLint::RedundantForce {
+                    name: "base".to_string()
+                },
+                ReqfileLint::RedundantForce {
+                    name: "heavy".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn preshrine_weapon_warned() {
+        let data = DeepData::from_json(
+            r#"{
+                "weapons": {
+                    "adretian_axe": {
+                        "name": "Adretian Axe",
+                        "type": "Greataxe",
+                        "rarity": "Rare",
+                        "reqs": "35r HVY",
+                        "enchantable": true,
+                        "equip_motifs": true,
+                        "voi": false,
+                        "desc": ""
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let rf = Reqfile::parse_str(
+            r"
+            Free:
+            adretian_axe := 35r hvy
+            base := 20 str
+
+            Post:
+            weapon:adretian_axe := 35r hvy
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            rf.lint_with_data(&data, false),
+            vec![ReqfileLint::WeaponPreshrine {
+                name: "adretian_axe".to_string(),
+                weapon: "Adretian Axe".to_string(),
+            }]
+        );
+        assert!(rf.lint_with_data(&data, true).is_empty());
+    }
+}
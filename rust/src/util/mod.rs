@@ -1,7 +1,10 @@
 pub mod aggregate;
 pub mod algos;
+pub mod lint;
 pub mod pips;
 pub mod reqtree;
+pub mod solve;
+pub mod statarray;
 pub mod statmap;
 pub mod traits;
 
@@ -10,12 +13,83 @@ pub mod datafetch;
 
 pub mod graph;
 
+#[cfg(test)]
+pub(crate) mod lcg;
+
 /// Transforms the name of things in-game into an identifier/key for the `DeepData` maps
+///
+/// Letters and digits are kept as-is (lowercased), Unicode ones included, and any other
+/// punctuation is dropped, so the result always parses as a reqfile identifier (with the
+/// `unicode-identifiers` feature for non-ASCII names).
+///
+/// Whitespace is collapsed first, so text copied out of the game (curly quotes, non-breaking
+/// spaces) maps to the same identifier. With `unicode-identifiers` the name is also NFKC
+/// normalized, so fullwidth letters do too.
 #[must_use]
 pub fn name_to_identifier(s: &str) -> String {
-    s.replace(": ", " ")
+    #[cfg(feature = "unicode-identifiers")]
+    let s = icu_normalizer::ComposingNormalizerBorrowed::new_nfkc().normalize(s);
+
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(": ", " ")
         .replace(' ', "_")
-        .replace(['[', ']', '\'', '(', ')', ','], "")
         .replace(['-'], "_")
         .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_')
+        .collect()
+}
+
+/// Levenshtein distance between `a` and `b`, by chars
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = substitute.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifiers_from_names() {
+        assert_eq!(name_to_identifier("Oath: Silentheart"), "oath_silentheart");
+        assert_eq!(name_to_identifier("Silencer's Blade"), "silencers_blade");
+        assert_eq!(name_to_identifier("Brace [Gale]"), "brace_gale");
+        assert_eq!(name_to_identifier("Épée Noire"), "épée_noire");
+    }
+
+    #[test]
+    fn copied_names_normalize() {
+        let straight = name_to_identifier("Silencer's Blade");
+        assert_eq!(name_to_identifier("Silencer’s Blade"), straight);
+        assert_eq!(name_to_identifier("Silencer‘s Blade"), straight);
+        assert_eq!(name_to_identifier("Silencer's\u{a0}Blade"), straight);
+        assert_eq!(name_to_identifier("  Silencer's   Blade "), straight);
+        assert_eq!(name_to_identifier("“Silencer's Blade”"), straight);
+        if cfg!(feature = "unicode-identifiers") {
+            assert_eq!(name_to_identifier("ＳＩＬＥＮＣＥＲ'Ｓ Blade"), straight);
+        }
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("FREE", "FREE"), 0);
+        assert_eq!(edit_distance("FRE", "FREE"), 1);
+        assert_eq!(edit_distance("FREEE", "FREE"), 1);
+        assert_eq!(edit_distance("PSOT", "POST"), 2);
+        assert_eq!(edit_distance("", "POST"), 4);
+    }
 }
@@ -1,6 +1,16 @@
-use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Write,
+};
 
-use crate::req::{PrereqGroup, Requirement};
+use crate::{
+    error::{DeepError, Result},
+    model::reqfile::Reqfile,
+    req::{PrereqGroup, Requirement},
+};
+
+/// Clause text past this many chars is cut off in [`ReqTree::to_dot`] labels
+const DOT_LABEL_MAX: usize = 40;
 
 pub struct ReqTree {
     // Keyed by name
@@ -18,10 +28,18 @@ impl ReqTree {
         }
     }
 
-    /// Insert a requirement
+    /// Insert a requirement, replacing any of the same name along with its prereq links
     pub fn insert(&mut self, req: Requirement) {
         let name = req.name_or_default();
 
+        if let Some(old) = self.reqs.get(&name) {
+            for prereq in old.prereqs.iter().flat_map(PrereqGroup::alternatives) {
+                if let Some(deps) = self.dependents.get_mut(prereq) {
+                    deps.remove(&name);
+                }
+            }
+        }
+
         for prereq in req.prereqs.iter().flat_map(PrereqGroup::alternatives) {
             self.dependents
                 .entry(prereq.clone())
@@ -106,6 +124,182 @@ impl ReqTree {
         None
     }
 
+    /// Names ordered so that every req comes after all of its prereqs (Kahn's algorithm).
+    /// Whenever several reqs are ready at once they're taken in name order, so the result is
+    /// deterministic. Prereqs that aren't in the tree are ignored.
+    ///
+    /// # Errors
+    /// [`DeepError::Reqfile`] naming the reqs of a cycle, if there is one.
+    pub fn topo_order(&self) -> Result<Vec<String>> {
+        let mut pending: HashMap<&str, usize> = self
+            .reqs
+            .iter()
+            .map(|(name, req)| (name.as_str(), self.known_prereqs(req).len()))
+            .collect();
+
+        let mut ready: BTreeSet<&str> = pending
+            .iter()
+            .filter(|(_, n)| **n == 0)
+            .map(|(name, _)| *name)
+            .collect();
+
+        // built from the same prereqs as `pending`, so every decrement has a count to take
+        let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (name, req) in &self.reqs {
+            for prereq in self.known_prereqs(req) {
+                dependents.entry(prereq).or_default().push(name);
+            }
+        }
+
+        let mut order = Vec::with_capacity(self.reqs.len());
+        while let Some(name) = ready.pop_first() {
+            order.push(name.to_string());
+
+            for dep in dependents.get(name).into_iter().flatten() {
+                if let Some(n) = pending.get_mut(dep) {
+                    *n -= 1;
+                    if *n == 0 {
+                        ready.insert(dep);
+                    }
+                }
+            }
+        }
+
+        if order.len() < self.reqs.len() {
+            let cycle = self.find_cycle().unwrap_or_default();
+            return Err(DeepError::Reqfile {
+                line: 0,
+                column: None,
+                message: format!(
+                    "Prereqs cannot be dependent on each other. Found cycle: {}",
+                    cycle.join(" => ")
+                ),
+            });
+        }
+
+        Ok(order)
+    }
+
+    #[must_use]
+    /// Length of the longest prereq chain below `name`, 0 if it has no prereqs in the tree.
+    /// `None` if `name` isn't in the tree or sits on a cycle.
+    pub fn depth(&self, name: &str) -> Option<usize> {
+        self.depth_visit(name, &mut HashMap::new(), &mut HashSet::new())
+    }
+
+    fn depth_visit<'a>(
+        &'a self,
+        name: &'a str,
+        memo: &mut HashMap<&'a str, usize>,
+        stack: &mut HashSet<&'a str>,
+    ) -> Option<usize> {
+        if let Some(depth) = memo.get(name) {
+            return Some(*depth);
+        }
+
+        let req = self.reqs.get(name)?;
+        if !stack.insert(name) {
+            return None;
+        }
+
+        let mut depth = 0;
+        for prereq in self.known_prereqs(req) {
+            depth = depth.max(self.depth_visit(prereq, memo, stack)? + 1);
+        }
+
+        stack.remove(name);
+        memo.insert(name, depth);
+        Some(depth)
+    }
+
+    /// Distinct prereqs of `req` that are themselves in the tree
+    fn known_prereqs<'a>(&self, req: &'a Requirement) -> HashSet<&'a str> {
+        req.prereqs
+            .iter()
+            .flat_map(PrereqGroup::alternatives)
+            .map(String::as_str)
+            .filter(|p| self.reqs.contains_key(*p))
+            .collect()
+    }
+
+    #[must_use]
+    /// The tree as a Graphviz digraph, one node per req (labeled with its name and clauses)
+    /// and an edge from each prereq to its dependents. Nodes are emitted in name order.
+    ///
+    /// Given the `reqfile` the tree came from, reqs that are only in its optional groups are
+    /// drawn dashed and grey.
+    pub fn to_dot(&self, reqfile: Option<&Reqfile>) -> String {
+        let required: HashSet<String> = reqfile
+            .map(|rf| rf.req_iter().map(Requirement::name_or_default).collect())
+            .unwrap_or_default();
+        let optional: HashSet<String> = reqfile
+            .map(|rf| {
+                rf.optional_req_iter()
+                    .map(Requirement::name_or_default)
+                    .filter(|name| !required.contains(name))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut names: Vec<&String> = self.reqs.keys().collect();
+        names.sort();
+
+        let mut dot = String::from("digraph reqs {\n    node [shape=box, style=rounded];\n");
+        for name in &names {
+            let req = &self.reqs[*name];
+            let clauses = Requirement {
+                name: None,
+                prereqs: BTreeSet::new(),
+                clauses: req.clauses.clone(),
+            }
+            .to_string();
+
+            let clauses = if clauses.chars().count() > DOT_LABEL_MAX {
+                let cut: String = clauses.chars().take(DOT_LABEL_MAX - 3).collect();
+                format!("{cut}...")
+            } else {
+                clauses
+            };
+
+            let style = if optional.contains(*name) {
+                ", style=\"rounded,dashed\", color=gray50, fontcolor=gray40"
+            } else {
+                ""
+            };
+
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\\n{}\"{style}];",
+                dot_escape(name),
+                dot_escape(name),
+                dot_escape(&clauses)
+            );
+        }
+
+        let edges: BTreeSet<(&String, &String)> = names
+            .iter()
+            .flat_map(|name| {
+                self.reqs[*name]
+                    .prereqs
+                    .iter()
+                    .flat_map(PrereqGroup::alternatives)
+                    .map(|prereq| (prereq, *name))
+            })
+            .collect();
+
+        for (prereq, name) in edges {
+            let _ = writeln!(
+                dot,
+                "    \"{}\" -> \"{}\";",
+                dot_escape(prereq),
+                dot_escape(name)
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
     fn cycle_visit(
         &self,
         name: &str,
@@ -140,8 +334,101 @@ impl ReqTree {
     }
 }
 
+/// Escapes `s` for use inside a quoted DOT string
+fn dot_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 impl Default for ReqTree {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(reqs: &[&str]) -> ReqTree {
+        let mut tree = ReqTree::new();
+        for r in reqs {
+            tree.insert(r.parse().unwrap());
+        }
+        tree
+    }
+
+    #[test]
+    fn prereqs_come_first() {
+        let tree = tree(&[
+            "b, c => d := 40 STR",
+            "a => c := 30 STR",
+            "a := 20 STR",
+            "b := 20 FTD",
+            "a | b => e := 25 FTD",
+        ]);
+
+        assert_eq!(tree.topo_order().unwrap(), ["a", "b", "c", "d", "e"]);
+        assert_eq!(tree.depth("a"), Some(0));
+        assert_eq!(tree.depth("e"), Some(1));
+        assert_eq!(tree.depth("d"), Some(2));
+        assert_eq!(tree.depth("missing"), None);
+    }
+
+    #[test]
+    fn dot_has_one_edge_per_dependency() {
+        let content = "Free:\na := 20 STR\na => b := 30 STR\n\
+                       a, b => c := 40 STR, (20 FLM OR 20 ICE OR 20 LTN OR 20 WND)\n\
+                       5; d := 25 FTD";
+        let rf = Reqfile::parse_str(content).unwrap();
+        let tree = tree(&[
+            "a := 20 STR",
+            "a => b := 30 STR",
+            "a, b => c := 40 STR, (20 FLM OR 20 ICE OR 20 LTN OR 20 WND)",
+            "d := 25 FTD",
+        ]);
+
+        let dot = tree.to_dot(Some(&rf));
+        assert!(dot.starts_with("digraph reqs {"));
+        assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+        assert_eq!(dot.matches(" -> ").count(), 3);
+        assert!(dot.contains("\"a\" -> \"b\";"));
+        assert!(dot.contains("..."));
+        assert_eq!(dot.matches("dashed").count(), 1);
+        assert!(dot.contains("\"d\" [label=\"d\\n25s FTD\", style="));
+
+        assert_eq!(dot_escape("say \"hi\"\\\n"), "say \\\"hi\\\"\\\\\\n");
+    }
+
+    #[test]
+    fn replaced_reqs_drop_their_old_prereqs() {
+        let mut tree = tree(&["a := 20 STR", "b := 20 FTD", "a, b => c := 40 STR"]);
+        tree.insert("b => c := 40 STR".parse().unwrap());
+
+        assert_eq!(tree.topo_order().unwrap(), ["a", "b", "c"]);
+        assert!(tree.dependents("a").unwrap().is_empty());
+        assert_eq!(tree.depth("c"), Some(1));
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let tree = tree(&["b => a := 20 STR", "a => b := 20 STR", "c := 20 STR"]);
+
+        let err = tree.topo_order().unwrap_err();
+        assert!(matches!(err, DeepError::Reqfile { .. }));
+        assert!(err.to_string().contains("a => b") || err.to_string().contains("b => a"));
+        assert_eq!(tree.depth("a"), None);
+        assert_eq!(tree.depth("c"), Some(0));
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{
+    Stat,
+    constants::STAT_CAP,
+    model::req::{Atom, ClauseType, Requirement},
+    util::{statarray::StatArray, statmap::StatMap},
+};
+
+/// A cheap stat map meeting every requirement in `reqs`.
+///
+/// Greedy rather than optimal: AND atoms are applied first, then each OR clause that isn't
+/// already met takes whichever alternative raises `cost()` the least. AND atoms gating on
+/// [`Stat::Total`] go last, since every other point already counts towards them. NOT clauses
+/// are never raised for, but aren't guarded either: another requirement can still push their
+/// stat past the limit.
+#[must_use]
+pub fn min_statmap<'a>(reqs: impl IntoIterator<Item = &'a Requirement>) -> StatMap {
+    min_statmap_from(&StatMap::new(), reqs)
+}
+
+/// Like [`min_statmap`], but starting from `floor` and only ever adding points on top of it.
+#[must_use]
+pub fn min_statmap_from<'a>(
+    floor: &StatMap,
+    reqs: impl IntoIterator<Item = &'a Requirement>,
+) -> StatMap {
+    let reqs: Vec<&Requirement> = reqs.into_iter().collect();
+    let mut map = StatArray::from(floor);
+
+    let and_atoms = reqs
+        .iter()
+        .flat_map(|r| r.and_iter())
+        .flat_map(|c| c.atoms.iter());
+
+    let (totals, and_atoms): (Vec<&Atom>, Vec<&Atom>) =
+        and_atoms.partition(|a| a.stats.contains(&Stat::Total));
+
+    // singles before sums, so a sum only tops up what the singles didn't already cover
+    let (singles, sums): (Vec<&Atom>, Vec<&Atom>) =
+        and_atoms.into_iter().partition(|a| a.stats.len() == 1);
+    for atom in singles.into_iter().chain(sums) {
+        satisfy_atom(&mut map, atom);
+    }
+
+    for clause in reqs
+        .iter()
+        .flat_map(|r| r.iter())
+        .filter(|c| c.clause_type == ClauseType::Or)
+    {
+        if clause.atoms.is_empty() || clause.atoms.iter().any(|a| atom_met(&map, a)) {
+            continue;
+        }
+
+        let cheapest = clause
+            .atoms
+            .iter()
+            .map(|atom| {
+                let mut candidate = map;
+                satisfy_atom(&mut candidate, atom);
+                candidate
+            })
+            .min_by_key(StatArray::cost);
+
+        if let Some(cheapest) = cheapest {
+            map = cheapest;
+        }
+    }
+
+    for atom in totals {
+        satisfy_atom(&mut map, atom);
+    }
+
+    let mut ret = floor.clone();
+    ret.extend(map.iter());
+    ret
+}
+
+fn atom_met(map: &StatArray, atom: &Atom) -> bool {
+    atom_sum(map, atom) >= atom.value
+}
+
+fn atom_sum(map: &StatArray, atom: &Atom) -> i64 {
+    atom.stats
+        .iter()
+        .map(|s| {
+            if *s == Stat::Total {
+                map.cost()
+            } else {
+                map.get(s)
+            }
+        })
+        .sum()
+}
+
+/// Raises `map` just enough to meet `atom`. A sum's deficit goes entirely to the one stat
+/// that raises `cost()` the least (the first such stat on ties), never split.
+pub(crate) fn satisfy_atom(map: &mut StatArray, atom: &Atom) {
+    if atom.stats.contains(&Stat::Total) {
+        satisfy_total(map, atom);
+        return;
+    }
+
+    let deficit = atom.value - atom.stats.iter().map(|s| map.get(s)).sum::<i64>();
+    if deficit <= 0 {
+        return;
+    }
+
+    let target = atom.stats.iter().min_by_key(|s| {
+        let mut candidate = *map;
+        candidate.insert(**s, map.get(s) + deficit);
+        candidate.cost()
+    });
+
+    if let Some(&target) = target {
+        map.insert(target, map.get(&target) + deficit);
+    }
+}
+
+/// Meets an atom gating on [`Stat::Total`] by filling stats up to [`STAT_CAP`]: invested
+/// stats first, then untouched ones. Attunements come after the other stats in both cases, as
+/// the first point in a new attunement is refunded by the discount and doesn't raise `cost()`.
+/// Gives up once every stat is capped.
+fn satisfy_total(map: &mut StatArray, atom: &Atom) {
+    let mut order: Vec<Stat> = Stat::iter_game_order()
+        .filter(|s| *s != Stat::Total)
+        .collect();
+    order.sort_by_key(|s| (map.get(s) == 0, s.is_attunement()));
+
+    for stat in order {
+        while !atom_met(map, atom) && map.get(&stat) < STAT_CAP {
+            let add = (atom.value - atom_sum(map, atom)).min(STAT_CAP - map.get(&stat));
+            map.insert(stat, map.get(&stat) + add.max(1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn greedy_takes_cheapest_or_branch() {
+        let reqs: Vec<Requirement> = [
+            "40 STR, 30 STR OR 20 AGL",
+            "HVY + STR = 60",
+            "30 FLM OR 30 ICE",
+        ]
+        .iter()
+        .map(|r| r.parse().unwrap())
+        .collect();
+
+        let map = min_statmap(&reqs);
+        assert!(reqs.iter().all(|r| r.satisfied_by(&map)));
+        // the sum's deficit lands on a single stat rather than being split
+        assert_eq!(map.get(&Stat::Strength), 60);
+        assert_eq!(map.get(&Stat::HeavyWeapon), 0);
+        assert_eq!(map.get(&Stat::Agility), 0);
+        assert_eq!(map.cost(), 90);
+    }
+
+    #[test]
+    fn floor_is_kept() {
+        let req: Requirement = "30 FTD, 20 STR".parse().unwrap();
+        let floor = StatMap::from(std::collections::HashMap::from([(Stat::Fortitude, 40)]));
+
+        let map = min_statmap_from(&floor, [&req]);
+        assert_eq!(map.get(&Stat::Fortitude), 40);
+        assert_eq!(map.get(&Stat::Strength), 20);
+    }
+
+    #[test]
+    fn total_is_allocated() {
+        let req: Requirement = "1500 TTL".parse().unwrap();
+        let map = min_statmap([&req]);
+        assert!(map.cost() >= 1500);
+        assert!(map.values().all(|v| *v <= STAT_CAP));
+        assert!(req.satisfied_by(&map));
+
+        // points land on what's already invested before anything new
+        let req: Requirement = "25 STR, 60 TTL".parse().unwrap();
+        let map = min_statmap([&req]);
+        assert_eq!(map.get(&Stat::Strength), 60);
+        assert_eq!(map.cost(), 60);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{Stat, util::statmap::StatMap};
+
+const LEN: usize = Stat::Total as usize + 1;
+
+/// Allocation-free counterpart of [`StatMap`], indexed by stat discriminant.
+///
+/// Mirrors the `get`/`insert`/`cost`/`iter` surface of [`StatMap`] and converts to and from
+/// it, for hot loops (like the solver's) that clone and compare maps constantly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StatArray([i64; LEN]);
+
+impl StatArray {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn get(&self, stat: &Stat) -> i64 {
+        self.0[*stat as usize]
+    }
+
+    /// Sets `stat`, returning its previous value
+    pub fn insert(&mut self, stat: Stat, value: i64) -> i64 {
+        std::mem::replace(&mut self.0[stat as usize], value)
+    }
+
+    /// Same as [`StatMap::cost`]
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_wrap,
+        reason = "we're never having too many stats in the array"
+    )]
+    pub fn cost(&self) -> i64 {
+        let attunements = self
+            .iter()
+            .filter(|(s, v)| s.is_attunement() && *v > 0)
+            .count() as i64;
+
+        self.0.iter().sum::<i64>() - (attunements - 1).max(0)
+    }
+
+    /// The stats with a non-zero value
+    pub fn iter(&self) -> impl Iterator<Item = (Stat, i64)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v != 0)
+            .map(|(i, v)| (stat_at(i), *v))
+    }
+}
+
+fn stat_at(i: usize) -> Stat {
+    Stat::GAME_ORDER[i]
+}
+
+impl From<&StatMap> for StatArray {
+    fn from(map: &StatMap) -> Self {
+        let mut arr = StatArray::new();
+        for (stat, value) in map.iter() {
+            arr.insert(*stat, *value);
+        }
+        arr
+    }
+}
+
+impl From<StatMap> for StatArray {
+    fn from(map: StatMap) -> Self {
+        StatArray::from(&map)
+    }
+}
+
+impl From<StatArray> for StatMap {
+    /// Zeroed stats are left out
+    fn from(arr: StatArray) -> Self {
+        StatMap(arr.iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::lcg::Lcg;
+
+    #[test]
+    fn agrees_with_statmap() {
+        let mut rng = Lcg::new(0x5eed);
+
+        for _ in 0..500 {
+            let stats: Vec<Stat> = (0..LEN)
+                .map(stat_at)
+                .filter(|_| rng.below(3) == 0)
+                .collect();
+            let mut map = rng.statmap(stats);
+
+            let arr = StatArray::from(&map);
+            assert_eq!(arr.cost(), map.cost());
+            for i in 0..LEN {
+                assert_eq!(arr.get(&stat_at(i)), map.get(&stat_at(i)));
+            }
+
+            let mut back = StatMap::from(arr);
+            map.retain(|_, v| *v != 0);
+            back.retain(|_, v| *v != 0);
+            assert_eq!(back, map);
+        }
+    }
+
+    #[test]
+    fn insert_returns_previous() {
+        let mut arr = StatArray::new();
+        assert_eq!(arr.insert(Stat::Total, 5), 0);
+        assert_eq!(arr.insert(Stat::Total, 7), 5);
+        assert_eq!(arr.iter().collect::<Vec<_>>(), [(Stat::Total, 7)]);
+    }
+}
@@ -1,18 +1,58 @@
 use std::{
+    cmp::Ordering,
     collections::HashMap,
-    ops::{Deref, DerefMut},
+    fmt,
+    ops::{Add, AddAssign, Deref, DerefMut, Sub},
 };
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
     Stat,
-    model::data::{DeepData, Talent},
-    constants::{MAX_LEVEL, MAX_TOTAL},
+    constants::{MAX_LEVEL, MAX_TOTAL, POINTS_PER_LEVEL, STAT_CAP},
+    error::{self, DeepError},
+    model::{
+        data::{Aspect, DeepData, Talent},
+        stat,
+    },
     req::Requirement,
     util::algos,
 };
 
+/// One way a [`StatMap`] can't be had in game, see [`StatMap::validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatViolation {
+    /// The map costs more than the [`MAX_TOTAL`] points a character gets
+    OverTotal {
+        cost: i64,
+    },
+    /// A stat is past [`STAT_CAP`]
+    OverCap {
+        stat: Stat,
+        value: i64,
+    },
+    Negative {
+        stat: Stat,
+        value: i64,
+    },
+}
+
+impl fmt::Display for StatViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StatViolation::OverTotal { cost } => {
+                write!(f, "costs {cost} points, past the {MAX_TOTAL} available")
+            }
+            StatViolation::OverCap { stat, value } => {
+                write!(f, "{} is {value}, past the cap of {STAT_CAP}", stat.name())
+            }
+            StatViolation::Negative { stat, value } => {
+                write!(f, "{} is negative ({value})", stat.name())
+            }
+        }
+    }
+}
+
 /// Wrapper around a `HashMap` of stats to their values
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StatMap(pub HashMap<Stat, i64>);
@@ -24,20 +64,121 @@ impl StatMap {
         StatMap(HashMap::new())
     }
 
+    /// Every attunement past the first is a point cheaper
     #[must_use]
     #[allow(
         clippy::cast_possible_wrap,
         reason = "we're never having too many stats in the statmap"
     )]
     pub fn cost(&self) -> i64 {
-        self.0.values().sum::<i64>()
-            - (self
-                .0
-                .iter()
-                .filter(|(s, v)| s.is_attunement() && **v > 0)
-                .count() as i64
-                - 1)
-            .max(0)
+        self.0.values().sum::<i64>() - (self.attunement_count() as i64 - 1).max(0)
+    }
+
+    /// How many attunements have points in them
+    #[must_use]
+    pub fn attunement_count(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|(s, v)| s.is_attunement() && **v > 0)
+            .count()
+    }
+
+    /// The invested attunements and their values, in [game order](Stat::GAME_ORDER)
+    #[must_use]
+    pub fn attunements(&self) -> Vec<(Stat, i64)> {
+        Stat::iter_game_order()
+            .filter(Stat::is_attunement)
+            .map(|s| (s, self.get(&s)))
+            .filter(|(_, v)| *v > 0)
+            .collect()
+    }
+
+    /// The invested attunement with the most points, the earliest in [`stat::ATTUNEMENT`]
+    /// order on ties
+    #[must_use]
+    pub fn primary_attunement(&self) -> Option<Stat> {
+        stat::ATTUNEMENT
+            .iter()
+            .filter(|s| self.get(s) > 0)
+            .fold(None, |best: Option<Stat>, s| match best {
+                Some(b) if self.get(&b) >= self.get(s) => Some(b),
+                _ => Some(*s),
+            })
+    }
+
+    /// Whether the map could be had in game, see [`StatMap::validate`]
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.violations().is_empty()
+    }
+
+    /// Checks the map could be had in game: it costs at most [`MAX_TOTAL`], and no stat is
+    /// negative or past [`STAT_CAP`]. Maps edited by hand or run through the shrine can end
+    /// up out of range.
+    ///
+    /// # Errors
+    /// [`DeepError::InvalidStats`] listing every violation, stats in game order.
+    pub fn validate(&self) -> error::Result<()> {
+        let violations = self.violations();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(DeepError::InvalidStats(violations))
+        }
+    }
+
+    fn violations(&self) -> Vec<StatViolation> {
+        let mut violations = vec![];
+
+        let cost = self.cost();
+        if cost > MAX_TOTAL {
+            violations.push(StatViolation::OverTotal { cost });
+        }
+
+        for stat in Stat::iter_game_order() {
+            let value = self.get(&stat);
+            if value < 0 {
+                violations.push(StatViolation::Negative { stat, value });
+            } else if value > STAT_CAP && stat != Stat::Total {
+                violations.push(StatViolation::OverCap { stat, value });
+            }
+        }
+
+        violations
+    }
+
+    /// Floors negative stats to 0 and caps every stat at [`STAT_CAP`]. Leaves the total alone,
+    /// as there's no telling which stats the excess should come out of.
+    pub fn clamp_to_caps(&mut self) {
+        for (stat, value) in &mut self.0 {
+            let cap = if *stat == Stat::Total {
+                i64::MAX
+            } else {
+                STAT_CAP
+            };
+            *value = (*value).clamp(0, cap);
+        }
+    }
+
+    /// Sets the stat named `stat` (anything [`Stat`] parses from) to `value`, for input that
+    /// comes in as text, like from bindings.
+    ///
+    /// # Errors
+    /// [`DeepError::UnknownStat`] if `stat` doesn't name a stat, or [`DeepError::InvalidStats`]
+    /// if `value` is negative. The map is left untouched either way.
+    pub fn try_set(&mut self, stat: &str, value: i64) -> error::Result<()> {
+        let stat: Stat = stat
+            .parse()
+            .map_err(|_| DeepError::UnknownStat(stat.to_string()))?;
+        if value < 0 {
+            return Err(DeepError::InvalidStats(vec![StatViolation::Negative {
+                stat,
+                value,
+            }]));
+        }
+
+        self.insert(stat, value);
+        Ok(())
     }
 
     #[must_use]
@@ -50,6 +191,13 @@ impl StatMap {
         ((self.cost() - 15) / 15).clamp(0, i64::from(max_level.unwrap_or(MAX_LEVEL)))
     }
 
+    /// The points a character has earned upon reaching power `level`, the inverse of
+    /// [`StatMap::level`]. Capped at [`MAX_TOTAL`].
+    #[must_use]
+    pub fn points_at_level(level: u32) -> i64 {
+        (POINTS_PER_LEVEL * (i64::from(level) + 1)).min(MAX_TOTAL)
+    }
+
     #[must_use]
     pub fn get(&self, stat: &Stat) -> i64 {
         *self.0.get(stat).unwrap_or(&0)
@@ -65,6 +213,114 @@ impl StatMap {
         req.satisfied_by(&self)
     }
 
+    /// `self - other` per stat, negative where `other` is higher. Stats equal in both are
+    /// left out.
+    #[must_use]
+    pub fn diff(&self, other: &StatMap) -> StatMap {
+        self.keys()
+            .chain(other.keys())
+            .map(|s| (*s, self.get(s) - other.get(s)))
+            .filter(|(_, delta)| *delta != 0)
+            .collect::<HashMap<_, _>>()
+            .into()
+    }
+
+    /// `self - other` per stat, clamped at 0 (and then left out), so what's still missing to
+    /// reach `self` from `other`. The same as `-`.
+    #[must_use]
+    pub fn saturating_sub(&self, other: &StatMap) -> StatMap {
+        StatMap(
+            self.iter()
+                .map(|(s, v)| (*s, v - other.get(s)))
+                .filter(|(_, v)| *v > 0)
+                .collect(),
+        )
+    }
+
+    /// `self - other` per stat, or `None` if any stat would go negative. Stats that end up
+    /// at 0 are left out.
+    #[must_use]
+    pub fn checked_sub(&self, other: &StatMap) -> Option<StatMap> {
+        if other.iter().any(|(s, v)| self.get(s) < *v) {
+            return None;
+        }
+        Some(self.saturating_sub(other))
+    }
+
+    /// Whether every stat in `self` is at least as high as in `other`, ignoring
+    /// [`Stat::Total`]. A build dominating another is better or equal everywhere, so the other
+    /// can be pruned from a search.
+    #[must_use]
+    pub fn dominates(&self, other: &StatMap) -> bool {
+        self.keys()
+            .chain(other.keys())
+            .filter(|s| **s != Stat::Total)
+            .all(|s| self.get(s) >= other.get(s))
+    }
+
+    /// The stats a build ends up with once the aspect's innate stats are added on top of the
+    /// points invested. Innates may be negative (debuffs), but no stat drops below 0.
+    #[must_use]
+    pub fn with_innate(&self, aspect: &Aspect) -> StatMap {
+        let mut ret = self.clone();
+        for (stat, innate) in aspect.innate_statmap().iter() {
+            if *stat == Stat::Total {
+                continue;
+            }
+            ret.insert(*stat, (self.get(stat) + innate).max(0));
+        }
+        ret
+    }
+
+    /// The points that must be invested to reach these stats given the aspect's innates.
+    ///
+    /// A positive innate lowers the required investment, a negative one raises it.
+    #[must_use]
+    pub fn without_innate(&self, aspect: &Aspect) -> StatMap {
+        let mut ret = self.clone();
+        for (stat, innate) in aspect.innate_statmap().iter() {
+            if *stat == Stat::Total {
+                continue;
+            }
+            ret.insert(*stat, (self.get(stat) - innate).max(0));
+        }
+        ret
+    }
+
+    /// Cosine similarity between the two builds' stat vectors, ignoring [`Stat::Total`].
+    ///
+    /// Ranges from 0 (no stats in common) to 1 (same stat spread, regardless of scale).
+    /// Two empty maps are considered identical, while an empty map shares nothing with a
+    /// non-empty one.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, reason = "stat values are small")]
+    pub fn similarity(&self, other: &StatMap) -> f64 {
+        let norm = |map: &StatMap| {
+            map.iter()
+                .filter(|(stat, _)| **stat != Stat::Total)
+                .map(|(_, value)| (*value * *value) as f64)
+                .sum::<f64>()
+                .sqrt()
+        };
+
+        let (a, b) = (norm(self), norm(other));
+
+        if a == 0.0 && b == 0.0 {
+            return 1.0;
+        }
+        if a == 0.0 || b == 0.0 {
+            return 0.0;
+        }
+
+        let dot: f64 = self
+            .iter()
+            .filter(|(stat, _)| **stat != Stat::Total)
+            .map(|(stat, value)| (*value * other.get(stat)) as f64)
+            .sum();
+
+        dot / (a * b)
+    }
+
     /// Returns the implicit talents granted by this stat map.
     ///
     /// Implicit talents are flagged `implicit` in the source data. They are granted automatically
@@ -86,6 +342,71 @@ impl Default for StatMap {
     }
 }
 
+/// Orders by domination (see [`StatMap::dominates`]). Builds that trade off stats are
+/// incomparable, as are ones that only differ by zeroed stats or [`Stat::Total`].
+impl PartialOrd for StatMap {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.dominates(other), other.dominates(self)) {
+            _ if self == other => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Greater),
+            (false, true) => Some(Ordering::Less),
+            _ => None,
+        }
+    }
+}
+
+/// Per stat sums, a stat missing from one side counting as 0
+impl AddAssign<&StatMap> for StatMap {
+    fn add_assign(&mut self, rhs: &StatMap) {
+        for (stat, value) in rhs.iter() {
+            *self.entry(*stat).or_insert(0) += value;
+        }
+    }
+}
+
+impl AddAssign for StatMap {
+    fn add_assign(&mut self, rhs: StatMap) {
+        *self += &rhs;
+    }
+}
+
+impl Add<&StatMap> for &StatMap {
+    type Output = StatMap;
+
+    fn add(self, rhs: &StatMap) -> StatMap {
+        let mut ret = self.clone();
+        ret += rhs;
+        ret
+    }
+}
+
+impl Add for StatMap {
+    type Output = StatMap;
+
+    fn add(mut self, rhs: StatMap) -> StatMap {
+        self += &rhs;
+        self
+    }
+}
+
+/// [`StatMap::saturating_sub`], see [`StatMap::checked_sub`] and [`StatMap::diff`] for the
+/// alternatives
+impl Sub<&StatMap> for &StatMap {
+    type Output = StatMap;
+
+    fn sub(self, rhs: &StatMap) -> StatMap {
+        self.saturating_sub(rhs)
+    }
+}
+
+impl Sub for StatMap {
+    type Output = StatMap;
+
+    fn sub(self, rhs: StatMap) -> StatMap {
+        self.saturating_sub(&rhs)
+    }
+}
+
 impl Deref for StatMap {
     type Target = HashMap<Stat, i64>;
 
@@ -115,3 +436,284 @@ impl From<StatMap> for HashMap<Stat, i64> {
         val.0
     }
 }
+
+/// Serializes like [`StatMap`]'s own impl, but leaves out zeroed stats. Use with
+/// `#[serde(serialize_with = "serialize_nonzero", deserialize_with = "deserialize_nonzero")]`.
+///
+/// # Errors
+///
+/// Whatever `serializer` errors with.
+pub fn serialize_nonzero<S>(map: &StatMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_map(map.0.iter().filter(|(_, v)| **v != 0))
+}
+
+/// Counterpart to [`serialize_nonzero`]. Missing stats already read as 0 through
+/// [`StatMap::get`], explicit zeros are dropped to match.
+///
+/// # Errors
+///
+/// If the input isn't a map of stats to integers.
+pub fn deserialize_nonzero<'de, D>(deserializer: D) -> Result<StatMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut map = HashMap::<Stat, i64>::deserialize(deserializer)?;
+    map.retain(|_, v| *v != 0);
+    Ok(StatMap(map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(stats: &[(Stat, i64)]) -> StatMap {
+        StatMap(stats.iter().copied().collect())
+    }
+
+    #[test]
+    fn violations_are_listed() {
+        assert!(map(&[(Stat::Strength, 100), (Stat::Fortitude, 90)]).is_valid());
+
+        let mut bad = map(&[
+            (Stat::Strength, 120),
+            (Stat::Fortitude, -5),
+            (Stat::Agility, 100),
+            (Stat::Intelligence, 100),
+            (Stat::Charisma, 100),
+        ]);
+        assert!(!bad.is_valid());
+
+        let Err(DeepError::InvalidStats(violations)) = bad.validate() else {
+            panic!("expected invalid stats");
+        };
+        assert_eq!(
+            violations,
+            [
+                StatViolation::OverTotal { cost: 415 },
+                StatViolation::OverCap {
+                    stat: Stat::Strength,
+                    value: 120
+                },
+                StatViolation::Negative {
+                    stat: Stat::Fortitude,
+                    value: -5
+                },
+            ]
+        );
+
+        bad.clamp_to_caps();
+        assert_eq!(bad.get(&Stat::Strength), STAT_CAP);
+        assert_eq!(bad.get(&Stat::Fortitude), 0);
+        // clamping doesn't take points off the total
+        assert_eq!(
+            bad.validate().unwrap_err().to_string(),
+            "Invalid stats: costs 400 points, past the 330 available"
+        );
+    }
+
+    #[test]
+    fn try_set_rejects_bad_input() {
+        let mut stats = StatMap::new();
+        stats.try_set("frost", 40).unwrap();
+        stats.try_set("STR", 0).unwrap();
+        assert_eq!(stats, map(&[(Stat::Frostdraw, 40), (Stat::Strength, 0)]));
+
+        assert!(matches!(
+            stats.try_set("ICE", -1),
+            Err(DeepError::InvalidStats(_))
+        ));
+        assert!(matches!(
+            stats.try_set("mana", 10),
+            Err(DeepError::UnknownStat(name)) if name == "mana"
+        ));
+        assert_eq!(stats.get(&Stat::Frostdraw), 40);
+    }
+
+    fn aspect(innate: &[(Stat, i64)]) -> Aspect {
+        Aspect {
+            name: "Test".to_string(),
+            desc: String::new(),
+            innate: innate.iter().copied().collect(),
+            is_pathfinder: false,
+            variants: HashMap::new(),
+            talent: vec![],
+            exclude_cosmetics: vec![],
+        }
+    }
+
+    #[test]
+    fn negative_innate_clamps_and_raises_investment() {
+        let debuffed = aspect(&[(Stat::Strength, -5), (Stat::Agility, 2)]);
+
+        let invested = map(&[(Stat::Strength, 3), (Stat::Agility, 10)]);
+        let total = invested.with_innate(&debuffed);
+        assert_eq!(total.get(&Stat::Strength), 0);
+        assert_eq!(total.get(&Stat::Agility), 12);
+
+        // reaching 25 STR costs 30 points when the race takes 5 away
+        let target = map(&[(Stat::Strength, 25), (Stat::Agility, 12)]);
+        let needed = target.without_innate(&debuffed);
+        assert_eq!(needed.get(&Stat::Strength), 30);
+        assert_eq!(needed.get(&Stat::Agility), 10);
+        assert_eq!(needed.cost(), target.cost() + 5 - 2);
+    }
+
+    #[test]
+    fn similarity_identical_and_scaled() {
+        let a = map(&[(Stat::Strength, 40), (Stat::Agility, 20)]);
+        assert!((a.similarity(&a) - 1.0).abs() < 1e-9);
+
+        let doubled = map(&[(Stat::Strength, 80), (Stat::Agility, 40)]);
+        assert!((a.similarity(&doubled) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn similarity_orthogonal_and_empty() {
+        let a = map(&[(Stat::Strength, 40)]);
+        let b = map(&[(Stat::Flamecharm, 40)]);
+        assert!(a.similarity(&b).abs() < 1e-9);
+
+        assert!((StatMap::new().similarity(&StatMap::new()) - 1.0).abs() < 1e-9);
+        assert!(a.similarity(&StatMap::new()).abs() < 1e-9);
+
+        // Total is not part of the stat vector
+        let total = map(&[(Stat::Total, 200)]);
+        assert!((total.similarity(&StatMap::new()) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn primary_attunement_is_the_largest() {
+        let build = map(&[
+            (Stat::Flamecharm, 30),
+            (Stat::Thundercall, 60),
+            (Stat::Strength, 90),
+        ]);
+        assert_eq!(build.attunement_count(), 2);
+        assert_eq!(
+            build.attunements(),
+            [(Stat::Flamecharm, 30), (Stat::Thundercall, 60)]
+        );
+        assert_eq!(build.primary_attunement(), Some(Stat::Thundercall));
+
+        let tied = map(&[
+            (Stat::Thundercall, 40),
+            (Stat::Flamecharm, 40),
+            (Stat::Frostdraw, 0),
+        ]);
+        assert_eq!(tied.attunement_count(), 2);
+        assert_eq!(tied.primary_attunement(), Some(Stat::Flamecharm));
+
+        // game order puts ICE before FLM
+        let both = map(&[(Stat::Flamecharm, 30), (Stat::Frostdraw, 20)]);
+        assert_eq!(
+            both.attunements(),
+            [(Stat::Frostdraw, 20), (Stat::Flamecharm, 30)]
+        );
+
+        let attunementless = map(&[(Stat::Strength, 90)]);
+        assert_eq!(attunementless.attunement_count(), 0);
+        assert!(attunementless.attunements().is_empty());
+        assert_eq!(attunementless.primary_attunement(), None);
+    }
+
+    #[test]
+    fn points_at_level_inverts_level() {
+        for level in 0..=MAX_LEVEL {
+            let map = map(&[(Stat::Strength, StatMap::points_at_level(level))]);
+            assert_eq!(map.level(None), i64::from(level));
+        }
+        assert_eq!(StatMap::points_at_level(MAX_LEVEL + 5), MAX_TOTAL);
+    }
+
+    #[test]
+    fn domination() {
+        let high = map(&[(Stat::Strength, 40), (Stat::Agility, 20)]);
+        let low = map(&[(Stat::Strength, 30), (Stat::Total, 900)]);
+        assert!(high.dominates(&low));
+        assert!(!low.dominates(&high));
+        assert!(high > low);
+
+        let traded = map(&[(Stat::Strength, 50)]);
+        assert!(!high.dominates(&traded));
+        assert!(!traded.dominates(&high));
+        assert_eq!(high.partial_cmp(&traded), None);
+
+        assert!(high.dominates(&high));
+        assert_eq!(high.partial_cmp(&high.clone()), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn arithmetic() {
+        let required = map(&[(Stat::Strength, 40), (Stat::Fortitude, 30)]);
+        let current = map(&[
+            (Stat::Strength, 25),
+            (Stat::Fortitude, 50),
+            (Stat::Agility, 10),
+        ]);
+
+        assert_eq!(
+            &required + &current,
+            map(&[
+                (Stat::Strength, 65),
+                (Stat::Fortitude, 80),
+                (Stat::Agility, 10)
+            ])
+        );
+
+        let mut sum = required.clone();
+        sum += current.clone();
+        assert_eq!(sum, required.clone() + current.clone());
+
+        // still to allocate
+        assert_eq!(&required - &current, map(&[(Stat::Strength, 15)]));
+        assert_eq!(required.checked_sub(&current), None);
+        assert_eq!(
+            current.checked_sub(&map(&[(Stat::Fortitude, 50)])),
+            Some(map(&[(Stat::Strength, 25), (Stat::Agility, 10)]))
+        );
+
+        assert_eq!(
+            required.diff(&current),
+            map(&[
+                (Stat::Strength, 15),
+                (Stat::Fortitude, -20),
+                (Stat::Agility, -10)
+            ])
+        );
+        assert!(required.diff(&required).is_empty());
+    }
+
+    #[test]
+    fn nonzero_serde_round_trip() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Build {
+            #[serde(
+                serialize_with = "serialize_nonzero",
+                deserialize_with = "deserialize_nonzero"
+            )]
+            stats: StatMap,
+        }
+
+        let build = Build {
+            stats: map(&[
+                (Stat::Strength, 40),
+                (Stat::Agility, 0),
+                (Stat::Fortitude, 0),
+            ]),
+        };
+
+        let json = serde_json::to_string(&build).unwrap();
+        assert_eq!(json, r#"{"stats":{"Strength":40}}"#);
+
+        let back: Build = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.stats, map(&[(Stat::Strength, 40)]));
+        assert_eq!(back.stats.get(&Stat::Agility), 0);
+
+        // explicit zeros read the same as missing ones
+        let back: Build = serde_json::from_str(r#"{"stats":{"Strength":40,"Agility":0}}"#).unwrap();
+        assert_eq!(back.stats, map(&[(Stat::Strength, 40)]));
+    }
+}
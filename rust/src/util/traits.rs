@@ -2,7 +2,7 @@ use std::borrow::Borrow;
 
 use crate::{
     Stat,
-    req::{PrereqGroup, Requirement},
+    req::{Atom, ClauseType, PrereqGroup, Requirement},
     util::statmap::StatMap,
 };
 
@@ -47,7 +47,7 @@ where
         for req in self {
             let req = req.borrow();
 
-            for atom in req.atoms() {
+            for atom in positive_atoms(req) {
                 for &stat in &atom.stats {
                     if stat == Stat::Total {
                         continue;
@@ -72,7 +72,7 @@ where
         for req in self {
             let req = req.borrow();
 
-            for atom in req.atoms() {
+            for atom in positive_atoms(req) {
                 if atom.stats.contains(&Stat::Total) {
                     max = max.max(atom.value);
                 }
@@ -82,3 +82,10 @@ where
         max
     }
 }
+
+/// Atoms that call for points, i.e. not the ones of NOT clauses
+pub(crate) fn positive_atoms(req: &Requirement) -> impl Iterator<Item = &Atom> {
+    req.iter()
+        .filter(|c| c.clause_type != ClauseType::Not)
+        .flat_map(|c| c.atoms.iter())
+}
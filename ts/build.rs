@@ -29,11 +29,17 @@ fn main() {
     writeln!(out).unwrap();
 
     // Stat
-    let all_stats: Vec<Stat> = (0u32..).map_while(|i| Stat::try_from(i).ok()).collect();
+    let all_stats: Vec<Stat> = Stat::iter_game_order().filter(|s| *s != Stat::Total).collect();
     let literals: Vec<String> = all_stats.iter().map(|s| format!("\"{}\"", s.name())).collect();
     writeln!(out, "export type Stat = {};", literals.join(" | ")).unwrap();
     writeln!(out).unwrap();
 
+    writeln!(out, "export const ALL_STATS: Stat[] = {};", format_array(&all_stats)).unwrap();
+    let short_names: Vec<String> =
+        all_stats.iter().map(|s| format!("\"{}\": \"{}\"", s.name(), s.short_name())).collect();
+    writeln!(out, "export const STAT_SHORT_NAMES: Record<Stat, string> = {{ {} }};", short_names.join(", ")).unwrap();
+    writeln!(out).unwrap();
+
     writeln!(out, "export const CORE_STATS: Stat[] = {};", format_array(stat::CORE)).unwrap();
     writeln!(out, "export const WEAPON_STATS: Stat[] = {};", format_array(stat::WEAPON)).unwrap();
     writeln!(out, "export const ATTUNEMENT_STATS: Stat[] = {};", format_array(stat::ATTUNEMENT)).unwrap();
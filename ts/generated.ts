@@ -2,6 +2,9 @@
 
 export type Stat = "Strength" | "Fortitude" | "Agility" | "Intelligence" | "Willpower" | "Charisma" | "Heavy" | "Medium" | "Light" | "Frostdraw" | "Flamecharm" | "Thundercall" | "Galebreathe" | "Shadowcast" | "Ironsing" | "Bloodrend";
 
+export const ALL_STATS: Stat[] = ["Strength", "Fortitude", "Agility", "Intelligence", "Willpower", "Charisma", "Heavy", "Medium", "Light", "Frostdraw", "Flamecharm", "Thundercall", "Galebreathe", "Shadowcast", "Ironsing", "Bloodrend"];
+export const STAT_SHORT_NAMES: Record<Stat, string> = { "Strength": "STR", "Fortitude": "FTD", "Agility": "AGL", "Intelligence": "INT", "Willpower": "WLL", "Charisma": "CHA", "Heavy": "HVY", "Medium": "MED", "Light": "LHT", "Frostdraw": "ICE", "Flamecharm": "FLM", "Thundercall": "LTN", "Galebreathe": "WND", "Shadowcast": "SDW", "Ironsing": "MTL", "Bloodrend": "BLD" };
+
 export const CORE_STATS: Stat[] = ["Strength", "Fortitude", "Agility", "Intelligence", "Willpower", "Charisma"];
 export const WEAPON_STATS: Stat[] = ["Heavy", "Medium", "Light"];
 export const ATTUNEMENT_STATS: Stat[] = ["Flamecharm", "Frostdraw", "Thundercall", "Galebreathe", "Shadowcast", "Ironsing", "Bloodrend"];
@@ -1,9 +1,10 @@
-export { ATTUNEMENT_STATS, CORE_STATS, DAMAGE_TYPES, WEAPON_STATS, ITEM_RARITIES, TALENT_RARITIES, WEAPON_TYPES, EQUIPMENT_SLOTS } from './types.js';
-export type { AggregateMode, BuildTotalStats, Aspect, BuildSnapshot, CombatState, DamageType, Enchant, Equipment, EquipmentSelection, EquipmentSlot, ItemRarity, Mantra, MantraSelection, MantraType, Objective, Origin, Outfit, Preset, RangeType, Resonance, Scenario, Stat, StatFormula, StatSource, Talent, TalentRarity, Weapon, WeaponSelection, WeaponType } from './types.js';
-export type { Atom, Clause, ClauseType, Reducability } from './requirement.js';
+export { ALL_STATS, ATTUNEMENT_STATS, CORE_STATS, DAMAGE_TYPES, WEAPON_STATS, ITEM_RARITIES, TALENT_RARITIES, WEAPON_TYPES, EQUIPMENT_SLOTS, STAT_SHORT_NAMES } from './types.js';
+export type { AggregateMode, BuildTotalStats, Aspect, BuildSnapshot, CombatState, DamageType, DataDiff, Enchant, Equipment, EquipmentSelection, EquipmentSlot, ItemRarity, Mantra, MantraSelection, MantraType, Objective, Origin, Outfit, Preset, RangeType, OptionalGroup, Resonance, Scenario, SearchHit, Stat, StatFormula, StatSource, Talent, TalentRarity, Weapon, WeaponSelection, WeaponType } from './types.js';
+export type { Atom, Clause, ClauseType, Reducability, Timing } from './requirement.js';
 
-import type { BuildTotalStats, Aspect, BuildSnapshot, Enchant, Equipment, Mantra, Objective, Origin, Outfit, Preset, Resonance, Scenario, Stat, Talent, Weapon } from './types.js';
-import type { Clause } from './requirement.js';
+import type { BuildTotalStats, Aspect, BuildSnapshot, DataDiff, Enchant, Equipment, Mantra, Objective, OptionalGroup, Origin, Outfit, Preset, Resonance, Scenario, SearchHit, Stat, Talent, Weapon } from './types.js';
+import type { Clause, Timing } from './requirement.js';
+import { STAT_SHORT_NAMES } from './types.js';
 
 // a top-level await here breaks older webkit stuff
 let wasm: any = null;
@@ -58,6 +59,11 @@ export class DeepData {
         return new DeepData(w().DeepData.fromJson(json));
     }
 
+    /** Tag of the release the data was fetched from (e.g. `v1.2.3`), null for data parsed from JSON */
+    get version(): string | null { return this._wasm.version ?? null; }
+    /** The JSON the data was parsed from, which may hold fields this version doesn't know about */
+    rawJson(): string { return this._wasm.rawJson(); }
+
     getTalent(name: string): Talent | null { return this._wasm.getTalent(name); }
     getMantra(name: string): Mantra | null { return this._wasm.getMantra(name); }
     getWeapon(name: string): Weapon | null { return this._wasm.getWeapon(name); }
@@ -70,18 +76,47 @@ export class DeepData {
     getResonance(name: string): Resonance | null { return this._wasm.getResonance(name); }
     getObjective(name: string): Objective | null { return this._wasm.getObjective(name); }
 
+    /** Ranked by how closely the name matches `query`, best first. Scores run from 0 to 1 (exact). */
+    searchTalent(query: string): SearchHit[] { return this._wasm.searchTalent(query); }
+    searchMantra(query: string): SearchHit[] { return this._wasm.searchMantra(query); }
+    searchWeapon(query: string): SearchHit[] { return this._wasm.searchWeapon(query); }
+    searchOutfit(query: string): SearchHit[] { return this._wasm.searchOutfit(query); }
+    searchEquipment(query: string): SearchHit[] { return this._wasm.searchEquipment(query); }
+    searchAspect(query: string): SearchHit[] { return this._wasm.searchAspect(query); }
+    searchEnchant(query: string): SearchHit[] { return this._wasm.searchEnchant(query); }
+    searchPreset(query: string): SearchHit[] { return this._wasm.searchPreset(query); }
+    searchOrigin(query: string): SearchHit[] { return this._wasm.searchOrigin(query); }
+    searchResonance(query: string): SearchHit[] { return this._wasm.searchResonance(query); }
+    searchObjective(query: string): SearchHit[] { return this._wasm.searchObjective(query); }
+    /** `getTalent`, falling back to the closest `searchTalent` match if it's close enough. */
+    getTalentFuzzy(query: string): Talent | null { return this._wasm.getTalentFuzzy(query); }
+
     requirement(id: string): Requirement | null {
         const wasm = this._wasm.requirement(id);
         if (wasm == null) return null;
-        const req = new Requirement("()");
-        req._wasm = wasm;
-        return req;
+        return Requirement.wrap(wasm);
     }
 
     prereqGraph(): PrereqGraph {
         return new PrereqGraph(this._wasm.prereqGraph());
     }
 
+    /** Every qualified item id (`ns:key`) in the bundle, sorted. */
+    ids(): string[] { return this._wasm.ids(); }
+
+    /** A smaller bundle with only `ids` and everything they need as a prereq. */
+    subset(ids: string[]): DeepData {
+        return new DeepData(this._wasm.subset(ids));
+    }
+
+    /** Patch this bundle's items in place. Returns the ids that were replaced or added. */
+    overlay(patch: DeepData): string[] {
+        return this._wasm.overlay(patch._wasm);
+    }
+
+    /** The ids added, removed or changed going from this bundle to `other`, e.g. for a changelog. */
+    diff(other: DeepData): DataDiff { return this._wasm.diff(other._wasm); }
+
     talents(): Talent[] { return this._wasm.talents(); }
     mantras(): Mantra[] { return this._wasm.mantras(); }
     weapons(): Weapon[] { return this._wasm.weapons(); }
@@ -94,6 +129,15 @@ export class DeepData {
     resonances(): Resonance[] { return this._wasm.resonances(); }
     objectives(): Objective[] { return this._wasm.objectives(); }
 
+    /** Talents in `category`, ignoring case and sorted by name. Vaulted ones only with `includeVaulted`. */
+    talentsByCategory(category: string, includeVaulted = true): Talent[] { return this._wasm.talentsByCategory(category, includeVaulted); }
+    talentsByRarity(rarity: string, includeVaulted = true): Talent[] { return this._wasm.talentsByRarity(rarity, includeVaulted); }
+    weaponsByType(weaponType: string): Weapon[] { return this._wasm.weaponsByType(weaponType); }
+    mantrasByType(mantraType: string, includeVaulted = true): Mantra[] { return this._wasm.mantrasByType(mantraType, includeVaulted); }
+    outfitsByCategory(category: string): Outfit[] { return this._wasm.outfitsByCategory(category); }
+    /** Every talent, mantra and outfit category, sorted */
+    categories(): string[] { return this._wasm.categories(); }
+
     aggregateStats(snapshot: BuildSnapshot, scenario?: Scenario): BuildTotalStats {
         return this._wasm.aggregateStats(snapshot, scenario);
     }
@@ -126,6 +170,16 @@ export function nameToIdentifier(name: string): string {
     return w().nameToIdentifier(name);
 }
 
+/** The stat a short name (`STR`, `AGI`, `ICE`...) refers to, or null if it isn't one */
+export function statFromShortName(short: string): Stat | null {
+    return w().statFromShortName(short) ?? null;
+}
+
+/** The short name used for a stat in requirement strings, e.g. `Strength` -> `STR` */
+export function statShortName(stat: Stat): string {
+    return STAT_SHORT_NAMES[stat];
+}
+
 export class StatMap {
     /** @internal */
     _wasm: any;
@@ -134,20 +188,39 @@ export class StatMap {
         this._wasm = new (w().StatMap)(map);
     }
 
+    /** @internal */
+    static wrap(wasm: any): StatMap {
+        const map = new StatMap();
+        map._wasm = wasm;
+        return map;
+    }
+
     /* The total build cost, accounting for multi-attunement shenanigans */
     cost(): number { return this._wasm.cost(); }
     /* The points remaining available to invest */
     remaining(): number { return this._wasm.remaining(); }
     /* The level the character is at */
     level(maxLevel?: number): number { return this._wasm.level(maxLevel); }
+    /* How many attunements have points in them */
+    attunementCount(): number { return this._wasm.attunementCount(); }
+
+    /** Whether the map could be had in game, see `validate` */
+    isValid(): boolean { return this._wasm.isValid(); }
+    /** Throws listing every way the map can't be had in game: costing more than the points
+     * available, or a stat past the cap or negative */
+    validate(): void { this._wasm.validate(); }
+    /** Floors negative stats to 0 and caps every stat */
+    clampToCaps(): void { this._wasm.clampToCaps(); }
 
     get(stat: Stat): number { return this._wasm.get(stat); }
     set(stat: Stat, value: number) { this._wasm.set(stat, value); }
-    shrineOrder(racial: StatMap): StatMap {
-        const result = new StatMap();
-        result._wasm = this._wasm.shrineOrder(racial._wasm);
-        return result;
-    }
+    add(other: StatMap): StatMap { return StatMap.wrap(this._wasm.add(other._wasm)); }
+    /** What's still missing to reach this map from `other`, clamped at 0 per stat */
+    sub(other: StatMap): StatMap { return StatMap.wrap(this._wasm.sub(other._wasm)); }
+    /** Signed per stat differences, `this - other` */
+    diff(other: StatMap): StatMap { return StatMap.wrap(this._wasm.diff(other._wasm)); }
+    /** The stats the Shrine of Order leaves, treating this map as the pre-shrine stats */
+    shrineOrder(racial: StatMap): StatMap { return StatMap.wrap(this._wasm.shrineOrder(racial._wasm)); }
     toJSON(): Partial<Record<Stat, number>> { return this._wasm.toJSON(); }
 
     /** The implicit talents (attunement milestones for now) granted by this stat map */
@@ -162,12 +235,103 @@ export class Requirement {
         this._wasm = new (w().Requirement)(input);
     }
 
+    /** @internal */
+    static wrap(wasm: any): Requirement {
+        const req = new Requirement("()");
+        req._wasm = wasm;
+        return req;
+    }
+
     satisfiedBy(stats: StatMap): boolean { return this._wasm.satisfiedBy(stats._wasm); }
+    /** How close `stats` gets to meeting the requirement, from 0 to 1 (met), e.g. to rank near misses */
+    satisfaction(stats: StatMap): number { return this._wasm.satisfaction(stats._wasm); }
     isEmpty(): boolean { return this._wasm.isEmpty(); }
+    /** Number of non-empty clauses, 0 exactly when `isEmpty` */
+    len(): number { return this._wasm.len(); }
+    /** Number of non-empty atoms in each clause, in the order `clauses()` returns them */
+    clauseLens(): number[] { return Array.from(this._wasm.clauseLens()); }
+    /** A cheap stat map meeting the requirement, the cheapest side of each OR */
+    minStatmap(): StatMap { return StatMap.wrap(this._wasm.minStatmap()); }
+    /** The stats the requirement mentions, in game order */
     usedStats(): Stat[] { return this._wasm.usedStats(); }
+    usedStatsAlphabetical(): Stat[] { return this._wasm.usedStatsAlphabetical(); }
     name(): string | null { return this._wasm.name(); }
+    /** The shrine phase of a requirement that came from a reqfile, null otherwise */
+    timing(): Timing | null { return this._wasm.timing() ?? null; }
     prereqs(): string[][] { return this._wasm.prereqs(); }
     clauses(): Clause[] { return this._wasm.clauses(); }
     addToStatAtoms(val: number): void { this._wasm.addToStatAtoms(val); }
+    /** A copy with every atom made strict, or reducible when `strict` is false */
+    withReducability(strict: boolean): Requirement { return Requirement.wrap(this._wasm.withReducability(strict)); }
+    /** A copy with every stat atom lowered by `n`, leaving power gates (TTL) as they are */
+    relaxedBy(n: number): Requirement { return Requirement.wrap(this._wasm.relaxedBy(n)); }
     toString(): string { return this._wasm.toString(); }
 }
+
+export class Reqfile {
+    /** @internal */
+    _wasm: any;
+
+    constructor(content: string) {
+        this._wasm = new (w().Reqfile)(content);
+    }
+
+    /** @internal */
+    static wrap(wasm: any): Reqfile {
+        const rf = new Reqfile("");
+        rf._wasm = wasm;
+        return rf;
+    }
+
+    /** The required requirements, each tagged with its timing */
+    requirements(): Requirement[] { return this._wasm.requirements().map(Requirement.wrap); }
+    /** The required requirements as text, converted one at a time as they're iterated */
+    *iterRequirements(): Generator<string> {
+        const count: number = this._wasm.requirementCount();
+        for (let i = 0; i < count; i++) yield this._wasm.requirementAt(i);
+    }
+    requirementCount(): number { return this._wasm.requirementCount(); }
+    requirementAt(index: number): string | null { return this._wasm.requirementAt(index) ?? null; }
+    optionalGroupCount(): number { return this._wasm.optionalGroupCount(); }
+    optionalGroupAt(index: number): OptionalGroup | null { return this._wasm.optionalGroupAt(index); }
+    generate(): string { return this._wasm.generate(); }
+    /** A cheap stat map meeting every required requirement */
+    solve(): StatMap { return StatMap.wrap(this._wasm.solve()); }
+    /** The average requirement `satisfaction`, 1 when `stats` meets them all */
+    satisfaction(stats: StatMap): number { return this._wasm.satisfaction(stats._wasm); }
+    /** How high each stat may have to go, taking the worst OR branch every time */
+    statEnvelope(): StatMap { return StatMap.wrap(this._wasm.statEnvelope()); }
+}
+
+export class BuildConfig {
+    /** @internal */
+    _wasm: any;
+
+    constructor() {
+        this._wasm = new (w().BuildConfig)();
+    }
+
+    static fromJson(json: string): BuildConfig {
+        const config = new BuildConfig();
+        config._wasm = w().BuildConfig.fromJson(json);
+        return config;
+    }
+
+    toJson(): string { return this._wasm.toJson(); }
+
+    addTalent(name: string): this { this._wasm.addTalent(name); return this; }
+    addMantra(name: string): this { this._wasm.addMantra(name); return this; }
+    addWeapon(name: string): this { this._wasm.addWeapon(name); return this; }
+    /** Replaces the outfit, if one was set */
+    setOutfit(name: string): this { this._wasm.setOutfit(name); return this; }
+    setRace(name: string | null): this { this._wasm.setRace(name ?? undefined); return this; }
+    setDisableSomWeapons(disable: boolean): this { this._wasm.setDisableSomWeapons(disable); return this; }
+    setAllowWeaponsPreshrine(allow: boolean): this { this._wasm.setAllowWeaponsPreshrine(allow); return this; }
+    /** Lets vaulted talents and mantras be picked, `toReqfile` throws on them otherwise */
+    setAllowVaulted(allow: boolean): this { this._wasm.setAllowVaulted(allow); return this; }
+    /** Qualified ids (`ns:name`) of everything the build must obtain */
+    reqs(): string[] { return this._wasm.reqs(); }
+
+    /** Generates the build's reqfile. Throws if something in the config isn't in `data`. */
+    toReqfile(data: DeepData): Reqfile { return Reqfile.wrap(this._wasm.toReqfile(data._wasm)); }
+}
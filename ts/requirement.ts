@@ -1,7 +1,8 @@
 import type { Stat } from './generated.js';
 
 export type Reducability = "reducible" | "strict";
-export type ClauseType = "and" | "or";
+export type ClauseType = "and" | "or" | "not";
+export type Timing = "free" | "post";
 
 export interface Atom {
     reducability: Reducability;
@@ -1,13 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use deepwoken_rs::Stat;
-use deepwoken_rs::data::DeepData;
+use deepwoken_rs::data::{DeepData, Mantra, Outfit, Talent, Weapon};
 use deepwoken_rs::model::aggregate::{BuildParams, Scenario};
-use deepwoken_rs::model::req::Requirement;
+#[cfg(feature = "intern")]
+use deepwoken_rs::model::intern::{InternedRequirement, Interner};
+use deepwoken_rs::model::req::{Reducability, Requirement, Timing};
+use deepwoken_rs::model::reqfile::Reqfile;
 use deepwoken_rs::util::aggregate;
+use deepwoken_rs::util::algos::{self, BuildConfig};
 use deepwoken_rs::util::graph::PrereqGraph;
+use deepwoken_rs::util::name_to_identifier;
 use deepwoken_rs::util::statmap::StatMap;
-use deepwoken_rs::util::{algos, name_to_identifier};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen(js_name = "DeepData")]
@@ -21,6 +25,24 @@ fn to_js<T: serde::Serialize>(value: &T) -> Result<JsValue, JsError> {
         .map_err(|e| JsError::new(&e.to_string()))
 }
 
+/// A fuzzy search result, see `DeepData::search_talent`
+#[derive(serde::Serialize)]
+struct SearchHit<'a> {
+    name: &'a str,
+    score: f64,
+}
+
+fn search_hits<T>(hits: &[(&T, f64)], name: impl Fn(&T) -> &str) -> Result<JsValue, JsError> {
+    let hits: Vec<SearchHit> = hits
+        .iter()
+        .map(|(item, score)| SearchHit {
+            name: name(item),
+            score: *score,
+        })
+        .collect();
+    to_js(&hits)
+}
+
 #[wasm_bindgen(js_class = "DeepData")]
 impl JsDeepData {
     /// Fetch the latest data bundle from pocamind/data on GitHub
@@ -54,6 +76,19 @@ impl JsDeepData {
         Ok(JsDeepData { inner: data })
     }
 
+    /// Tag of the release the data was fetched from (e.g. `v1.2.3`), `undefined` for data
+    /// parsed from JSON
+    #[wasm_bindgen(getter)]
+    pub fn version(&self) -> Option<String> {
+        self.inner.version().map(str::to_string)
+    }
+
+    /// The JSON the data was parsed from, which may hold fields this version doesn't know
+    #[wasm_bindgen(js_name = "rawJson")]
+    pub fn raw_json(&self) -> String {
+        self.inner.raw_json().to_string()
+    }
+
     #[wasm_bindgen(js_name = "getTalent")]
     pub fn get_talent(&self, name: &str) -> Result<JsValue, JsError> {
         to_js(&self.inner.get_talent(name))
@@ -109,8 +144,97 @@ impl JsDeepData {
         to_js(&self.inner.get_objective(name))
     }
 
+    #[wasm_bindgen(js_name = "searchTalent")]
+    pub fn search_talent(&self, query: &str) -> Result<JsValue, JsError> {
+        search_hits(&self.inner.search_talent(query), |i| &i.name)
+    }
+
+    #[wasm_bindgen(js_name = "searchMantra")]
+    pub fn search_mantra(&self, query: &str) -> Result<JsValue, JsError> {
+        search_hits(&self.inner.search_mantra(query), |i| &i.name)
+    }
+
+    #[wasm_bindgen(js_name = "searchWeapon")]
+    pub fn search_weapon(&self, query: &str) -> Result<JsValue, JsError> {
+        search_hits(&self.inner.search_weapon(query), |i| &i.name)
+    }
+
+    #[wasm_bindgen(js_name = "searchOutfit")]
+    pub fn search_outfit(&self, query: &str) -> Result<JsValue, JsError> {
+        search_hits(&self.inner.search_outfit(query), |i| &i.name)
+    }
+
+    #[wasm_bindgen(js_name = "searchEquipment")]
+    pub fn search_equipment(&self, query: &str) -> Result<JsValue, JsError> {
+        search_hits(&self.inner.search_equipment(query), |i| &i.name)
+    }
+
+    #[wasm_bindgen(js_name = "searchAspect")]
+    pub fn search_aspect(&self, query: &str) -> Result<JsValue, JsError> {
+        search_hits(&self.inner.search_aspect(query), |i| &i.name)
+    }
+
+    #[wasm_bindgen(js_name = "searchEnchant")]
+    pub fn search_enchant(&self, query: &str) -> Result<JsValue, JsError> {
+        search_hits(&self.inner.search_enchant(query), |i| &i.name)
+    }
+
+    #[wasm_bindgen(js_name = "searchPreset")]
+    pub fn search_preset(&self, query: &str) -> Result<JsValue, JsError> {
+        search_hits(&self.inner.search_preset(query), |i| &i.name)
+    }
+
+    #[wasm_bindgen(js_name = "searchOrigin")]
+    pub fn search_origin(&self, query: &str) -> Result<JsValue, JsError> {
+        search_hits(&self.inner.search_origin(query), |i| &i.name)
+    }
+
+    #[wasm_bindgen(js_name = "searchResonance")]
+    pub fn search_resonance(&self, query: &str) -> Result<JsValue, JsError> {
+        search_hits(&self.inner.search_resonance(query), |i| &i.name)
+    }
+
+    #[wasm_bindgen(js_name = "searchObjective")]
+    pub fn search_objective(&self, query: &str) -> Result<JsValue, JsError> {
+        search_hits(&self.inner.search_objective(query), |i| &i.name)
+    }
+
+    /// `getTalent`, falling back to the closest fuzzy match if it's close enough
+    #[wasm_bindgen(js_name = "getTalentFuzzy")]
+    pub fn get_talent_fuzzy(&self, query: &str) -> Result<JsValue, JsError> {
+        to_js(&self.inner.get_talent_fuzzy(query))
+    }
+
     pub fn requirement(&self, id: &str) -> Option<JsRequirement> {
-        self.inner.requirement(id).map(|inner| JsRequirement { inner })
+        self.inner.requirement(id).map(|inner| JsRequirement { inner, timing: None })
+    }
+
+    /// Every qualified item id in the bundle, sorted
+    pub fn ids(&self) -> Vec<String> {
+        self.inner.ids()
+    }
+
+    /// A smaller bundle with only the given ids and their prereqs. Call `ids()` on the result
+    /// for what was included.
+    pub fn subset(&self, ids: Vec<String>) -> Result<JsDeepData, JsError> {
+        let data = self
+            .inner
+            .subset(ids.iter().map(String::as_str))
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(JsDeepData { inner: data })
+    }
+
+    /// Patch this bundle's items in place, returning the ids that were replaced or added
+    pub fn overlay(&mut self, patch: &JsDeepData) -> Result<Vec<String>, JsError> {
+        self.inner
+            .overlay(&patch.inner)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// The ids added, removed or changed going from this bundle to `other`, as
+    /// `{ added, removed, changed }`
+    pub fn diff(&self, other: &JsDeepData) -> Result<JsValue, JsError> {
+        to_js(&self.inner.diff(&other.inner))
     }
 
     #[wasm_bindgen(js_name = "prereqGraph")]
@@ -164,6 +288,53 @@ impl JsDeepData {
         to_js(&self.inner.presets().collect::<Vec<_>>())
     }
 
+    /// Talents in `category`, ignoring case and sorted by name
+    #[wasm_bindgen(js_name = "talentsByCategory")]
+    pub fn talents_by_category(
+        &self,
+        category: &str,
+        include_vaulted: bool,
+    ) -> Result<JsValue, JsError> {
+        to_js(&self.inner.talents_by_category(category, include_vaulted))
+    }
+
+    #[wasm_bindgen(js_name = "talentsByRarity")]
+    pub fn talents_by_rarity(
+        &self,
+        rarity: &str,
+        include_vaulted: bool,
+    ) -> Result<JsValue, JsError> {
+        to_js(&self.inner.talents_by_rarity(rarity, include_vaulted))
+    }
+
+    #[wasm_bindgen(js_name = "weaponsByType")]
+    pub fn weapons_by_type(&self, weapon_type: &str) -> Result<JsValue, JsError> {
+        to_js(&self.inner.weapons_by_type(weapon_type))
+    }
+
+    #[wasm_bindgen(js_name = "mantrasByType")]
+    pub fn mantras_by_type(
+        &self,
+        mantra_type: &str,
+        include_vaulted: bool,
+    ) -> Result<JsValue, JsError> {
+        to_js(&self.inner.mantras_by_type(mantra_type, include_vaulted))
+    }
+
+    #[wasm_bindgen(js_name = "outfitsByCategory")]
+    pub fn outfits_by_category(&self, category: &str) -> Result<JsValue, JsError> {
+        to_js(&self.inner.outfits_by_category(category))
+    }
+
+    /// Every talent, mantra and outfit category, sorted
+    pub fn categories(&self) -> Vec<String> {
+        self.inner
+            .categories()
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
     #[wasm_bindgen(js_name = "aggregateStats")]
     pub fn aggregate_stats(
         &self,
@@ -188,6 +359,56 @@ impl JsDeepData {
     }
 }
 
+#[cfg(feature = "intern")]
+#[wasm_bindgen(js_class = "DeepData")]
+impl JsDeepData {
+    /// Every item's requirement by qualified id, with the names and clause sets they share
+    /// held once, for keeping the whole bundle's requirements in memory
+    #[wasm_bindgen(js_name = "internRequirements")]
+    pub fn intern_requirements(&self) -> JsRequirementIndex {
+        let mut interner = Interner::new();
+        let reqs = self.inner.intern_requirements(&mut interner);
+        JsRequirementIndex {
+            reqs,
+            clause_sets: interner.clause_set_count(),
+        }
+    }
+}
+
+/// Requirements by qualified id, see `DeepData.internRequirements`
+#[cfg(feature = "intern")]
+#[wasm_bindgen(js_name = "RequirementIndex")]
+pub struct JsRequirementIndex {
+    reqs: HashMap<String, InternedRequirement>,
+    clause_sets: usize,
+}
+
+#[cfg(feature = "intern")]
+#[wasm_bindgen(js_class = "RequirementIndex")]
+impl JsRequirementIndex {
+    pub fn get(&self, id: &str) -> Option<JsRequirement> {
+        self.reqs.get(id).map(|req| JsRequirement {
+            inner: Requirement::from(req),
+            timing: None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.reqs.len()
+    }
+
+    #[wasm_bindgen(js_name = "isEmpty")]
+    pub fn is_empty(&self) -> bool {
+        self.reqs.is_empty()
+    }
+
+    /// How many distinct clause sets the requirements share between them
+    #[wasm_bindgen(js_name = "clauseSetCount")]
+    pub fn clause_set_count(&self) -> usize {
+        self.clause_sets
+    }
+}
+
 #[wasm_bindgen(js_name = "PrereqGraph")]
 pub struct JsPrereqGraph {
     inner: PrereqGraph,
@@ -261,15 +482,21 @@ impl JsStatMap {
         self.inner.level(max_level) as i32
     }
 
+    /// How many attunements have points in them
+    #[wasm_bindgen(js_name = "attunementCount")]
+    pub fn attunement_count(&self) -> i32 {
+        self.inner.attunement_count() as i32
+    }
+
     pub fn get(&self, stat: &str) -> Result<i32, JsError> {
         let stat: Stat = stat.parse().map_err(|e: &str| JsError::new(e))?;
         Ok(self.inner.get(&stat) as i32)
     }
 
     pub fn set(&mut self, stat: &str, value: i32) -> Result<(), JsError> {
-        let stat: Stat = stat.parse().map_err(|e: &str| JsError::new(e))?;
-        self.inner.insert(stat, value as i64);
-        Ok(())
+        self.inner
+            .try_set(stat, value as i64)
+            .map_err(|e| JsError::new(&e.to_string()))
     }
 
     #[wasm_bindgen(js_name = "toJSON")]
@@ -277,6 +504,48 @@ impl JsStatMap {
         to_js(&self.inner)
     }
 
+    /// Per stat sums
+    pub fn add(&self, other: &JsStatMap) -> JsStatMap {
+        JsStatMap {
+            inner: &self.inner + &other.inner,
+        }
+    }
+
+    /// Per stat differences clamped at 0, what's still missing to reach `this` from `other`
+    pub fn sub(&self, other: &JsStatMap) -> JsStatMap {
+        JsStatMap {
+            inner: &self.inner - &other.inner,
+        }
+    }
+
+    /// Whether the map could be had in game, see `validate`
+    #[wasm_bindgen(js_name = "isValid")]
+    pub fn is_valid(&self) -> bool {
+        self.inner.is_valid()
+    }
+
+    /// Throws listing every way the map can't be had in game: costing more than the points
+    /// available, or a stat past the cap or negative
+    pub fn validate(&self) -> Result<(), JsError> {
+        self.inner
+            .validate()
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Floors negative stats to 0 and caps every stat
+    #[wasm_bindgen(js_name = "clampToCaps")]
+    pub fn clamp_to_caps(&mut self) {
+        self.inner.clamp_to_caps();
+    }
+
+    /// Signed per stat differences, `this - other`
+    pub fn diff(&self, other: &JsStatMap) -> JsStatMap {
+        JsStatMap {
+            inner: self.inner.diff(&other.inner),
+        }
+    }
+
+    /// The stats the Shrine of Order leaves, treating this map as the pre-shrine stats
     #[wasm_bindgen(js_name = "shrineOrder")]
     pub fn shrine_order(&self, racial: &JsStatMap) -> JsStatMap {
         JsStatMap {
@@ -304,9 +573,19 @@ pub fn js_name_to_identifier(name: &str) -> String {
     name_to_identifier(name)
 }
 
+/// The full stat name for a short name like `STR`, `None` if it isn't one
+#[wasm_bindgen(js_name = "statFromShortName")]
+pub fn stat_from_short_name(short: &str) -> Option<String> {
+    Stat::from_short_name(short)
+        .filter(|s| *s != Stat::Total)
+        .map(|s| s.name().to_string())
+}
+
 #[wasm_bindgen(js_name = "Requirement")]
 pub struct JsRequirement {
     inner: Requirement,
+    /// Set when the requirement came out of a reqfile
+    timing: Option<Timing>,
 }
 
 #[wasm_bindgen(js_class = "Requirement")]
@@ -314,7 +593,10 @@ impl JsRequirement {
     #[wasm_bindgen(constructor)]
     pub fn new(input: &str) -> Result<JsRequirement, JsError> {
         let req = Requirement::parse(input).map_err(|e| JsError::new(&e.to_string()))?;
-        Ok(JsRequirement { inner: req })
+        Ok(JsRequirement {
+            inner: req,
+            timing: None,
+        })
     }
 
     #[wasm_bindgen(js_name = "satisfiedBy")]
@@ -322,19 +604,81 @@ impl JsRequirement {
         self.inner.satisfied_by(&stats.inner)
     }
 
+    /// How close `stats` gets to meeting the requirement, from 0 to 1 (met)
+    pub fn satisfaction(&self, stats: &JsStatMap) -> f64 {
+        self.inner.satisfaction(&stats.inner)
+    }
+
     #[wasm_bindgen(js_name = "isEmpty")]
     pub fn is_empty(&self) -> bool {
         self.inner.is_empty()
     }
 
+    /// Number of non-empty clauses, 0 exactly when `isEmpty`
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Number of non-empty atoms in each clause, in the order `clauses` returns them
+    #[wasm_bindgen(js_name = "clauseLens")]
+    pub fn clause_lens(&self) -> Vec<usize> {
+        self.inner.clauses.iter().map(|c| c.len()).collect()
+    }
+
+    /// A cheap stat map meeting the requirement
+    #[wasm_bindgen(js_name = "minStatmap")]
+    pub fn min_statmap(&self) -> JsStatMap {
+        JsStatMap {
+            inner: self.inner.min_statmap(),
+        }
+    }
+
     #[wasm_bindgen(js_name = "addToStatAtoms")]
     pub fn add_to_stat_atoms(&mut self, val: i32) {
         self.inner.add_to_stat_atoms(i64::from(val));
     }
 
+    /// A copy with every atom made strict (or reducible)
+    #[wasm_bindgen(js_name = "withReducability")]
+    pub fn with_reducability(&self, strict: bool) -> JsRequirement {
+        let reducability = if strict {
+            Reducability::Strict
+        } else {
+            Reducability::Reducible
+        };
+        JsRequirement {
+            inner: self.inner.with_reducability(reducability),
+            timing: self.timing,
+        }
+    }
+
+    /// A copy with every stat atom lowered by `n`. Power gates (TTL) are left as they are.
+    #[wasm_bindgen(js_name = "relaxedBy")]
+    pub fn relaxed_by(&self, n: i32) -> JsRequirement {
+        let mut inner = self.inner.clone();
+        inner.add_to_stat_atoms(-i64::from(n));
+        JsRequirement {
+            inner,
+            timing: self.timing,
+        }
+    }
+
+    /// The stats the requirement mentions, in game order
     #[wasm_bindgen(js_name = "usedStats")]
     pub fn used_stats(&self) -> Result<JsValue, JsError> {
-        let stats: Vec<&str> = self.inner.used_stats().iter().map(Stat::name).collect();
+        let stats: Vec<&str> = self
+            .inner
+            .used_stats_sorted()
+            .iter()
+            .map(Stat::name)
+            .collect();
+        to_js(&stats)
+    }
+
+    #[wasm_bindgen(js_name = "usedStatsAlphabetical")]
+    pub fn used_stats_alphabetical(&self) -> Result<JsValue, JsError> {
+        let mut stats: Vec<&str> = self.inner.used_stats().iter().map(Stat::name).collect();
+        stats.sort_unstable();
         to_js(&stats)
     }
 
@@ -342,6 +686,11 @@ impl JsRequirement {
         self.inner.name.clone()
     }
 
+    /// "free" or "post" for requirements from a reqfile, the shrine phase they belong to
+    pub fn timing(&self) -> Option<String> {
+        self.timing.map(|t| t.name().to_string())
+    }
+
     pub fn prereqs(&self) -> Result<JsValue, JsError> {
         let groups: Vec<Vec<String>> = self
             .inner
@@ -361,3 +710,195 @@ impl JsRequirement {
         self.inner.to_string()
     }
 }
+
+#[wasm_bindgen(js_name = "Reqfile")]
+pub struct JsReqfile {
+    inner: Reqfile,
+}
+
+#[wasm_bindgen(js_class = "Reqfile")]
+impl JsReqfile {
+    #[wasm_bindgen(constructor)]
+    pub fn new(content: &str) -> Result<JsReqfile, JsError> {
+        let inner = Reqfile::parse_str(content).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(JsReqfile { inner })
+    }
+
+    /// The required requirements, each tagged with its timing
+    pub fn requirements(&self) -> Vec<JsRequirement> {
+        self.inner
+            .timed_req_iter()
+            .map(|(timing, req)| JsRequirement {
+                inner: req.clone(),
+                timing: Some(timing),
+            })
+            .collect()
+    }
+
+    /// How many required requirements there are, for walking them with `requirementAt`
+    #[wasm_bindgen(js_name = "requirementCount")]
+    pub fn requirement_count(&self) -> usize {
+        self.inner.requirement_count()
+    }
+
+    /// The `index`th required requirement, as text
+    #[wasm_bindgen(js_name = "requirementAt")]
+    pub fn requirement_at(&self, index: usize) -> Option<String> {
+        self.inner
+            .requirement_at(index)
+            .map(|(_, req)| req.to_string())
+    }
+
+    #[wasm_bindgen(js_name = "optionalGroupCount")]
+    pub fn optional_group_count(&self) -> usize {
+        self.inner.optional.len()
+    }
+
+    /// The `index`th optional group as `{ weight, free, post }`, its members as sorted text
+    #[wasm_bindgen(js_name = "optionalGroupAt")]
+    pub fn optional_group_at(&self, index: usize) -> Result<JsValue, JsError> {
+        #[derive(serde::Serialize)]
+        struct Group {
+            weight: i64,
+            free: Vec<String>,
+            post: Vec<String>,
+        }
+
+        let sorted = |reqs: &HashSet<Requirement>| {
+            let mut reqs: Vec<String> = reqs.iter().map(ToString::to_string).collect();
+            reqs.sort();
+            reqs
+        };
+
+        to_js(&self.inner.optional.get(index).map(|group| Group {
+            weight: group.weight,
+            free: sorted(&group.general),
+            post: sorted(&group.post),
+        }))
+    }
+
+    pub fn generate(&self) -> String {
+        self.inner.generate()
+    }
+
+    /// A cheap stat map meeting every required requirement
+    pub fn solve(&self) -> JsStatMap {
+        JsStatMap {
+            inner: self.inner.solve(),
+        }
+    }
+
+    /// The average requirement `satisfaction`, 1 when `stats` meets them all
+    pub fn satisfaction(&self, stats: &JsStatMap) -> f64 {
+        self.inner.satisfaction(&stats.inner)
+    }
+
+    /// How high each stat may have to go, taking the worst OR branch every time
+    #[wasm_bindgen(js_name = "statEnvelope")]
+    pub fn stat_envelope(&self) -> JsStatMap {
+        JsStatMap {
+            inner: self.inner.stat_envelope(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "BuildConfig")]
+pub struct JsBuildConfig {
+    inner: BuildConfig,
+}
+
+impl JsBuildConfig {
+    fn add(&mut self, namespace: &str, name: &str) {
+        self.inner
+            .reqs
+            .push(format!("{namespace}:{}", name_to_identifier(name)));
+    }
+}
+
+#[wasm_bindgen(js_class = "BuildConfig")]
+impl JsBuildConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsBuildConfig {
+        JsBuildConfig {
+            inner: BuildConfig::default(),
+        }
+    }
+
+    #[wasm_bindgen(js_name = "fromJson")]
+    pub fn from_json(json: &str) -> Result<JsBuildConfig, JsError> {
+        let inner = BuildConfig::from_json(json).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(JsBuildConfig { inner })
+    }
+
+    #[wasm_bindgen(js_name = "toJson")]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        self.inner
+            .to_json()
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = "addTalent")]
+    pub fn add_talent(&mut self, name: &str) {
+        self.add(Talent::NAMESPACE, name);
+    }
+
+    #[wasm_bindgen(js_name = "addMantra")]
+    pub fn add_mantra(&mut self, name: &str) {
+        self.add(Mantra::NAMESPACE, name);
+    }
+
+    #[wasm_bindgen(js_name = "addWeapon")]
+    pub fn add_weapon(&mut self, name: &str) {
+        self.add(Weapon::NAMESPACE, name);
+    }
+
+    /// Replaces the outfit, if one was set
+    #[wasm_bindgen(js_name = "setOutfit")]
+    pub fn set_outfit(&mut self, name: &str) {
+        let prefix = format!("{}:", Outfit::NAMESPACE);
+        self.inner.reqs.retain(|id| !id.starts_with(&prefix));
+        self.add(Outfit::NAMESPACE, name);
+    }
+
+    #[wasm_bindgen(js_name = "setRace")]
+    pub fn set_race(&mut self, name: Option<String>) {
+        self.inner.race = name.as_deref().map(name_to_identifier);
+    }
+
+    #[wasm_bindgen(js_name = "setDisableSomWeapons")]
+    pub fn set_disable_som_weapons(&mut self, disable: bool) {
+        self.inner.disable_som_weapons = disable;
+    }
+
+    #[wasm_bindgen(js_name = "setAllowWeaponsPreshrine")]
+    pub fn set_allow_weapons_preshrine(&mut self, allow: bool) {
+        self.inner.allow_weapons_preshrine = allow;
+    }
+
+    /// Lets vaulted talents and mantras be picked, `toReqfile` throws on them otherwise
+    #[wasm_bindgen(js_name = "setAllowVaulted")]
+    pub fn set_allow_vaulted(&mut self, allow: bool) {
+        self.inner.allow_vaulted = allow;
+    }
+
+    /// Qualified ids (`ns:name`) of everything the build must obtain
+    pub fn reqs(&self) -> Vec<String> {
+        self.inner.reqs.clone()
+    }
+
+    /// Generates the build's reqfile. Throws if something in the config isn't in `data`.
+    #[wasm_bindgen(js_name = "toReqfile")]
+    pub fn to_reqfile(&self, data: &JsDeepData) -> Result<JsReqfile, JsError> {
+        let inner = self
+            .inner
+            .to_reqfile(&data.inner)
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(JsReqfile { inner })
+    }
+}
+
+impl Default for JsBuildConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
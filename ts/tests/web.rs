@@ -0,0 +1,163 @@
+//! Tests for the bindings themselves, checked against the Rust API they wrap.
+//! Run with `wasm-pack test --node`.
+
+use std::collections::HashMap;
+
+use deepwoken::{JsDeepData, JsReqfile, JsRequirement, JsStatMap};
+use deepwoken_rs::Stat;
+use deepwoken_rs::data::DeepData;
+use deepwoken_rs::model::req::{Clause, Requirement};
+use deepwoken_rs::model::reqfile::Reqfile;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const REQFILE: &str = "
+    Free:
+    25 STR
+    1; opt_a := 20 INT
+    2; opt_b := 30 FTD
+
+    Post:
+    40 FLM
+    3; post_opt := 50 WLL
+";
+
+#[derive(serde::Deserialize, Debug, PartialEq)]
+struct Group {
+    weight: i64,
+    free: Vec<String>,
+    post: Vec<String>,
+}
+
+#[wasm_bindgen_test]
+fn reqfile_iterates_by_index() {
+    let js = JsReqfile::new(REQFILE).unwrap();
+    let rf = Reqfile::parse_str(REQFILE).unwrap();
+
+    assert_eq!(js.requirement_count(), rf.requirement_count());
+    let indexed: Vec<String> = (0..js.requirement_count())
+        .map(|i| js.requirement_at(i).unwrap())
+        .collect();
+    let iterated: Vec<String> = rf.req_iter().map(ToString::to_string).collect();
+    assert_eq!(indexed, iterated);
+    assert!(js.requirement_at(js.requirement_count()).is_none());
+
+    assert_eq!(js.optional_group_count(), rf.optional.len());
+    for (i, group) in rf.optional.iter().enumerate() {
+        let got: Option<Group> =
+            serde_wasm_bindgen::from_value(js.optional_group_at(i).unwrap()).unwrap();
+        let got = got.unwrap();
+        assert_eq!(got.weight, group.weight);
+        assert_eq!(got.free.len(), group.general.len());
+        assert_eq!(got.post.len(), group.post.len());
+    }
+    let past_end: Option<Group> =
+        serde_wasm_bindgen::from_value(js.optional_group_at(rf.optional.len()).unwrap()).unwrap();
+    assert_eq!(past_end, None);
+}
+
+#[wasm_bindgen_test]
+fn subset_and_overlay_match_rust() {
+    let json = include_str!("../../rust/assets/fixture.json");
+    let js = JsDeepData::from_json(json).unwrap();
+    let data = DeepData::from_json(json).unwrap();
+
+    let ids = ["talent:a_world_without_song".to_string()];
+    let subset = js.subset(ids.to_vec()).unwrap();
+    assert_eq!(
+        subset.ids(),
+        data.subset(ids.iter().map(String::as_str)).unwrap().ids()
+    );
+
+    let mut base = JsDeepData::from_json(json).unwrap();
+    let patch = JsDeepData::from_json(
+        r#"{
+            "talents": {
+                "flamecharmer": {
+                    "name": "Flamecharmer",
+                    "desc": "patched",
+                    "rarity": "Common",
+                    "category": "Flamecharmer",
+                    "reqs": "1s FLM",
+                    "count_towards_talent_total": false,
+                    "vaulted": false,
+                    "voi": false
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(base.overlay(&patch).unwrap(), ["talent:flamecharmer"]);
+    assert_eq!(base.ids(), data.ids());
+}
+
+#[wasm_bindgen_test]
+fn lengths_match_rust() {
+    let text = "40 STR AND 40 AGL, 25 CHA OR 25 WLL, 90 TTL";
+    let js = JsRequirement::new(text).unwrap();
+    let req = Requirement::parse(text).unwrap();
+
+    assert_eq!(js.len(), req.len());
+    assert_eq!(
+        js.clause_lens(),
+        req.clauses.iter().map(Clause::len).collect::<Vec<_>>()
+    );
+    assert_eq!(js.clause_lens().iter().sum::<usize>(), 5);
+
+    let empty = JsRequirement::new("()").unwrap();
+    assert_eq!(empty.len(), 0);
+    assert!(empty.is_empty());
+}
+
+fn stat_map(stats: &[(Stat, i64)]) -> JsStatMap {
+    let map: HashMap<Stat, i64> = stats.iter().copied().collect();
+    JsStatMap::new(serde_wasm_bindgen::to_value(&map).unwrap()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn satisfaction_scores_progress() {
+    let req = JsRequirement::new("40 STR, 20 AGL OR 20 FTD").unwrap();
+    let full = stat_map(&[(Stat::Strength, 40), (Stat::Fortitude, 30)]);
+    assert!((req.satisfaction(&full) - 1.0).abs() < f64::EPSILON);
+
+    let half = stat_map(&[(Stat::Strength, 20), (Stat::Agility, 10)]);
+    assert!((req.satisfaction(&half) - 0.5).abs() < f64::EPSILON);
+
+    let rf = JsReqfile::new("Free:\n40 STR\n20 AGL").unwrap();
+    assert!(
+        (rf.satisfaction(&stat_map(&[(Stat::Strength, 40), (Stat::Agility, 20)])) - 1.0).abs()
+            < f64::EPSILON
+    );
+    assert!((rf.satisfaction(&stat_map(&[(Stat::Strength, 40)])) - 0.5).abs() < f64::EPSILON);
+}
+
+#[wasm_bindgen_test]
+fn relaxed_by_leaves_power_gates() {
+    let req = JsRequirement::new("abyss_wanderers_boots := 165r TTL, 30r STR").unwrap();
+    assert_eq!(
+        req.relaxed_by(5).to_string_js(),
+        "abyss_wanderers_boots := 25r STR, 165r TTL"
+    );
+    assert_eq!(
+        req.relaxed_by(40).to_string_js(),
+        "abyss_wanderers_boots := 0r STR, 165r TTL"
+    );
+}
+
+#[cfg(feature = "intern")]
+#[wasm_bindgen_test]
+fn interned_requirements_match_rust() {
+    let json = include_str!("../../rust/assets/fixture.json");
+    let js = JsDeepData::from_json(json).unwrap();
+    let data = DeepData::from_json(json).unwrap();
+
+    let index = js.intern_requirements();
+    assert_eq!(index.len(), data.ids().len());
+    assert!(index.clause_set_count() <= index.len());
+    for id in data.ids() {
+        assert_eq!(
+            index.get(&id).unwrap().to_string_js(),
+            data.requirement(&id).unwrap().to_string()
+        );
+    }
+    assert!(index.get("talent:missing").is_none());
+}
@@ -1,4 +1,4 @@
-export { ATTUNEMENT_STATS, CORE_STATS, DAMAGE_TYPES, EQUIPMENT_SLOTS, ITEM_RARITIES, TALENT_RARITIES, WEAPON_STATS, WEAPON_TYPES } from './generated.js';
+export { ALL_STATS, ATTUNEMENT_STATS, CORE_STATS, DAMAGE_TYPES, EQUIPMENT_SLOTS, ITEM_RARITIES, STAT_SHORT_NAMES, TALENT_RARITIES, WEAPON_STATS, WEAPON_TYPES } from './generated.js';
 export type { DamageType, EquipmentSlot, ItemRarity, MantraType, RangeType, Stat, TalentRarity, WeaponType } from './generated.js';
 
 import type { EquipmentSlot, ItemRarity, MantraType, RangeType, Stat, TalentRarity, WeaponType } from './generated.js';
@@ -14,16 +14,23 @@ export interface Talent {
     rarity: TalentRarity;
     category: string;
     reqs: string;
+    prereqs?: string[];
+    /** False for talents that don't take up a slot towards the talent total */
     count_towards_talent_total: boolean;
     vaulted: boolean;
     voi: boolean;
     voi_only: boolean;
     implicit?: boolean;
+    /** Qualified ids of the talents this one can't be taken alongside */
     exclusive?: string[];
-    stats?: Record<string, number>;
-    additional_info?: string;
-    icon?: string;
-    roll2able?: boolean;
+    /** The talent's innate stats, see `StatFormula` */
+    stats?: Record<string, StatFormula>;
+    conditional_stats?: Record<string, StatFormula>;
+    multiplicative_percents?: Record<string, StatFormula>;
+    conditional_multiplicative_percents?: Record<string, StatFormula>;
+    additional_info?: string | null;
+    icon?: string | null;
+    roll2able?: boolean | null;
 }
 
 export interface Weapon {
@@ -241,3 +248,26 @@ export interface Objective {
     reqs: string;
     prereqs: string[];
 }
+
+/** A fuzzy lookup result, from the `DeepData.search*` methods. */
+export interface SearchHit {
+    name: string;
+    /** 0 to 1, where 1 is an exact match */
+    score: number;
+}
+
+/** An optional group of a reqfile, from `Reqfile.optionalGroupAt`. */
+export interface OptionalGroup {
+    weight: number;
+    /** Members taken before the shrine, as requirement text */
+    free: string[];
+    /** Members taken after the shrine */
+    post: string[];
+}
+
+/** What changed between two bundles, from `DeepData.diff`. Entries are sorted qualified ids. */
+export interface DataDiff {
+    added: string[];
+    removed: string[];
+    changed: string[];
+}